@@ -1,12 +1,15 @@
 use node_template_runtime::{
-	AccountId, AuraConfig, BalancesConfig, GrandpaConfig, RuntimeGenesisConfig, Signature,
-	SudoConfig, SystemConfig, WASM_BINARY,
+	AccountId, AssetsConfig, AuraConfig, Balance, BalancesConfig, DexAssetKind, DexConfig, GrandpaConfig,
+	RuntimeGenesisConfig, Signature, SudoConfig, SystemConfig, WASM_BINARY,
 };
 use sc_service::ChainType;
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 use sp_consensus_grandpa::AuthorityId as GrandpaId;
 use sp_core::{sr25519, Pair, Public};
-use sp_runtime::traits::{IdentifyAccount, Verify};
+use sp_runtime::{
+	traits::{IdentifyAccount, Verify},
+	Permill,
+};
 
 // The URL for the telemetry server.
 // const STAGING_TELEMETRY_URL: &str = "wss://telemetry.polkadot.io/submit/";
@@ -124,6 +127,24 @@ pub fn local_testnet_config() -> Result<ChainSpec, String> {
 	))
 }
 
+// The assets every test chain starts with: id, name, symbol and decimals
+const DEMO_ASSETS: [(u32, &str, &str, u8); 3] =
+	[(1, "Demo Dollar", "DUSD", 12), (2, "Demo Euro", "DEUR", 12), (3, "Demo Bitcoin", "DBTC", 12)];
+
+// Every endowed account holds this amount of every demo asset
+const DEMO_ASSET_BALANCE: Balance = 1 << 50;
+
+// The pools every test chain starts with, all in the default 5% fee tier and funded by the sudo account
+fn demo_pools(lp_holder: &AccountId) -> Vec<(DexAssetKind, DexAssetKind, Permill, Balance, Balance, AccountId)> {
+	let asset = DexAssetKind::AssetBalance;
+	let fee = Permill::from_percent(5);
+	vec![
+		(DexAssetKind::NativeBalance, asset(1), fee, 1 << 40, 1 << 40, lp_holder.clone()),
+		(asset(1), asset(2), fee, 1 << 40, 1 << 40, lp_holder.clone()),
+		(asset(1), asset(3), fee, 1 << 44, 1 << 30, lp_holder.clone()),
+	]
+}
+
 /// Configure initial storage state for FRAME modules.
 fn testnet_genesis(
 	wasm_binary: &[u8],
@@ -151,9 +172,21 @@ fn testnet_genesis(
 		},
 		sudo: SudoConfig {
 			// Assign network admin rights.
-			key: Some(root_key),
+			key: Some(root_key.clone()),
 		},
 		transaction_payment: Default::default(),
-		assets: Default::default(),
+		assets: AssetsConfig {
+			// The demo assets are sufficient, so any account can hold them
+			assets: DEMO_ASSETS.iter().map(|(id, ..)| (*id, root_key.clone(), true, 1)).collect(),
+			metadata: DEMO_ASSETS
+				.iter()
+				.map(|(id, name, symbol, decimals)| (*id, name.as_bytes().to_vec(), symbol.as_bytes().to_vec(), *decimals))
+				.collect(),
+			accounts: DEMO_ASSETS
+				.iter()
+				.flat_map(|(id, ..)| endowed_accounts.iter().map(move |k| (*id, k.clone(), DEMO_ASSET_BALANCE)))
+				.collect(),
+		},
+		dex: DexConfig { fee_tiers: Vec::new(), pools: demo_pools(&root_key) },
 	}
 }
@@ -0,0 +1,241 @@
+//! JSON-RPC interface for the dex pallet.
+//!
+//! Exposes the `DexApi` runtime API as `dex_*` methods, so clients can quote swaps and read the
+//! state of the pools without reimplementing the pool math.
+//!
+//! Balances are passed as `NumberOrHex` like in `pallet_transaction_payment_rpc`, so amounts beyond
+//! the safe integers of JavaScript can be sent as hex strings.
+
+use std::sync::Arc;
+
+use codec::Codec;
+use jsonrpsee::{
+	core::{async_trait, RpcResult},
+	proc_macros::rpc,
+	types::error::{CallError, ErrorObject},
+};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_rpc::number::NumberOrHex;
+use sp_runtime::traits::Block as BlockT;
+
+pub use pallet_dex_runtime_api::DexApi as DexRuntimeApi;
+
+#[rpc(client, server)]
+pub trait DexApi<BlockHash, AssetKind, FeeTier, AssetId> {
+	/// The amount of `asset_out` received for swapping exactly `amount_in` of `asset_in`.
+	#[method(name = "dex_quoteExactIn")]
+	fn quote_exact_in(
+		&self,
+		asset_in: AssetKind,
+		asset_out: AssetKind,
+		fee: FeeTier,
+		amount_in: NumberOrHex,
+		at: Option<BlockHash>,
+	) -> RpcResult<Option<NumberOrHex>>;
+
+	/// The amount of `asset_in` needed to receive exactly `amount_out` of `asset_out`.
+	#[method(name = "dex_quoteExactOut")]
+	fn quote_exact_out(
+		&self,
+		asset_in: AssetKind,
+		asset_out: AssetKind,
+		fee: FeeTier,
+		amount_out: NumberOrHex,
+		at: Option<BlockHash>,
+	) -> RpcResult<Option<NumberOrHex>>;
+
+	/// `amount_in` of `asset_in` converted to `asset_out` at the spot price of the pool.
+	#[method(name = "dex_getPrice")]
+	fn get_price(
+		&self,
+		asset_in: AssetKind,
+		asset_out: AssetKind,
+		fee: FeeTier,
+		amount_in: NumberOrHex,
+		at: Option<BlockHash>,
+	) -> RpcResult<Option<NumberOrHex>>;
+
+	/// The reserves of the pool of `asset_a` and `asset_b` with the fee tier, in the order the assets are given.
+	#[method(name = "dex_poolReserves")]
+	fn pool_reserves(
+		&self,
+		asset_a: AssetKind,
+		asset_b: AssetKind,
+		fee: FeeTier,
+		at: Option<BlockHash>,
+	) -> RpcResult<Option<(NumberOrHex, NumberOrHex)>>;
+
+	/// The id of the LP token of the pool of `asset_a` and `asset_b` with the fee tier.
+	#[method(name = "dex_lpTokenId")]
+	fn lp_token_id(
+		&self,
+		asset_a: AssetKind,
+		asset_b: AssetKind,
+		fee: FeeTier,
+		at: Option<BlockHash>,
+	) -> RpcResult<Option<AssetId>>;
+
+	/// The assets, the fee tier and the LP token id of every pool.
+	#[method(name = "dex_listPools")]
+	fn list_pools(&self, at: Option<BlockHash>) -> RpcResult<Vec<(AssetKind, AssetKind, FeeTier, AssetId)>>;
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+	/// The call to runtime failed.
+	RuntimeError,
+	/// A balance doesn't fit in the balance type of the runtime, or the other way around.
+	InvalidBalance,
+}
+
+impl From<Error> for i32 {
+	fn from(e: Error) -> i32 {
+		match e {
+			Error::RuntimeError => 1,
+			Error::InvalidBalance => 2,
+		}
+	}
+}
+
+/// Provides RPC methods to query the dex pallet, for a runtime with the given balance type.
+pub struct Dex<C, Block, Balance> {
+	client: Arc<C>,
+	_marker: std::marker::PhantomData<(Block, Balance)>,
+}
+
+impl<C, Block, Balance> Dex<C, Block, Balance> {
+	/// Creates a new instance of the dex RPC handler.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, _marker: Default::default() }
+	}
+}
+
+fn runtime_error_into_rpc_err(err: impl std::fmt::Display) -> jsonrpsee::core::Error {
+	CallError::Custom(ErrorObject::owned(
+		Error::RuntimeError.into(),
+		"Unable to query the dex pallet",
+		Some(err.to_string()),
+	))
+	.into()
+}
+
+fn invalid_balance_err(message: String) -> jsonrpsee::core::Error {
+	CallError::Custom(ErrorObject::owned(Error::InvalidBalance.into(), message, None::<()>)).into()
+}
+
+fn balance_from_rpc<Balance: TryFrom<NumberOrHex>>(value: NumberOrHex) -> RpcResult<Balance> {
+	Balance::try_from(value)
+		.map_err(|_| invalid_balance_err(format!("{:?} doesn't fit in the balance type of the runtime", value)))
+}
+
+fn balance_into_rpc<Balance>(value: Balance) -> RpcResult<NumberOrHex>
+where
+	Balance: TryInto<NumberOrHex> + Copy + std::fmt::Display,
+{
+	value
+		.try_into()
+		.map_err(|_| invalid_balance_err(format!("{} doesn't fit in NumberOrHex representation", value)))
+}
+
+#[async_trait]
+impl<C, Block, AssetKind, FeeTier, AssetId, Balance>
+	DexApiServer<<Block as BlockT>::Hash, AssetKind, FeeTier, AssetId> for Dex<C, Block, Balance>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: DexRuntimeApi<Block, AssetKind, FeeTier, AssetId, Balance>,
+	AssetKind: Codec,
+	FeeTier: Codec,
+	AssetId: Codec,
+	Balance: Codec + Copy + std::fmt::Display + TryFrom<NumberOrHex> + TryInto<NumberOrHex> + Send + Sync + 'static,
+{
+	fn quote_exact_in(
+		&self,
+		asset_in: AssetKind,
+		asset_out: AssetKind,
+		fee: FeeTier,
+		amount_in: NumberOrHex,
+		at: Option<Block::Hash>,
+	) -> RpcResult<Option<NumberOrHex>> {
+		let at_hash = at.unwrap_or_else(|| self.client.info().best_hash);
+		let amount_in = balance_from_rpc(amount_in)?;
+		self.client
+			.runtime_api()
+			.quote_exact_in(at_hash, asset_in, asset_out, fee, amount_in)
+			.map_err(runtime_error_into_rpc_err)?
+			.map(balance_into_rpc)
+			.transpose()
+	}
+
+	fn quote_exact_out(
+		&self,
+		asset_in: AssetKind,
+		asset_out: AssetKind,
+		fee: FeeTier,
+		amount_out: NumberOrHex,
+		at: Option<Block::Hash>,
+	) -> RpcResult<Option<NumberOrHex>> {
+		let at_hash = at.unwrap_or_else(|| self.client.info().best_hash);
+		let amount_out = balance_from_rpc(amount_out)?;
+		self.client
+			.runtime_api()
+			.quote_exact_out(at_hash, asset_in, asset_out, fee, amount_out)
+			.map_err(runtime_error_into_rpc_err)?
+			.map(balance_into_rpc)
+			.transpose()
+	}
+
+	fn get_price(
+		&self,
+		asset_in: AssetKind,
+		asset_out: AssetKind,
+		fee: FeeTier,
+		amount_in: NumberOrHex,
+		at: Option<Block::Hash>,
+	) -> RpcResult<Option<NumberOrHex>> {
+		let at_hash = at.unwrap_or_else(|| self.client.info().best_hash);
+		let amount_in = balance_from_rpc(amount_in)?;
+		self.client
+			.runtime_api()
+			.get_price(at_hash, asset_in, asset_out, fee, amount_in)
+			.map_err(runtime_error_into_rpc_err)?
+			.map(balance_into_rpc)
+			.transpose()
+	}
+
+	fn pool_reserves(
+		&self,
+		asset_a: AssetKind,
+		asset_b: AssetKind,
+		fee: FeeTier,
+		at: Option<Block::Hash>,
+	) -> RpcResult<Option<(NumberOrHex, NumberOrHex)>> {
+		let at_hash = at.unwrap_or_else(|| self.client.info().best_hash);
+		self.client
+			.runtime_api()
+			.pool_reserves(at_hash, asset_a, asset_b, fee)
+			.map_err(runtime_error_into_rpc_err)?
+			.map(|(reserve_a, reserve_b)| Ok((balance_into_rpc(reserve_a)?, balance_into_rpc(reserve_b)?)))
+			.transpose()
+	}
+
+	fn lp_token_id(
+		&self,
+		asset_a: AssetKind,
+		asset_b: AssetKind,
+		fee: FeeTier,
+		at: Option<Block::Hash>,
+	) -> RpcResult<Option<AssetId>> {
+		let at_hash = at.unwrap_or_else(|| self.client.info().best_hash);
+		self.client
+			.runtime_api()
+			.lp_token_id(at_hash, asset_a, asset_b, fee)
+			.map_err(runtime_error_into_rpc_err)
+	}
+
+	fn list_pools(&self, at: Option<Block::Hash>) -> RpcResult<Vec<(AssetKind, AssetKind, FeeTier, AssetId)>> {
+		let at_hash = at.unwrap_or_else(|| self.client.info().best_hash);
+		self.client.runtime_api().list_pools(at_hash).map_err(runtime_error_into_rpc_err)
+	}
+}
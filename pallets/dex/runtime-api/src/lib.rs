@@ -0,0 +1,43 @@
+//! Runtime API definition for the dex pallet.
+//!
+//! It lets nodes quote swaps and read the state of the pools with the exact same math the pallet
+//! uses, so front ends don't have to reimplement it.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use sp_std::vec::Vec;
+
+sp_api::decl_runtime_apis! {
+	/// Quotes and pool state of the dex pallet.
+	///
+	/// `AssetKind` is the asset type of the pools, `FeeTier` the fee tier of a pool and `AssetId` the id
+	/// of the LP tokens.
+	pub trait DexApi<AssetKind, FeeTier, AssetId, Balance> where
+		AssetKind: Codec,
+		FeeTier: Codec,
+		AssetId: Codec,
+		Balance: Codec,
+	{
+		/// The amount of `asset_out` received for swapping exactly `amount_in` of `asset_in`,
+		/// including the fee. `None` if the swap isn't possible.
+		fn quote_exact_in(asset_in: AssetKind, asset_out: AssetKind, fee: FeeTier, amount_in: Balance) -> Option<Balance>;
+
+		/// The amount of `asset_in` needed to receive exactly `amount_out` of `asset_out`,
+		/// including the fee. `None` if the swap isn't possible.
+		fn quote_exact_out(asset_in: AssetKind, asset_out: AssetKind, fee: FeeTier, amount_out: Balance) -> Option<Balance>;
+
+		/// `amount_in` of `asset_in` converted to `asset_out` at the spot price of the pool.
+		fn get_price(asset_in: AssetKind, asset_out: AssetKind, fee: FeeTier, amount_in: Balance) -> Option<Balance>;
+
+		/// The reserves of the pool of `asset_a` and `asset_b` with the fee tier, in the order the assets
+		/// are given.
+		fn pool_reserves(asset_a: AssetKind, asset_b: AssetKind, fee: FeeTier) -> Option<(Balance, Balance)>;
+
+		/// The id of the LP token of the pool of `asset_a` and `asset_b` with the fee tier.
+		fn lp_token_id(asset_a: AssetKind, asset_b: AssetKind, fee: FeeTier) -> Option<AssetId>;
+
+		/// The assets, the fee tier and the LP token id of every pool.
+		fn list_pools() -> Vec<(AssetKind, AssetKind, FeeTier, AssetId)>;
+	}
+}
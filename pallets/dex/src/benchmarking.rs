@@ -5,30 +5,669 @@ use super::*;
 #[allow(unused)]
 use crate::Pallet as Dex;
 use frame_benchmarking::v2::*;
+use frame_support::assert_ok;
 use frame_system::RawOrigin;
 
-#[benchmarks]
+// Every benchmark pool is created with this amount of both assets
+const POOL_AMOUNT: u32 = 1_000_000;
+
+// Every benchmark pool uses the default fee tier, which is always allowed
+fn fee<T: Config>() -> Permill {
+	T::DefaultFeeTier::get()
+}
+
+fn asset<T: Config>(id: u32) -> BalanceType<T>
+where
+	AssetIdOf<T>: From<u32>,
+{
+	BalanceType::AssetBalance(id.into())
+}
+
+// Creates an account holding the native currency and the assets 1 up to `assets`.
+// The dex extrinsics are benchmarked with a signed origin, which is what `CreatePoolOrigin` is in the runtime.
+fn funded_account<T: Config>(assets: u32) -> T::AccountId
+where
+	AssetIdOf<T>: From<u32>,
+{
+	let caller: T::AccountId = whitelisted_caller();
+	let balances = (1..=assets).map(|id| (id.into(), 1_000_000_000u32.into())).collect();
+	assert_ok!(Dex::<T>::setup_account(caller.clone(), 1_000_000_000u32.into(), balances));
+	caller
+}
+
+// Creates assets at all but the last of the ids a new LP token looks at, so it takes the last one
+fn take_lp_asset_ids_of<T: Config>(who: &T::AccountId)
+where
+	AssetIdOf<T>: From<u32>,
+{
+	let start = T::LpAssetIdStart::get();
+	for id in start..start + LP_ASSET_ID_PROBES - 1 {
+		assert_ok!(Dex::<T>::do_mint_asset(who, id.into(), 1u32.into()));
+	}
+}
+
+fn create_pool_of<T: Config>(who: &T::AccountId, asset_a: u32, asset_b: u32)
+where
+	AssetIdOf<T>: From<u32>,
+{
+	assert_ok!(Dex::<T>::create_pool(
+		RawOrigin::Signed(who.clone()).into(),
+		asset::<T>(asset_a),
+		asset::<T>(asset_b),
+		fee::<T>(),
+		POOL_AMOUNT.into(),
+		POOL_AMOUNT.into(),
+	));
+}
+
+// Creates the concentrated liquidity pool 0 of the assets 1 and 2 at a price of 1 with a tick spacing of 10
+fn create_concentrated_pool_of<T: Config>(who: &T::AccountId)
+where
+	AssetIdOf<T>: From<u32>,
+{
+	let price = FixedU128::saturating_from_integer(1u32);
+	assert_ok!(Dex::<T>::do_create_concentrated_pool(who, asset::<T>(1), asset::<T>(2), fee::<T>(), 10, price));
+}
+
+fn mint_position_of<T: Config>(who: &T::AccountId, tick_lower: i32, tick_upper: i32, amount: u32) {
+	assert_ok!(Dex::<T>::do_mint_position(
+		who,
+		0,
+		tick_lower,
+		tick_upper,
+		(amount.into(), amount.into()),
+		(0u32.into(), 0u32.into()),
+	));
+}
+
+// Places the limit order 0 to sell 1_000 of asset 1 for asset 2 in the pool of the default fee tier
+fn place_limit_order_of<T: Config>(who: &T::AccountId, limit_price: FixedU128)
+where
+	AssetIdOf<T>: From<u32>,
+{
+	assert_ok!(Dex::<T>::do_place_limit_order(
+		who,
+		asset::<T>(1),
+		asset::<T>(2),
+		fee::<T>(),
+		1_000u32.into(),
+		limit_price,
+		10u32.into(),
+	));
+}
+
+// Returns the key of the pool of the assets 1 and 2 in the default fee tier
+fn pool_key_of<T: Config>() -> PoolKeyOf<T>
+where
+	AssetIdOf<T>: From<u32>,
+{
+	let (asset_a, asset_b, _, _) = Dex::<T>::order_asset_ids(asset::<T>(1), asset::<T>(2), 0u32.into(), 0u32.into())
+		.expect("the assets are different");
+	(asset_a, asset_b, fee::<T>())
+}
+
+// Returns the path [1, 2, ..., n] with a pool for every hop, and the fee tiers of those pools
+fn path_of_pools<T: Config>(
+	who: &T::AccountId,
+	n: u32,
+) -> (BoundedVec<BalanceType<T>, T::MaxSwapPathLength>, BoundedVec<Permill, T::MaxSwapPathLength>)
+where
+	AssetIdOf<T>: From<u32>,
+{
+	for id in 1..n {
+		create_pool_of::<T>(who, id, id + 1);
+	}
+	let path = (1..=n).map(asset::<T>).collect::<Vec<_>>().try_into().expect("n is at most MaxSwapPathLength");
+	let fees = (1..n).map(|_| fee::<T>()).collect::<Vec<_>>().try_into().expect("n is at most MaxSwapPathLength");
+	(path, fees)
+}
+
+// Returns the assets 1 up to n with equal weights, the first one gets the rounding remainder
+fn weighted_assets_of<T: Config>(
+	n: u32,
+) -> BoundedVec<(BalanceType<T>, Perbill, AssetBalanceOf<T>), T::MaxWeightedPoolAssets>
+where
+	AssetIdOf<T>: From<u32>,
+{
+	let weight = Perbill::from_rational(1, n);
+	let first_weight = Perbill::from_parts(Perbill::one().deconstruct() - weight.deconstruct() * (n - 1));
+	(1..=n)
+		.map(|id| (asset::<T>(id), if id == 1 { first_weight } else { weight }, POOL_AMOUNT.into()))
+		.collect::<Vec<_>>()
+		.try_into()
+		.expect("n is at most MaxWeightedPoolAssets")
+}
+
+#[benchmarks(where AssetIdOf<T>: From<u32>)]
 mod benchmarks {
 	use super::*;
 
 	#[benchmark]
-	fn do_something() {
-		let value = 100u32.into();
-		let caller: T::AccountId = whitelisted_caller();
+	fn create_pool() {
+		let caller = funded_account::<T>(2);
+		take_lp_asset_ids_of::<T>(&caller);
+
+		#[extrinsic_call]
+		create_pool(RawOrigin::Signed(caller), asset::<T>(1), asset::<T>(2), fee::<T>(), POOL_AMOUNT.into(), POOL_AMOUNT.into());
+
+		assert!(LiquidityPoolMap::<T>::contains_key(&(asset::<T>(2), asset::<T>(1), fee::<T>())));
+	}
+
+	#[benchmark]
+	fn add_liquidity() {
+		let caller = funded_account::<T>(2);
+		create_pool_of::<T>(&caller, 1, 2);
+
+		#[extrinsic_call]
+		add_liquidity(
+			RawOrigin::Signed(caller),
+			asset::<T>(1),
+			asset::<T>(2),
+			fee::<T>(),
+			POOL_AMOUNT.into(),
+			POOL_AMOUNT.into(),
+			1u32.into(),
+			1u32.into(),
+			None,
+		);
+
+		let pool = LiquidityPoolMap::<T>::get(&(asset::<T>(2), asset::<T>(1), fee::<T>())).unwrap();
+		assert_eq!(pool.reserve_a, (2 * POOL_AMOUNT).into());
+	}
+
+	#[benchmark]
+	fn remove_liquidity() {
+		let caller = funded_account::<T>(2);
+		create_pool_of::<T>(&caller, 1, 2);
+
+		#[extrinsic_call]
+		remove_liquidity(
+			RawOrigin::Signed(caller),
+			asset::<T>(1),
+			asset::<T>(2),
+			fee::<T>(),
+			1u32.into(),
+			1u32.into(),
+			(POOL_AMOUNT / 2).into(),
+			None,
+		);
+
+		let pool = LiquidityPoolMap::<T>::get(&(asset::<T>(2), asset::<T>(1), fee::<T>())).unwrap();
+		assert_eq!(pool.reserve_a, (POOL_AMOUNT / 2).into());
+	}
+
+	#[benchmark]
+	fn remove_pool() {
+		let caller = funded_account::<T>(2);
+		create_pool_of::<T>(&caller, 1, 2);
+
+		#[extrinsic_call]
+		remove_pool(RawOrigin::Signed(caller), asset::<T>(1), asset::<T>(2), fee::<T>());
+
+		assert!(!LiquidityPoolMap::<T>::contains_key(&(asset::<T>(2), asset::<T>(1), fee::<T>())));
+	}
+
+	#[benchmark]
+	fn swap_exact_in_for_out() {
+		let caller = funded_account::<T>(2);
+		create_pool_of::<T>(&caller, 1, 2);
+
+		#[extrinsic_call]
+		swap_exact_in_for_out(RawOrigin::Signed(caller), asset::<T>(1), asset::<T>(2), fee::<T>(), 1_000u32.into(), 1u32.into(), None);
+
+		let pool = LiquidityPoolMap::<T>::get(&(asset::<T>(2), asset::<T>(1), fee::<T>())).unwrap();
+		assert_eq!(pool.reserve_b, (POOL_AMOUNT + 1_000).into());
+	}
+
+	#[benchmark]
+	fn swap_in_for_exact_out() {
+		let caller = funded_account::<T>(2);
+		create_pool_of::<T>(&caller, 1, 2);
+
+		#[extrinsic_call]
+		swap_in_for_exact_out(RawOrigin::Signed(caller), asset::<T>(1), asset::<T>(2), fee::<T>(), 10_000u32.into(), 1_000u32.into(), None);
+
+		let pool = LiquidityPoolMap::<T>::get(&(asset::<T>(2), asset::<T>(1), fee::<T>())).unwrap();
+		assert_eq!(pool.reserve_a, (POOL_AMOUNT - 1_000).into());
+	}
+
+	#[benchmark]
+	fn swap_exact_in_for_out_via_path(n: Linear<2, { T::MaxSwapPathLength::get() }>) {
+		let caller = funded_account::<T>(n);
+		let (path, fees) = path_of_pools::<T>(&caller, n);
+		let balance_before = Dex::<T>::asset_balance(&asset::<T>(n), &caller);
+
+		#[extrinsic_call]
+		swap_exact_in_for_out_via_path(RawOrigin::Signed(caller.clone()), path, fees, 1_000u32.into(), 1u32.into(), None);
+
+		assert!(Dex::<T>::asset_balance(&asset::<T>(n), &caller) > balance_before);
+	}
+
+	#[benchmark]
+	fn swap_in_for_exact_out_via_path(n: Linear<2, { T::MaxSwapPathLength::get() }>) {
+		let caller = funded_account::<T>(n);
+		let (path, fees) = path_of_pools::<T>(&caller, n);
+		let balance_before = Dex::<T>::asset_balance(&asset::<T>(n), &caller);
+
+		#[extrinsic_call]
+		swap_in_for_exact_out_via_path(RawOrigin::Signed(caller.clone()), path, fees, 10_000u32.into(), 1_000u32.into(), None);
+
+		assert_eq!(Dex::<T>::asset_balance(&asset::<T>(n), &caller), balance_before + 1_000u32.into());
+	}
+
+	#[benchmark]
+	fn skim() {
+		let caller = funded_account::<T>(2);
+		create_pool_of::<T>(&caller, 1, 2);
+		let pool = LiquidityPoolMap::<T>::get(&(asset::<T>(2), asset::<T>(1), fee::<T>())).unwrap();
+		assert_ok!(Dex::<T>::transfer_asset(&asset::<T>(1), &caller, &pool.pool_account, 1_000u32.into()));
+		assert_ok!(Dex::<T>::transfer_asset(&asset::<T>(2), &caller, &pool.pool_account, 1_000u32.into()));
+
+		#[extrinsic_call]
+		skim(RawOrigin::Signed(caller), asset::<T>(1), asset::<T>(2), fee::<T>());
+
+		assert_eq!(Dex::<T>::pool_reserve(&asset::<T>(1), &pool.pool_account), POOL_AMOUNT.into());
+	}
+
+	#[benchmark]
+	fn sync() {
+		let caller = funded_account::<T>(2);
+		create_pool_of::<T>(&caller, 1, 2);
+		let pool = LiquidityPoolMap::<T>::get(&(asset::<T>(2), asset::<T>(1), fee::<T>())).unwrap();
+		assert_ok!(Dex::<T>::transfer_asset(&asset::<T>(1), &caller, &pool.pool_account, 1_000u32.into()));
+
+		#[extrinsic_call]
+		sync(RawOrigin::Signed(caller), asset::<T>(1), asset::<T>(2), fee::<T>());
+
+		let pool = LiquidityPoolMap::<T>::get(&(asset::<T>(2), asset::<T>(1), fee::<T>())).unwrap();
+		assert_eq!(pool.reserve_b, (POOL_AMOUNT + 1_000).into());
+	}
+
+	#[benchmark]
+	fn set_protocol_fee() -> Result<(), BenchmarkError> {
+		let recipient: T::AccountId = account("recipient", 0, 0);
+		let origin = T::GovernanceOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, Some(recipient.clone()), Percent::from_percent(20));
+
+		assert_eq!(ProtocolFeeRecipient::<T>::get(), Some(recipient));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn set_fee_tiers() -> Result<(), BenchmarkError> {
+		let fee_tiers: BoundedVec<Permill, T::MaxFeeTiers> = (0..T::MaxFeeTiers::get())
+			.map(|i| Permill::from_parts(100 * (i + 1)))
+			.collect::<Vec<_>>()
+			.try_into()
+			.expect("exactly MaxFeeTiers fee tiers");
+		let origin = T::GovernanceOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, fee_tiers.clone());
+
+		assert_eq!(FeeTiers::<T>::get(), fee_tiers);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn flash_swap() {
+		let caller = funded_account::<T>(2);
+		create_pool_of::<T>(&caller, 1, 2);
+		// The handler of the runtime may do nothing, so the amount paid back is donated upfront
+		let pool = LiquidityPoolMap::<T>::get(&(asset::<T>(2), asset::<T>(1), fee::<T>())).unwrap();
+		assert_ok!(Dex::<T>::transfer_asset(&asset::<T>(1), &caller, &pool.pool_account, 2_000u32.into()));
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller), asset::<T>(1), asset::<T>(2), fee::<T>(), 1_000u32.into(), 0u32.into());
+
+		let pool = LiquidityPoolMap::<T>::get(&(asset::<T>(2), asset::<T>(1), fee::<T>())).unwrap();
+		assert_eq!(pool.reserve_b, (POOL_AMOUNT + 1_000).into());
+	}
+
+	#[benchmark]
+	fn create_stable_pool() {
+		let caller = funded_account::<T>(2);
+		take_lp_asset_ids_of::<T>(&caller);
+		let amplification = T::MaxAmplification::get();
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller), asset::<T>(1), asset::<T>(2), fee::<T>(), amplification, POOL_AMOUNT.into(), POOL_AMOUNT.into());
+
+		let pool = LiquidityPoolMap::<T>::get(&(asset::<T>(2), asset::<T>(1), fee::<T>())).unwrap();
+		assert_eq!(pool.curve, PoolCurve::StableSwap { amplification });
+	}
+
+	#[benchmark]
+	fn create_weighted_pool(n: Linear<2, { T::MaxWeightedPoolAssets::get() }>) {
+		let caller = funded_account::<T>(n);
+		take_lp_asset_ids_of::<T>(&caller);
+		let assets = weighted_assets_of::<T>(n);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller), assets, fee::<T>());
+
+		assert_eq!(WeightedPools::<T>::get(0).unwrap().assets.len(), n as usize);
+	}
+
+	#[benchmark]
+	fn join_weighted_pool() {
+		let caller = funded_account::<T>(2);
+		assert_ok!(Dex::<T>::do_create_weighted_pool(&caller, weighted_assets_of::<T>(2).into_inner(), fee::<T>()));
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller), 0, asset::<T>(1), 1_000u32.into(), 1u32.into(), None);
+
+		assert_eq!(WeightedPools::<T>::get(0).unwrap().assets[0].reserve, (POOL_AMOUNT + 1_000).into());
+	}
+
+	#[benchmark]
+	fn exit_weighted_pool() {
+		let caller = funded_account::<T>(2);
+		assert_ok!(Dex::<T>::do_create_weighted_pool(&caller, weighted_assets_of::<T>(2).into_inner(), fee::<T>()));
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller), 0, asset::<T>(1), 1_000u32.into(), 1u32.into(), None);
+
+		assert!(WeightedPools::<T>::get(0).unwrap().assets[0].reserve < POOL_AMOUNT.into());
+	}
+
+	#[benchmark]
+	fn swap_weighted() {
+		let caller = funded_account::<T>(2);
+		assert_ok!(Dex::<T>::do_create_weighted_pool(&caller, weighted_assets_of::<T>(2).into_inner(), fee::<T>()));
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller), 0, asset::<T>(1), asset::<T>(2), 1_000u32.into(), 1u32.into(), None);
+
+		assert_eq!(WeightedPools::<T>::get(0).unwrap().assets[0].reserve, (POOL_AMOUNT + 1_000).into());
+	}
+
+	#[benchmark]
+	fn create_concentrated_pool() {
+		let caller = funded_account::<T>(2);
+		let price = FixedU128::saturating_from_integer(1u32);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller), asset::<T>(1), asset::<T>(2), fee::<T>(), 10, price);
+
+		assert_eq!(ConcentratedPools::<T>::get(0).unwrap().tick, 0);
+	}
+
+	#[benchmark]
+	fn mint_position() {
+		let caller = funded_account::<T>(2);
+		create_concentrated_pool_of::<T>(&caller);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()), 0, -100, 100, 1_000u32.into(), 1_000u32.into(), 0u32.into(), 0u32.into(), None);
+
+		assert!(Positions::<T>::contains_key(&caller, 0));
+	}
+
+	#[benchmark]
+	fn decrease_liquidity() {
+		let caller = funded_account::<T>(2);
+		create_concentrated_pool_of::<T>(&caller);
+		mint_position_of::<T>(&caller, -100, 100, POOL_AMOUNT);
+		let liquidity = Positions::<T>::get(&caller, 0).unwrap().liquidity;
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()), 0, liquidity, 0u32.into(), 0u32.into(), None);
+
+		assert_eq!(Positions::<T>::get(&caller, 0).unwrap().liquidity, 0);
+	}
+
+	#[benchmark]
+	fn collect() {
+		let caller = funded_account::<T>(2);
+		create_concentrated_pool_of::<T>(&caller);
+		mint_position_of::<T>(&caller, -100, 100, POOL_AMOUNT);
+		let liquidity = Positions::<T>::get(&caller, 0).unwrap().liquidity;
+		assert_ok!(Dex::<T>::do_decrease_liquidity(&caller, 0, liquidity, (0u32.into(), 0u32.into())));
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()), 0);
+
+		assert!(!Positions::<T>::contains_key(&caller, 0));
+	}
+
+	#[benchmark]
+	fn swap_concentrated(n: Linear<1, { T::MaxConcentratedTicks::get() - 2 }>) {
+		let caller = funded_account::<T>(2);
+		create_concentrated_pool_of::<T>(&caller);
+		// A wide position keeps the swap going while it crosses the ticks of n narrow positions above the price
+		mint_position_of::<T>(&caller, 0, 20_000, POOL_AMOUNT);
+		for i in 0..n as i32 {
+			mint_position_of::<T>(&caller, i * 10, (i + 1) * 10, 1_000);
+		}
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller), 0, asset::<T>(1), (POOL_AMOUNT / 2).into(), 1u32.into(), None);
+
+		assert!(ConcentratedPools::<T>::get(0).unwrap().tick >= n as i32 * 10);
+	}
+
+	#[benchmark]
+	fn place_limit_order() {
+		let caller = funded_account::<T>(2);
+		create_pool_of::<T>(&caller, 1, 2);
+		let limit_price = FixedU128::saturating_from_rational(9u32, 10u32);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller), asset::<T>(1), asset::<T>(2), fee::<T>(), 1_000u32.into(), limit_price, 10u32.into());
+
+		assert!(LimitOrders::<T>::contains_key(0));
+	}
+
+	#[benchmark]
+	fn cancel_limit_order() {
+		let caller = funded_account::<T>(2);
+		create_pool_of::<T>(&caller, 1, 2);
+		place_limit_order_of::<T>(&caller, FixedU128::saturating_from_integer(2u32));
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller), 0);
+
+		assert!(!LimitOrders::<T>::contains_key(0));
+	}
+
+	#[benchmark]
+	fn fill_order() {
+		let caller = funded_account::<T>(2);
+		create_pool_of::<T>(&caller, 1, 2);
+		place_limit_order_of::<T>(&caller, FixedU128::saturating_from_rational(9u32, 10u32));
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller), 0);
+
+		assert!(!LimitOrders::<T>::contains_key(0));
+	}
+
+	#[benchmark]
+	fn set_batch_mode() -> Result<(), BenchmarkError> {
+		let caller = funded_account::<T>(2);
+		create_pool_of::<T>(&caller, 1, 2);
+		let origin = T::GovernanceOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
 		#[extrinsic_call]
-		do_something(RawOrigin::Signed(caller), value);
+		_(origin as T::RuntimeOrigin, asset::<T>(1), asset::<T>(2), fee::<T>(), true);
 
-		assert_eq!(Something::<T>::get(), Some(value));
+		assert!(BatchModePools::<T>::get(pool_key_of::<T>()));
+		Ok(())
 	}
 
 	#[benchmark]
-	fn cause_error() {
-		Something::<T>::put(100u32);
-		let caller: T::AccountId = whitelisted_caller();
+	fn submit_swap_intent() {
+		let caller = funded_account::<T>(2);
+		create_pool_of::<T>(&caller, 1, 2);
+		BatchModePools::<T>::insert(pool_key_of::<T>(), true);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller), asset::<T>(1), asset::<T>(2), fee::<T>(), 1_000u32.into(), 1u32.into(), None);
+
+		assert_eq!(SwapIntents::<T>::get(pool_key_of::<T>()).len(), 1);
+	}
+
+	#[benchmark]
+	fn clear_swap_batch(n: Linear<1, { T::MaxSwapIntents::get() }>) {
+		// the worst case clears the batch twice on the StableSwap curve, whose amounts out are iterated
+		let caller = funded_account::<T>(2);
+		assert_ok!(Dex::<T>::create_stable_pool(
+			RawOrigin::Signed(caller).into(),
+			asset::<T>(1),
+			asset::<T>(2),
+			fee::<T>(),
+			T::MaxAmplification::get(),
+			POOL_AMOUNT.into(),
+			POOL_AMOUNT.into(),
+		));
+		BatchModePools::<T>::insert(pool_key_of::<T>(), true);
+		// n intents of different accounts, selling both assets of the pool. The ones selling asset 2 have
+		// a limit no share meets, so they are paid back and the rest is cleared again.
+		for i in 0..n {
+			let who: T::AccountId = account("intent", i, 0);
+			let balances = Vec::from([(1u32.into(), 1_000_000u32.into()), (2u32.into(), 1_000_000u32.into())]);
+			assert_ok!(Dex::<T>::setup_account(who.clone(), 1_000_000_000u32.into(), balances));
+			let (asset_in, asset_out, min_out) = if i % 2 == 0 { (1, 2, 1) } else { (2, 1, u32::MAX) };
+			assert_ok!(Dex::<T>::do_submit_swap_intent(
+				&who,
+				asset::<T>(asset_in),
+				asset::<T>(asset_out),
+				fee::<T>(),
+				(1_000u32 * (i + 1)).into(),
+				min_out.into(),
+			));
+		}
+
+		#[block]
+		{
+			Dex::<T>::clear_swap_batches();
+		}
+
+		assert!(!SwapIntents::<T>::contains_key(pool_key_of::<T>()));
+	}
+
+	#[benchmark]
+	fn pause_pool() -> Result<(), BenchmarkError> {
+		let caller = funded_account::<T>(2);
+		create_pool_of::<T>(&caller, 1, 2);
+		let origin = T::PauseOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let flags = PauseFlags { swaps: true, adds: true };
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, asset::<T>(1), asset::<T>(2), fee::<T>(), flags);
+
+		assert_eq!(PausedPools::<T>::get(pool_key_of::<T>()), flags);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn unpause_pool() -> Result<(), BenchmarkError> {
+		let caller = funded_account::<T>(2);
+		create_pool_of::<T>(&caller, 1, 2);
+		PausedPools::<T>::insert(pool_key_of::<T>(), PauseFlags { swaps: true, adds: true });
+		let origin = T::PauseOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, asset::<T>(1), asset::<T>(2), fee::<T>());
+
+		assert!(!PausedPools::<T>::contains_key(pool_key_of::<T>()));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn pause_all() -> Result<(), BenchmarkError> {
+		let origin = T::PauseOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let flags = PauseFlags { swaps: true, adds: true };
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, flags);
+
+		assert_eq!(GlobalPause::<T>::get(), flags);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn unpause_all() -> Result<(), BenchmarkError> {
+		GlobalPause::<T>::put(PauseFlags { swaps: true, adds: true });
+		let origin = T::PauseOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin);
+
+		assert_eq!(GlobalPause::<T>::get(), PauseFlags::default());
+		Ok(())
+	}
+
+	#[benchmark]
+	fn pause_weighted_pool() -> Result<(), BenchmarkError> {
+		let caller = funded_account::<T>(2);
+		assert_ok!(Dex::<T>::do_create_weighted_pool(&caller, weighted_assets_of::<T>(2).into_inner(), fee::<T>()));
+		let origin = T::PauseOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let flags = PauseFlags { swaps: true, adds: true };
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, 0, flags);
+
+		assert_eq!(PausedWeightedPools::<T>::get(0), flags);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn unpause_weighted_pool() -> Result<(), BenchmarkError> {
+		let caller = funded_account::<T>(2);
+		assert_ok!(Dex::<T>::do_create_weighted_pool(&caller, weighted_assets_of::<T>(2).into_inner(), fee::<T>()));
+		PausedWeightedPools::<T>::insert(0, PauseFlags { swaps: true, adds: true });
+		let origin = T::PauseOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, 0);
+
+		assert!(!PausedWeightedPools::<T>::contains_key(0));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn pause_concentrated_pool() -> Result<(), BenchmarkError> {
+		let caller = funded_account::<T>(2);
+		create_concentrated_pool_of::<T>(&caller);
+		let origin = T::PauseOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let flags = PauseFlags { swaps: true, adds: true };
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, 0, flags);
+
+		assert_eq!(PausedConcentratedPools::<T>::get(0), flags);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn unpause_concentrated_pool() -> Result<(), BenchmarkError> {
+		let caller = funded_account::<T>(2);
+		create_concentrated_pool_of::<T>(&caller);
+		PausedConcentratedPools::<T>::insert(0, PauseFlags { swaps: true, adds: true });
+		let origin = T::PauseOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, 0);
+
+		assert!(!PausedConcentratedPools::<T>::contains_key(0));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn claim_swap_intent_refund() {
+		// the escrow account holds the amount of an intent that couldn't be paid back
+		let caller = funded_account::<T>(2);
+		assert_ok!(Dex::<T>::do_mint_asset(&Dex::<T>::escrow_account(), 1u32.into(), 1_000u32.into()));
+		UnclaimedRefunds::<T>::insert(&caller, asset::<T>(1), AssetBalanceOf::<T>::from(1_000u32));
+
 		#[extrinsic_call]
-		cause_error(RawOrigin::Signed(caller));
+		_(RawOrigin::Signed(caller.clone()), asset::<T>(1));
 
-		assert_eq!(Something::<T>::get(), Some(101u32));
+		assert!(!UnclaimedRefunds::<T>::contains_key(&caller, asset::<T>(1)));
 	}
 
 	impl_benchmark_test_suite!(Dex, crate::mock::new_test_ext(), crate::mock::Test);
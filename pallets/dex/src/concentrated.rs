@@ -0,0 +1,273 @@
+//! The math of concentrated liquidity pools in the style of Uniswap V3.
+//!
+//! The price of asset_0 in asset_1 at tick `i` is `1.0001^i`. Prices are tracked as their square roots in
+//! Q64.64 fixed point, the upper 64 bits of a `u128` hold the integer part and the lower 64 bits the
+//! fractional part. Amounts paid into a pool are rounded up and amounts paid out are rounded down.
+
+use sp_arithmetic::{PerThing, Permill};
+use sp_core::{U256, U512};
+use sp_runtime::{FixedPointNumber, FixedU128, RuntimeDebug};
+
+/// The lowest tick, its price is about 2^-64.
+pub const MIN_TICK: i32 = -443_636;
+
+/// The highest tick, its price is about 2^64.
+pub const MAX_TICK: i32 = 443_636;
+
+/// The largest tick spacing of a pool, like in Uniswap V3.
+pub const MAX_TICK_SPACING: u32 = 16_384;
+
+/// The number of fractional bits of a square root price.
+const RESOLUTION: usize = 64;
+
+/// `2^128 / sqrt(1.0001)^(2^i)` for the bits `i` of a tick, like in `TickMath` of Uniswap V3.
+const TICK_RATIOS: [u128; 19] = [
+	0xfffcb933bd6fad37aa2d162d1a594001,
+	0xfff97272373d413259a46990580e213a,
+	0xfff2e50f5f656932ef12357cf3c7fdcc,
+	0xffe5caca7e10e4e61c3624eaa0941cd0,
+	0xffcb9843d60f6159c9db58835c926644,
+	0xff973b41fa98c081472e6896dfb254c0,
+	0xff2ea16466c96a3843ec78b326b52861,
+	0xfe5dee046a99a2a811c461f1969c3053,
+	0xfcbe86c7900a88aedcffc83b479aa3a4,
+	0xf987a7253ac413176f2b074cf7815e54,
+	0xf3392b0822b70005940c7a398e4b70f3,
+	0xe7159475a2c29b7443b29c7fa6e889d9,
+	0xd097f3bdfd2022b8845ad8f792aa5825,
+	0xa9f746462d870fdf8a65dc1f90e061e5,
+	0x70d869a156d2a1b890bb3df62baf32f7,
+	0x31be135f97d08fd981231505542fcfa6,
+	0x9aa508b5b7a84e1c677de54f3e99bc9,
+	0x5d6af8dedb81196699c329225ee604,
+	0x2216e584f5fa1ea926041bedfe98,
+];
+
+/// The result of swapping within the range between two initialized ticks.
+#[derive(Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub struct SwapStep {
+	/// The square root price after the step.
+	pub sqrt_price_next: u128,
+	/// The amount paid into the pool, without the fee.
+	pub amount_in: u128,
+	/// The amount paid out of the pool.
+	pub amount_out: u128,
+	/// The fee taken from the input of the step.
+	pub fee_amount: u128,
+}
+
+/// Returns the square root price at `tick`, like `getSqrtRatioAtTick` in Uniswap V3.
+pub fn sqrt_price_at_tick(tick: i32) -> Option<u128> {
+	if !(MIN_TICK..=MAX_TICK).contains(&tick) {
+		return None
+	}
+	let abs_tick = tick.unsigned_abs();
+
+	// 1 / sqrt(1.0001)^abs_tick in Q128.128, multiplied together from the ratios of the bits of the tick
+	let mut ratio = if abs_tick & 1 != 0 { U256::from(TICK_RATIOS[0]) } else { U256::one() << 128 };
+	for (bit, tick_ratio) in TICK_RATIOS.iter().enumerate().skip(1) {
+		if abs_tick & (1 << bit) != 0 {
+			ratio = (ratio * U256::from(*tick_ratio)) >> 128;
+		}
+	}
+	if tick > 0 {
+		ratio = U256::MAX / ratio;
+	}
+
+	// from Q128.128 to Q64.64, rounded up so the price at a tick never falls below the tick
+	let shift = 128 - RESOLUTION;
+	let rounding = if (ratio % (U256::one() << shift)).is_zero() { 0u8 } else { 1u8 };
+	((ratio >> shift) + U256::from(rounding)).try_into().ok()
+}
+
+/// Returns the highest tick whose square root price is at most `sqrt_price`.
+pub fn tick_at_sqrt_price(sqrt_price: u128) -> Option<i32> {
+	if sqrt_price < sqrt_price_at_tick(MIN_TICK)? || sqrt_price > sqrt_price_at_tick(MAX_TICK)? {
+		return None
+	}
+	let (mut low, mut high) = (MIN_TICK, MAX_TICK);
+	while low < high {
+		let middle = low + (high - low + 1) / 2;
+		if sqrt_price_at_tick(middle)? <= sqrt_price {
+			low = middle;
+		} else {
+			high = middle - 1;
+		}
+	}
+	Some(low)
+}
+
+/// Converts the price of asset_0 in asset_1 to its square root in Q64.64.
+pub fn sqrt_price_from_price(price: FixedU128) -> Option<u128> {
+	let scaled = (U512::from(price.into_inner()) << (2 * RESOLUTION)) / U512::from(FixedU128::DIV);
+	U256::try_from(scaled.integer_sqrt()).ok()?.try_into().ok()
+}
+
+/// Adds a signed liquidity delta to `liquidity`, `None` on overflow or underflow.
+pub fn add_delta(liquidity: u128, delta: i128) -> Option<u128> {
+	if delta < 0 {
+		liquidity.checked_sub(delta.unsigned_abs())
+	} else {
+		liquidity.checked_add(delta.unsigned_abs())
+	}
+}
+
+/// The fees per unit of liquidity in Q64.64 for `fee_amount` earned by `liquidity`. The fee growth of a
+/// pool wraps on overflow like in Uniswap V3, only the difference between two values is meaningful.
+pub fn fee_growth(fee_amount: u128, liquidity: u128) -> u128 {
+	if liquidity == 0 {
+		return 0
+	}
+	((U256::from(fee_amount) << RESOLUTION) / U256::from(liquidity)).low_u128()
+}
+
+/// The fees earned by `liquidity` while the fees per unit of liquidity grew by `fee_growth_delta`.
+pub fn fees_owed(fee_growth_delta: u128, liquidity: u128) -> u128 {
+	((U256::from(fee_growth_delta) * U256::from(liquidity)) >> RESOLUTION).try_into().unwrap_or(u128::MAX)
+}
+
+/// Returns `a * b / c` without overflowing in between.
+fn mul_div(a: U256, b: U256, c: U256, round_up: bool) -> Option<U256> {
+	if c.is_zero() {
+		return None
+	}
+	let product = a.full_mul(b);
+	let (quotient, remainder) = product.div_mod(U512::from(c));
+	let quotient = if round_up && !remainder.is_zero() { quotient + U512::one() } else { quotient };
+	U256::try_from(quotient).ok()
+}
+
+/// The amount of asset_0 between two square root prices for `liquidity`,
+/// `liquidity * (sqrt_b - sqrt_a) / (sqrt_a * sqrt_b)`.
+pub fn amount_0_delta(sqrt_a: u128, sqrt_b: u128, liquidity: u128, round_up: bool) -> Option<u128> {
+	let (sqrt_a, sqrt_b) = if sqrt_a > sqrt_b { (sqrt_b, sqrt_a) } else { (sqrt_a, sqrt_b) };
+	if sqrt_a == 0 {
+		return None
+	}
+	let numerator = U256::from(liquidity) << RESOLUTION;
+	let amount = mul_div(numerator, U256::from(sqrt_b - sqrt_a), U256::from(sqrt_b), round_up)?;
+	mul_div(amount, U256::one(), U256::from(sqrt_a), round_up)?.try_into().ok()
+}
+
+/// The amount of asset_1 between two square root prices for `liquidity`, `liquidity * (sqrt_b - sqrt_a)`.
+pub fn amount_1_delta(sqrt_a: u128, sqrt_b: u128, liquidity: u128, round_up: bool) -> Option<u128> {
+	let (sqrt_a, sqrt_b) = if sqrt_a > sqrt_b { (sqrt_b, sqrt_a) } else { (sqrt_a, sqrt_b) };
+	mul_div(U256::from(liquidity), U256::from(sqrt_b - sqrt_a), U256::one() << RESOLUTION, round_up)?.try_into().ok()
+}
+
+/// Returns the amounts of asset_0 and asset_1 of `liquidity` in the range between `sqrt_a` and `sqrt_b`
+/// at `sqrt_price`. Below the range it is all asset_0, above the range all asset_1.
+pub fn amounts_for_liquidity(
+	sqrt_price: u128,
+	sqrt_a: u128,
+	sqrt_b: u128,
+	liquidity: u128,
+	round_up: bool,
+) -> Option<(u128, u128)> {
+	if sqrt_price < sqrt_a {
+		Some((amount_0_delta(sqrt_a, sqrt_b, liquidity, round_up)?, 0))
+	} else if sqrt_price < sqrt_b {
+		Some((
+			amount_0_delta(sqrt_price, sqrt_b, liquidity, round_up)?,
+			amount_1_delta(sqrt_a, sqrt_price, liquidity, round_up)?,
+		))
+	} else {
+		Some((0, amount_1_delta(sqrt_a, sqrt_b, liquidity, round_up)?))
+	}
+}
+
+/// Returns the most liquidity in the range between `sqrt_a` and `sqrt_b` that `amount_0` and `amount_1`
+/// pay for at `sqrt_price`, like `getLiquidityForAmounts` in Uniswap V3.
+pub fn liquidity_for_amounts(
+	sqrt_price: u128,
+	sqrt_a: u128,
+	sqrt_b: u128,
+	amount_0: u128,
+	amount_1: u128,
+) -> Option<u128> {
+	// amount_0 * sqrt_a * sqrt_b / (sqrt_b - sqrt_a)
+	let liquidity_0 = |sqrt_a: u128, sqrt_b: u128| -> Option<u128> {
+		let intermediate = mul_div(U256::from(sqrt_a), U256::from(sqrt_b), U256::one() << RESOLUTION, false)?;
+		mul_div(U256::from(amount_0), intermediate, U256::from(sqrt_b - sqrt_a), false)?.try_into().ok()
+	};
+	// amount_1 / (sqrt_b - sqrt_a)
+	let liquidity_1 = |sqrt_a: u128, sqrt_b: u128| -> Option<u128> {
+		mul_div(U256::from(amount_1), U256::one() << RESOLUTION, U256::from(sqrt_b - sqrt_a), false)?.try_into().ok()
+	};
+
+	if sqrt_a >= sqrt_b {
+		return None
+	}
+	if sqrt_price <= sqrt_a {
+		liquidity_0(sqrt_a, sqrt_b)
+	} else if sqrt_price < sqrt_b {
+		Some(liquidity_0(sqrt_price, sqrt_b)?.min(liquidity_1(sqrt_a, sqrt_price)?))
+	} else {
+		liquidity_1(sqrt_a, sqrt_b)
+	}
+}
+
+/// Returns the square root price after paying `amount_in` into `liquidity`. Asset_0 moves the price down
+/// and asset_1 moves it up, the price is rounded so the pool is never paid too little.
+fn next_sqrt_price_from_input(sqrt_price: u128, liquidity: u128, amount_in: u128, zero_for_one: bool) -> Option<u128> {
+	if liquidity == 0 {
+		return None
+	}
+	if zero_for_one {
+		// liquidity * sqrt_price / (liquidity + amount_in * sqrt_price)
+		let numerator = U256::from(liquidity) << RESOLUTION;
+		let denominator = numerator.checked_add(U256::from(amount_in).checked_mul(U256::from(sqrt_price))?)?;
+		mul_div(numerator, U256::from(sqrt_price), denominator, true)?.try_into().ok()
+	} else {
+		// sqrt_price + amount_in / liquidity
+		let quotient: u128 = ((U256::from(amount_in) << RESOLUTION) / U256::from(liquidity)).try_into().ok()?;
+		sqrt_price.checked_add(quotient)
+	}
+}
+
+/// Swaps as much of `amount_remaining` as possible before the price reaches `sqrt_price_target`, like
+/// `computeSwapStep` of an exact input swap in Uniswap V3. The fee is taken from `amount_remaining`.
+pub fn compute_swap_step(
+	sqrt_price: u128,
+	sqrt_price_target: u128,
+	liquidity: u128,
+	amount_remaining: u128,
+	fee: Permill,
+) -> Option<SwapStep> {
+	let zero_for_one = sqrt_price >= sqrt_price_target;
+	let fee_parts = U256::from(fee.deconstruct());
+	let one = U256::from(Permill::ACCURACY);
+
+	let remaining_less_fee: u128 =
+		mul_div(U256::from(amount_remaining), one.checked_sub(fee_parts)?, one, false)?.try_into().ok()?;
+	let amount_to_target = if zero_for_one {
+		amount_0_delta(sqrt_price_target, sqrt_price, liquidity, true)?
+	} else {
+		amount_1_delta(sqrt_price, sqrt_price_target, liquidity, true)?
+	};
+
+	// the target is reached if the input covers it, otherwise the whole input moves the price
+	let sqrt_price_next = if remaining_less_fee >= amount_to_target {
+		sqrt_price_target
+	} else {
+		next_sqrt_price_from_input(sqrt_price, liquidity, remaining_less_fee, zero_for_one)?
+	};
+	let reached_target = sqrt_price_next == sqrt_price_target;
+
+	let (amount_in, amount_out) = if zero_for_one {
+		let amount_in = if reached_target { amount_to_target } else { amount_0_delta(sqrt_price_next, sqrt_price, liquidity, true)? };
+		(amount_in, amount_1_delta(sqrt_price_next, sqrt_price, liquidity, false)?)
+	} else {
+		let amount_in = if reached_target { amount_to_target } else { amount_1_delta(sqrt_price, sqrt_price_next, liquidity, true)? };
+		(amount_in, amount_0_delta(sqrt_price, sqrt_price_next, liquidity, false)?)
+	};
+
+	// the rest of the input is the fee if the target isn't reached
+	let fee_amount = if reached_target {
+		mul_div(U256::from(amount_in), fee_parts, one.checked_sub(fee_parts)?, true)?.try_into().ok()?
+	} else {
+		amount_remaining.checked_sub(amount_in)?
+	};
+
+	Some(SwapStep { sqrt_price_next, amount_in, amount_out, fee_amount })
+}
@@ -0,0 +1,168 @@
+//! The invariants the pools of the DEX can trade on.
+//!
+//! Every pool keeps the curve it was created with. The swap math, the initial LP tokens, the protocol fee
+//! and the price oracle of a pool all go through its curve, so the pallet handles both kinds the same way.
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use scale_info::TypeInfo;
+use sp_arithmetic::traits::IntegerSquareRoot;
+use sp_core::U256;
+use sp_runtime::{FixedPointNumber, FixedU128, RuntimeDebug};
+
+/// The maximum number of Newton iterations for the StableSwap invariant, it usually converges in a handful.
+const MAX_ITERATIONS: usize = 255;
+
+/// The invariant a pool keeps during swaps.
+#[derive(Clone, Copy, PartialEq, Eq, RuntimeDebug, Encode, Decode, MaxEncodedLen, TypeInfo)]
+pub enum PoolCurve {
+	/// `x * y = k` like in Uniswap V2, for any pair of assets.
+	ConstantProduct,
+	/// The StableSwap invariant of Curve for two assets, `4A(x + y) + D = 4AD + D^3 / 4xy`.
+	/// It stays close to `x + y = D` around the peg, so pegged assets can be swapped with far lower slippage.
+	/// The higher the amplification `A`, the flatter the curve around the peg.
+	StableSwap { amplification: u32 },
+}
+
+impl PoolCurve {
+	/// Returns `k` of the pool, which only grows with the fees left in the pool. For a stable pool it is
+	/// `(D / 2)^2`, which equals `x * y` while the pool is balanced, so `sqrt(k)` measures the liquidity
+	/// of both kinds of pools the same way.
+	pub fn k(&self, reserve_a: u128, reserve_b: u128) -> Option<u128> {
+		match *self {
+			PoolCurve::ConstantProduct => reserve_a.checked_mul(reserve_b),
+			PoolCurve::StableSwap { amplification } => {
+				let half_d = get_d(amplification, reserve_a, reserve_b)? / 2;
+				half_d.checked_mul(half_d)
+			},
+		}
+	}
+
+	/// Returns the liquidity of the pool, `sqrt(k)`.
+	pub fn liquidity(&self, reserve_a: u128, reserve_b: u128) -> Option<u128> {
+		self.k(reserve_a, reserve_b).map(|k| k.integer_sqrt())
+	}
+
+	/// Calculates the amount the pool pays out for `amount_in`, after the fee has been taken from it.
+	pub fn amount_out(&self, amount_in: u128, reserve_in: u128, reserve_out: u128) -> Option<u128> {
+		match *self {
+			// formula: amount_out = reserve_out - (reserve_in * reserve_out) / (reserve_in + amount_in)
+			PoolCurve::ConstantProduct => {
+				let k = reserve_in.checked_mul(reserve_out)?;
+				reserve_out.checked_sub(k.checked_div(reserve_in.checked_add(amount_in)?)?)
+			},
+			PoolCurve::StableSwap { amplification } => {
+				let d = get_d(amplification, reserve_in, reserve_out)?;
+				let new_reserve_out = get_y(amplification, reserve_in.checked_add(amount_in)?, d)?;
+				// one unit is kept in the pool, so the rounding of the invariant never works against it
+				Some(reserve_out.checked_sub(new_reserve_out)?.saturating_sub(1))
+			},
+		}
+	}
+
+	/// Calculates the amount the pool requires to pay out exactly `amount_out`, before the fee is added to it.
+	pub fn amount_in(&self, amount_out: u128, reserve_in: u128, reserve_out: u128) -> Option<u128> {
+		let new_reserve_out = reserve_out.checked_sub(amount_out).filter(|reserve| *reserve > 0)?;
+		match *self {
+			// formula: amount_in = (reserve_in * reserve_out) / (reserve_out - amount_out) - reserve_in
+			PoolCurve::ConstantProduct => {
+				let k = reserve_in.checked_mul(reserve_out)?;
+				k.checked_div(new_reserve_out)?.checked_sub(reserve_in)
+			},
+			PoolCurve::StableSwap { amplification } => {
+				let d = get_d(amplification, reserve_in, reserve_out)?;
+				let new_reserve_in = get_y(amplification, new_reserve_out, d)?;
+				new_reserve_in.checked_sub(reserve_in)?.checked_add(1)
+			},
+		}
+	}
+
+	/// Returns the marginal prices of asset_a in asset_b and of asset_b in asset_a.
+	/// A pool without reserves has no price.
+	pub fn spot_prices(&self, reserve_a: u128, reserve_b: u128) -> (FixedU128, FixedU128) {
+		match *self {
+			PoolCurve::ConstantProduct => (
+				FixedU128::checked_from_rational(reserve_b, reserve_a).unwrap_or_default(),
+				FixedU128::checked_from_rational(reserve_a, reserve_b).unwrap_or_default(),
+			),
+			PoolCurve::StableSwap { amplification } => {
+				let price_a = stable_spot_price(amplification, reserve_a, reserve_b).unwrap_or_default();
+				let price_b = stable_spot_price(amplification, reserve_b, reserve_a).unwrap_or_default();
+				(price_a, price_b)
+			},
+		}
+	}
+}
+
+/// Solves the StableSwap invariant for `D` with Newton's method, like `get_D` in Curve.
+fn get_d(amplification: u32, reserve_x: u128, reserve_y: u128) -> Option<u128> {
+	if reserve_x == 0 || reserve_y == 0 {
+		return None
+	}
+	let (x, y) = (U256::from(reserve_x), U256::from(reserve_y));
+	// A * n^n with n = 2
+	let ann = U256::from(amplification).checked_mul(U256::from(4))?;
+	let sum = x + y;
+
+	let mut d = sum;
+	for _ in 0..MAX_ITERATIONS {
+		// d_p = D^3 / (4xy)
+		let d_p = d.checked_mul(d)?.checked_div(x * 2)?.checked_mul(d)?.checked_div(y * 2)?;
+		let d_prev = d;
+		// D = (Ann * S + 2 * d_p) * D / ((Ann - 1) * D + 3 * d_p)
+		let numerator = ann.checked_mul(sum)?.checked_add(d_p * 2)?.checked_mul(d)?;
+		let denominator = ann.checked_sub(U256::one())?.checked_mul(d)?.checked_add(d_p * 3)?;
+		d = numerator.checked_div(denominator)?;
+		if converged(d, d_prev) {
+			return d.try_into().ok()
+		}
+	}
+	None
+}
+
+/// Solves the StableSwap invariant for the reserve of one asset, given `D` and the reserve of the other
+/// asset, with Newton's method, like `get_y` in Curve.
+fn get_y(amplification: u32, reserve_x: u128, d: u128) -> Option<u128> {
+	if reserve_x == 0 {
+		return None
+	}
+	let (x, d) = (U256::from(reserve_x), U256::from(d));
+	// A * n^n with n = 2
+	let ann = U256::from(amplification).checked_mul(U256::from(4))?;
+
+	// c = D^3 / (4x * Ann * 2), b = x + D / Ann
+	let c = d.checked_mul(d)?.checked_div(x * 2)?.checked_mul(d)?.checked_div(ann.checked_mul(U256::from(2))?)?;
+	let b = x.checked_add(d.checked_div(ann)?)?;
+
+	let mut y = d;
+	for _ in 0..MAX_ITERATIONS {
+		let y_prev = y;
+		// y = (y^2 + c) / (2y + b - D)
+		let numerator = y.checked_mul(y)?.checked_add(c)?;
+		let denominator = y.checked_mul(U256::from(2))?.checked_add(b)?.checked_sub(d)?;
+		y = numerator.checked_div(denominator)?;
+		if converged(y, y_prev) {
+			return y.try_into().ok()
+		}
+	}
+	None
+}
+
+/// Newton's method has converged once an iteration moves the value by at most one unit.
+fn converged(value: U256, previous: U256) -> bool {
+	let difference = if value > previous { value - previous } else { previous - value };
+	difference <= U256::one()
+}
+
+/// The marginal price of x in y on the StableSwap curve, the ratio of the partial derivatives of the
+/// invariant: `(4Axy + d_p * y) / (4Axy + d_p * x)` with `d_p = D^3 / 4xy`.
+fn stable_spot_price(amplification: u32, reserve_x: u128, reserve_y: u128) -> Option<FixedU128> {
+	let d = U256::from(get_d(amplification, reserve_x, reserve_y)?);
+	let (x, y) = (U256::from(reserve_x), U256::from(reserve_y));
+	let d_p = d.checked_mul(d)?.checked_div(x * 2)?.checked_mul(d)?.checked_div(y * 2)?;
+	let four_a_xy = U256::from(amplification).checked_mul(U256::from(4))?.checked_mul(x)?.checked_mul(y)?;
+
+	let numerator = four_a_xy.checked_add(d_p.checked_mul(y)?)?;
+	let denominator = four_a_xy.checked_add(d_p.checked_mul(x)?)?;
+	let price = numerator.checked_mul(U256::from(FixedU128::DIV))?.checked_div(denominator)?;
+	price.try_into().ok().map(FixedU128::from_inner)
+}
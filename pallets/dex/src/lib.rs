@@ -14,10 +14,42 @@ mod tests;
 
 #[cfg(feature = "runtime-benchmarks")]
 mod benchmarking;
+pub mod concentrated;
+pub mod curve;
+pub mod migrations;
+pub mod weighted;
+pub mod weights;
+pub use curve::PoolCurve;
+pub use weights::*;
 
 // This const indicates a fee percentage, it must be a number between 1 and 100:
 //const FEE_PERCENTAGE: u32 = 5;
 
+// Native currency transferred into the pool account when the pool is created, so the account exists.
+// It is not part of the pool reserves and is returned when the pool is removed.
+const POOL_ACCOUNT_DEPOSIT: u32 = 1_000;
+
+// Like in Balancer, a trade with a weighted pool may take in at most half of the reserve of an asset and pay
+// out at most a third of it. The powers of the weighted math are only accurate within these bounds.
+const WEIGHTED_MAX_IN_RATIO: u32 = 2;
+const WEIGHTED_MAX_OUT_RATIO: u32 = 3;
+
+// The smallest weight of an asset in a weighted pool, it bounds the exponents of the weighted math.
+const WEIGHTED_MIN_WEIGHT: Perbill = Perbill::from_percent(1);
+
+// The decimals of every LP token, like the native currency of the node template.
+const LP_TOKEN_DECIMALS: u8 = 12;
+
+// The most bytes of the symbol of an asset used in the name of an LP token, so the metadata stays short.
+const LP_TOKEN_LABEL_LIMIT: usize = 8;
+
+// The most accounts and approvals of an LP token removed when it is destroyed with its pool. The accounts are
+// already gone with the burned tokens, a pool can't be removed while its LP token has more approvals.
+const LP_TOKEN_DESTROY_LIMIT: u32 = 16;
+
+// The most asset ids looked at for a free LP token id, so creating a pool reads a bounded number of assets.
+const LP_ASSET_ID_PROBES: u32 = 16;
+
 type AssetIdOf<T> = <<T as Config>::Fungibles as fungibles::Inspect<
 	<T as frame_system::Config>::AccountId,
 >>::AssetId;
@@ -32,7 +64,66 @@ type AssetBalanceOf<T> = <<T as Config>::Fungibles as fungibles::Inspect<
 
 use frame_support::traits::fungible;
 
-use sp_runtime::traits::{AccountIdConversion, CheckedAdd, CheckedSub, CheckedDiv, One, Zero};
+use serde::{Deserialize, Serialize};
+
+use sp_runtime::traits::{AccountIdConversion, CheckedAdd, CheckedSub, CheckedDiv, One, Saturating, Zero};
+
+/// Runs the logic of a flash swap, e.g. an arbitrage across pools, while the borrowed amounts are out of the pool.
+pub trait FlashSwapHandler<AccountId, AssetKind, Balance> {
+	/// Called after `amount_a_out` of `asset_a` and `amount_b_out` of `asset_b` have been transferred from
+	/// `pool_account` to `who`. Before it returns enough has to be paid back to `pool_account` to restore
+	/// the constant product of the pool, including the fee on the amounts paid back.
+	fn on_flash_swap(
+		who: &AccountId,
+		pool_account: &AccountId,
+		asset_a: &AssetKind,
+		asset_b: &AssetKind,
+		amount_a_out: Balance,
+		amount_b_out: Balance,
+	) -> sp_runtime::DispatchResult;
+}
+
+/// Runs no logic, so a flash swap only succeeds if the pool account already holds enough on top of its reserves.
+impl<AccountId, AssetKind, Balance> FlashSwapHandler<AccountId, AssetKind, Balance> for () {
+	fn on_flash_swap(
+		_who: &AccountId,
+		_pool_account: &AccountId,
+		_asset_a: &AssetKind,
+		_asset_b: &AssetKind,
+		_amount_a_out: Balance,
+		_amount_b_out: Balance,
+	) -> sp_runtime::DispatchResult {
+		Ok(())
+	}
+}
+
+/// The `CreateOrigin` of `pallet_assets` for a runtime with this pallet. It lets `Inner` create assets with the
+/// ids below `LpAssetIdStart` only, so the ids of the LP tokens can't be taken by anyone but the pallet.
+pub struct EnsureBelowLpAssetIds<T, Inner>(PhantomData<(T, Inner)>);
+
+impl<O, AssetId, T, Inner> frame_support::traits::EnsureOriginWithArg<O, AssetId> for EnsureBelowLpAssetIds<T, Inner>
+where
+	T: Config,
+	Inner: EnsureOrigin<O>,
+	AssetId: Clone + Into<u32>,
+{
+	type Success = Inner::Success;
+
+	fn try_origin(o: O, id: &AssetId) -> Result<Self::Success, O> {
+		if id.clone().into() >= T::LpAssetIdStart::get() {
+			return Err(o)
+		}
+		Inner::try_origin(o)
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	fn try_successful_origin(id: &AssetId) -> Result<O, ()> {
+		if id.clone().into() >= T::LpAssetIdStart::get() {
+			return Err(())
+		}
+		Inner::try_successful_origin()
+	}
+}
 
 #[frame_support::pallet]
 pub mod pallet {
@@ -40,7 +131,12 @@ pub mod pallet {
 	use frame_support::traits::{fungible, fungibles};
 	use frame_system::pallet_prelude::*;
 
+	/// The in-code storage version. Bump it together with a migration in [`crate::migrations`] whenever the
+	/// layout of the storage changes.
+	const STORAGE_VERSION: StorageVersion = StorageVersion::new(2);
+
 	#[pallet::pallet]
+	#[pallet::storage_version(STORAGE_VERSION)]
 	pub struct Pallet<T>(_);
 
 	/// Configure the pallet by specifying the parameters and types on which it depends.
@@ -60,56 +156,795 @@ pub mod pallet {
 		/// Type to access the Assets Pallet.
 		type Fungibles: fungibles::Inspect<Self::AccountId, Balance = BalanceOf<Self>>
 			+ fungibles::Mutate<Self::AccountId>
-			+ fungibles::Create<Self::AccountId>;
+			+ fungibles::Create<Self::AccountId>
+			+ fungibles::Destroy<Self::AccountId>
+			+ fungibles::metadata::Inspect<Self::AccountId>
+			+ fungibles::metadata::Mutate<Self::AccountId>;
 
-		/// Minimal deposit to create the pool
+		/// Minimal deposit of each asset to create the pool
+		#[pallet::constant]
 		type MinPoolDeposit: Get<u32>;
 
-		type FeePercentage: Get<u32>;
+		/// The LP tokens minted to the pool account and locked forever when a pool is created, so the
+		/// pool can never be emptied and the price of its LP token can't be inflated by the first depositor.
+		#[pallet::constant]
+		type MinimumLiquidity: Get<AssetBalanceOf<Self>>;
+
+		/// The fee tier of the pools used through `DexInterface`, it is always allowed.
+		#[pallet::constant]
+		type DefaultFeeTier: Get<Permill>;
+
+		/// The asset id of the first LP token, as a `u32` decoded into an asset id. LP tokens take the ids from
+		/// there on and skip the ids of existing assets, the runtime should keep the range free for them.
+		#[pallet::constant]
+		type LpAssetIdStart: Get<u32>;
+
+		/// The native currency the creator of a pool pays for the metadata of its LP token. It has to cover the
+		/// metadata deposit of `Fungibles`, and is paid back to whoever removes the pool.
+		#[pallet::constant]
+		type LpMetadataDeposit: Get<BalanceOf<Self>>;
+
+		/// The maximum number of fee tiers governance can allow besides the default one.
+		#[pallet::constant]
+		type MaxFeeTiers: Get<u32>;
+
+		/// The maximum amplification coefficient of a StableSwap pool.
+		#[pallet::constant]
+		type MaxAmplification: Get<u32>;
+
+		/// The maximum number of assets in a weighted pool, at least 2.
+		#[pallet::constant]
+		type MaxWeightedPoolAssets: Get<u32>;
+
+		/// The maximum number of initialized ticks of a concentrated liquidity pool, it bounds the ticks a
+		/// swap can cross.
+		#[pallet::constant]
+		type MaxConcentratedTicks: Get<u32>;
+
+		/// The maximum number of assets in a swap path, including the input and output assets.
+		#[pallet::constant]
+		type MaxSwapPathLength: Get<u32>;
+
+		/// The maximum number of price observations kept per pool for the TWAP oracle.
+		/// A new observation is recorded in every block in which the pool's reserves change.
+		#[pallet::constant]
+		type MaxPriceObservations: Get<u32>;
+
+		/// The maximum number of open limit orders of an account.
+		#[pallet::constant]
+		type MaxLimitOrders: Get<u32>;
+
+		/// The maximum number of swap intents a pool in batch mode collects in a block.
+		#[pallet::constant]
+		type MaxSwapIntents: Get<u32>;
 
 		/// Origin for admin-level operations, like creating the pool.
 		type CreatePoolOrigin: EnsureOrigin<Self::RuntimeOrigin, Success = Self::AccountId>;
 
+		/// Origin allowed to change the protocol fee and the fee tiers, e.g. the DAO of the chain.
+		type GovernanceOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+		/// Origin allowed to pause and unpause pools in an emergency, e.g. a technical committee that can act
+		/// faster than governance.
+		type PauseOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+		/// Runs the logic of flash swaps while the borrowed amounts are out of the pool.
+		type FlashSwapHandler: FlashSwapHandler<Self::AccountId, BalanceType<Self>, AssetBalanceOf<Self>>;
+
 		/// The DEX's pallet id, used for deriving its sovereign account ID.
 		#[pallet::constant]
 		type PalletId: Get<frame_support::PalletId>;
+
+		/// Weight information for extrinsics in this pallet.
+		type WeightInfo: WeightInfo;
 	}
 
-	/// native token balance
-	#[derive(Encode, Decode, MaxEncodedLen, TypeInfo)]
+	/// An asset that can be traded in a pool, either the native currency or an asset of `T::Fungibles`.
+	#[derive(
+		CloneNoBound, PartialEqNoBound, EqNoBound, RuntimeDebugNoBound, Encode, Decode, MaxEncodedLen, TypeInfo,
+		Serialize, Deserialize,
+	)]
 	#[scale_info(skip_type_params(T))]
+	#[serde(
+		rename_all = "camelCase",
+		bound(serialize = "AssetIdOf<T>: Serialize", deserialize = "AssetIdOf<T>: Deserialize<'de>")
+	)]
 	pub enum BalanceType<T: Config> {
+		/// The native currency, moved through `T::NativeBalance`.
 		NativeBalance,
+		/// An asset, moved through `T::Fungibles`.
 		AssetBalance(AssetIdOf<T>),
-	}      
+	}
+
+	/// Identifies a pool by its ordered assets and its fee tier, the same pair can have a pool for every fee tier.
+	pub type PoolKeyOf<T> = (BalanceType<T>, BalanceType<T>, Permill);
+
+	/// Identifies a weighted pool, they are numbered in the order they are created.
+	pub type WeightedPoolId = u32;
+
+	/// Identifies a concentrated liquidity pool, they are numbered in the order they are created.
+	pub type ConcentratedPoolId = u32;
+
+	/// Identifies a position in a concentrated liquidity pool, they are numbered in the order they are minted.
+	pub type PositionId = u32;
+
+	/// Identifies a limit order, they are numbered in the order they are placed.
+	pub type OrderId = u32;
+
+	/// The state of a liquidity pool. The reserves are tracked here instead of being read from the
+	/// balances of the pool account, so tokens donated to the pool account don't move the price.
+	#[derive(CloneNoBound, PartialEqNoBound, EqNoBound, RuntimeDebugNoBound, Encode, Decode, MaxEncodedLen, TypeInfo)]
+	#[scale_info(skip_type_params(T))]
+	pub struct LiquidityPool<T: Config> {
+		/// The account holding the assets of the pool.
+		pub pool_account: T::AccountId,
+		/// The id of the LP token of the pool.
+		pub liquidity_id: AssetIdOf<T>,
+		/// The tracked reserve of asset_a.
+		pub reserve_a: AssetBalanceOf<T>,
+		/// The tracked reserve of asset_b.
+		pub reserve_b: AssetBalanceOf<T>,
+		/// The account that created the pool.
+		pub creator: T::AccountId,
+		/// The block in which the pool was created.
+		pub created_at: BlockNumberFor<T>,
+		/// The fee tier of the pool, taken from every swap in this pool.
+		pub fee: Permill,
+		/// The invariant the pool keeps during swaps.
+		pub curve: PoolCurve,
+		/// The sum of the prices of asset_a in asset_b, multiplied by the number of blocks each price lasted.
+		/// It wraps on overflow like in Uniswap V2, only the difference between two values is meaningful.
+		pub price_a_cumulative: FixedU128,
+		/// The sum of the prices of asset_b in asset_a, multiplied by the number of blocks each price lasted.
+		pub price_b_cumulative: FixedU128,
+		/// The block in which the cumulative prices were last updated.
+		pub last_oracle_update: BlockNumberFor<T>,
+		/// `k` of the curve of the pool right after the last liquidity event, used to calculate the protocol fee.
+		/// It is zero while the protocol fee is switched off.
+		pub k_last: AssetBalanceOf<T>,
+	}
+
+	/// An asset of a weighted pool with its normalized weight and its tracked reserve.
+	#[derive(CloneNoBound, PartialEqNoBound, EqNoBound, RuntimeDebugNoBound, Encode, Decode, MaxEncodedLen, TypeInfo)]
+	#[scale_info(skip_type_params(T))]
+	pub struct WeightedAsset<T: Config> {
+		/// The asset.
+		pub asset: BalanceType<T>,
+		/// The share of the value of the pool held in the asset, the weights of a pool add up to 100%.
+		pub weight: Perbill,
+		/// The tracked reserve of the asset.
+		pub reserve: AssetBalanceOf<T>,
+	}
 
-	// #[derive(Encode, Decode, MaxEncodedLen, TypeInfo)]
-	// pub struct LiquidityPool<T: Config> {
-	// 	pub liquidity_id: AssetIdOf<T>,
-	// 	pub reserve_liq: AssetBalanceOf<T>,
-	// 	pub reserve_a: AssetBalanceOf<T>,
-	// 	pub reserve_b: AssetBalanceOf<T>,
-	// }
+	/// The state of a weighted pool of 2 up to `MaxWeightedPoolAssets` assets. Swaps keep
+	/// `prod(reserve_i ^ weight_i)` constant, like in Balancer.
+	#[derive(CloneNoBound, PartialEqNoBound, EqNoBound, RuntimeDebugNoBound, Encode, Decode, MaxEncodedLen, TypeInfo)]
+	#[scale_info(skip_type_params(T))]
+	pub struct WeightedPool<T: Config> {
+		/// The account holding the assets of the pool.
+		pub pool_account: T::AccountId,
+		/// The id of the LP token of the pool.
+		pub liquidity_id: AssetIdOf<T>,
+		/// The assets of the pool, in the order the pool was created with.
+		pub assets: BoundedVec<WeightedAsset<T>, T::MaxWeightedPoolAssets>,
+		/// The fee of the pool, taken from every swap and from single asset joins and exits.
+		pub fee: Permill,
+		/// The account that created the pool.
+		pub creator: T::AccountId,
+	}
+
+	/// The state of a concentrated liquidity pool like in Uniswap V3. Liquidity is provided within price ranges
+	/// between two ticks, a swap only trades against the liquidity of the ranges containing the current price.
+	#[derive(CloneNoBound, PartialEqNoBound, EqNoBound, RuntimeDebugNoBound, Encode, Decode, MaxEncodedLen, TypeInfo)]
+	#[scale_info(skip_type_params(T))]
+	pub struct ConcentratedPool<T: Config> {
+		/// The account holding the assets of the pool.
+		pub pool_account: T::AccountId,
+		/// The asset whose price is tracked, in asset_1.
+		pub asset_0: BalanceType<T>,
+		/// The asset the price is denominated in.
+		pub asset_1: BalanceType<T>,
+		/// The fee tier of the pool, taken from every swap and shared by the positions in range.
+		pub fee: Permill,
+		/// Positions can only start and end at multiples of the tick spacing.
+		pub tick_spacing: u32,
+		/// The square root of the price of asset_0 in asset_1 in Q64.64.
+		pub sqrt_price: u128,
+		/// The highest tick whose price is at most the current price.
+		pub tick: i32,
+		/// The liquidity of the positions whose range contains the current price.
+		pub liquidity: u128,
+		/// The fees of asset_0 earned per unit of liquidity since the pool was created, in Q64.64.
+		pub fee_growth_global_0: u128,
+		/// The fees of asset_1 earned per unit of liquidity since the pool was created, in Q64.64.
+		pub fee_growth_global_1: u128,
+		/// The account that created the pool.
+		pub creator: T::AccountId,
+	}
+
+	/// The state of an initialized tick, a tick where at least one position starts or ends.
+	#[derive(Clone, Copy, PartialEq, Eq, Default, RuntimeDebug, Encode, Decode, MaxEncodedLen, TypeInfo)]
+	pub struct TickInfo {
+		/// The liquidity of all the positions starting or ending at the tick.
+		pub liquidity_gross: u128,
+		/// The liquidity added to the pool when the price crosses the tick upwards, removed when it crosses
+		/// the tick downwards.
+		pub liquidity_net: i128,
+		/// The fee growth of asset_0 on the other side of the tick from the current price.
+		pub fee_growth_outside_0: u128,
+		/// The fee growth of asset_1 on the other side of the tick from the current price.
+		pub fee_growth_outside_1: u128,
+	}
+
+	/// Liquidity provided to a concentrated liquidity pool between two ticks.
+	#[derive(CloneNoBound, PartialEqNoBound, EqNoBound, RuntimeDebugNoBound, Encode, Decode, MaxEncodedLen, TypeInfo)]
+	#[scale_info(skip_type_params(T))]
+	pub struct Position<T: Config> {
+		/// The pool of the position.
+		pub pool_id: ConcentratedPoolId,
+		/// The tick the range of the position starts at.
+		pub tick_lower: i32,
+		/// The tick the range of the position ends at.
+		pub tick_upper: i32,
+		/// The liquidity of the position, it is only used while the price is in its range.
+		pub liquidity: u128,
+		/// The fee growth of asset_0 within the range when the fees of the position were last updated.
+		pub fee_growth_inside_0_last: u128,
+		/// The fee growth of asset_1 within the range when the fees of the position were last updated.
+		pub fee_growth_inside_1_last: u128,
+		/// The fees and the removed liquidity of asset_0 that can be collected.
+		pub tokens_owed_0: AssetBalanceOf<T>,
+		/// The fees and the removed liquidity of asset_1 that can be collected.
+		pub tokens_owed_1: AssetBalanceOf<T>,
+	}
+
+	/// An order to sell `amount_in` of `asset_in` for at least `limit_price` of `asset_out` per unit in the pool
+	/// with the fee tier `fee`. The amount is held in the escrow account until the order is filled or cancelled.
+	#[derive(CloneNoBound, PartialEqNoBound, EqNoBound, RuntimeDebugNoBound, Encode, Decode, MaxEncodedLen, TypeInfo)]
+	#[scale_info(skip_type_params(T))]
+	pub struct LimitOrder<T: Config> {
+		/// The account that placed the order and receives the proceeds.
+		pub owner: T::AccountId,
+		/// The asset sold.
+		pub asset_in: BalanceType<T>,
+		/// The asset bought.
+		pub asset_out: BalanceType<T>,
+		/// The fee tier of the pool the order is filled in.
+		pub fee: Permill,
+		/// The amount sold, all of it is swapped at once.
+		pub amount_in: AssetBalanceOf<T>,
+		/// The least amount of `asset_out` per unit of `asset_in`.
+		pub limit_price: FixedU128,
+		/// The last block the order can be filled in.
+		pub expires_at: BlockNumberFor<T>,
+	}
+
+	/// A swap submitted to a pool in batch mode, cleared with the other intents of the block in `on_finalize`.
+	#[derive(CloneNoBound, PartialEqNoBound, EqNoBound, RuntimeDebugNoBound, Encode, Decode, MaxEncodedLen, TypeInfo)]
+	#[scale_info(skip_type_params(T))]
+	pub struct SwapIntent<T: Config> {
+		/// The account that submitted the intent and receives its share of the batch.
+		pub who: T::AccountId,
+		/// The asset sold, the other asset of the pool is bought.
+		pub asset_in: BalanceType<T>,
+		/// The amount sold, held in the escrow account until the batch is cleared.
+		pub amount_in: AssetBalanceOf<T>,
+		/// The least amount of the other asset to receive, otherwise the intent is paid back.
+		pub min_out: AssetBalanceOf<T>,
+	}
+
+	/// What a pause blocks in a pool, or in all the pools. `remove_liquidity` is never paused, so LPs can
+	/// always exit.
+	#[derive(Clone, Copy, PartialEq, Eq, Default, RuntimeDebug, Encode, Decode, MaxEncodedLen, TypeInfo)]
+	pub struct PauseFlags {
+		/// Swaps of any kind fail, including flash swaps, limit order fills and swap intents.
+		pub swaps: bool,
+		/// Adding liquidity fails.
+		pub adds: bool,
+	}
+
+	/// The cumulative prices of a pool at the first interaction with the pool in a block.
+	#[derive(Clone, Copy, PartialEq, Eq, RuntimeDebug, Encode, Decode, MaxEncodedLen, TypeInfo)]
+	pub struct PriceObservation<BlockNumber> {
+		/// The block in which the observation was recorded.
+		pub block: BlockNumber,
+		/// The cumulative price of asset_a at `block`.
+		pub price_a_cumulative: FixedU128,
+		/// The cumulative price of asset_b at `block`.
+		pub price_b_cumulative: FixedU128,
+	}
 
 	// The pallet's runtime storage items.
 	// https://docs.substrate.io/main-docs/build/runtime-storage/
 	#[pallet::storage]
-	#[pallet::getter(fn something)]
+	#[pallet::getter(fn liquidity_pool)]
 	// Learn more about declaring storage items:
 	// https://docs.substrate.io/main-docs/build/runtime-storage/#declaring-storage-items
 	pub type LiquidityPoolMap<T: Config> =
-		StorageMap<_, Blake2_128Concat, (AssetIdOf<T>, AssetIdOf<T>), T::AccountId>;
+		StorageMap<_, Blake2_128Concat, PoolKeyOf<T>, LiquidityPool<T>>;
+
+	/// The most recent price observations of every pool, oldest first. They are used to calculate
+	/// the time-weighted average price over a window of blocks.
+	#[pallet::storage]
+	#[pallet::getter(fn price_observations)]
+	pub type PriceObservations<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		PoolKeyOf<T>,
+		BoundedVec<PriceObservation<BlockNumberFor<T>>, T::MaxPriceObservations>,
+		ValueQuery,
+	>;
+
+	/// The account that receives the protocol's share of the swap fees, as LP tokens of the pools.
+	#[pallet::storage]
+	#[pallet::getter(fn protocol_fee_recipient)]
+	pub type ProtocolFeeRecipient<T: Config> = StorageValue<_, T::AccountId, OptionQuery>;
+
+	/// The share of the swap fees that goes to the protocol, zero switches the protocol fee off.
+	#[pallet::storage]
+	#[pallet::getter(fn protocol_fee_share)]
+	pub type ProtocolFeeShare<T: Config> = StorageValue<_, Percent, ValueQuery>;
+
+	/// The fee tiers pools can be created with besides `T::DefaultFeeTier`, managed by governance.
+	#[pallet::storage]
+	#[pallet::getter(fn fee_tiers)]
+	pub type FeeTiers<T: Config> = StorageValue<_, BoundedVec<Permill, T::MaxFeeTiers>, ValueQuery>;
+
+	/// The next id an LP token may take, as a `u32` decoded into an asset id. It starts at `LpAssetIdStart`.
+	#[pallet::storage]
+	pub type NextLpAssetId<T: Config> = StorageValue<_, u32, OptionQuery>;
+
+	/// The weighted pools by their id.
+	#[pallet::storage]
+	#[pallet::getter(fn weighted_pool)]
+	pub type WeightedPools<T: Config> = StorageMap<_, Blake2_128Concat, WeightedPoolId, WeightedPool<T>>;
+
+	/// The id of the next weighted pool.
+	#[pallet::storage]
+	pub type NextWeightedPoolId<T: Config> = StorageValue<_, WeightedPoolId, ValueQuery>;
+
+	/// The concentrated liquidity pools by their id.
+	#[pallet::storage]
+	#[pallet::getter(fn concentrated_pool)]
+	pub type ConcentratedPools<T: Config> = StorageMap<_, Blake2_128Concat, ConcentratedPoolId, ConcentratedPool<T>>;
+
+	/// The id of the next concentrated liquidity pool.
+	#[pallet::storage]
+	pub type NextConcentratedPoolId<T: Config> = StorageValue<_, ConcentratedPoolId, ValueQuery>;
+
+	/// The initialized ticks of every concentrated liquidity pool.
+	#[pallet::storage]
+	#[pallet::getter(fn tick)]
+	pub type Ticks<T: Config> =
+		StorageDoubleMap<_, Blake2_128Concat, ConcentratedPoolId, Blake2_128Concat, i32, TickInfo>;
+
+	/// The initialized ticks of every concentrated liquidity pool in ascending order, a swap walks them to
+	/// find the next tick to cross.
+	#[pallet::storage]
+	#[pallet::getter(fn initialized_ticks)]
+	pub type InitializedTicks<T: Config> =
+		StorageMap<_, Blake2_128Concat, ConcentratedPoolId, BoundedVec<i32, T::MaxConcentratedTicks>, ValueQuery>;
+
+	/// The positions in concentrated liquidity pools by their owner and id.
+	#[pallet::storage]
+	#[pallet::getter(fn position)]
+	pub type Positions<T: Config> =
+		StorageDoubleMap<_, Blake2_128Concat, T::AccountId, Blake2_128Concat, PositionId, Position<T>>;
+
+	/// The id of the next position.
+	#[pallet::storage]
+	pub type NextPositionId<T: Config> = StorageValue<_, PositionId, ValueQuery>;
+
+	/// The open limit orders by their id.
+	#[pallet::storage]
+	#[pallet::getter(fn limit_order)]
+	pub type LimitOrders<T: Config> = StorageMap<_, Blake2_128Concat, OrderId, LimitOrder<T>>;
+
+	/// The id of the next limit order.
+	#[pallet::storage]
+	pub type NextOrderId<T: Config> = StorageValue<_, OrderId, ValueQuery>;
+
+	/// The number of open limit orders of every account, at most `MaxLimitOrders`.
+	#[pallet::storage]
+	pub type OpenOrdersOf<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, u32, ValueQuery>;
+
+	/// The raw storage key of the last limit order checked in `on_idle`, the next block continues after it.
+	/// It is cleared once all the open orders have been checked.
+	#[pallet::storage]
+	pub type OrderMatchingCursor<T: Config> = StorageValue<_, BoundedVec<u8, ConstU32<128>>, OptionQuery>;
+
+	/// The pools that only swap in batches cleared at a uniform price, set by the governance origin.
+	#[pallet::storage]
+	#[pallet::getter(fn batch_mode)]
+	pub type BatchModePools<T: Config> = StorageMap<_, Blake2_128Concat, PoolKeyOf<T>, bool, ValueQuery>;
+
+	/// The swap intents submitted to the pools in batch mode in the current block, cleared in `on_finalize`.
+	#[pallet::storage]
+	pub type SwapIntents<T: Config> =
+		StorageMap<_, Blake2_128Concat, PoolKeyOf<T>, BoundedVec<SwapIntent<T>, T::MaxSwapIntents>, ValueQuery>;
+
+	/// The amounts of swap intents that couldn't be paid back in `on_finalize`, by account and asset. They stay
+	/// in the escrow account until the account claims them with `claim_swap_intent_refund`.
+	#[pallet::storage]
+	pub type UnclaimedRefunds<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		Blake2_128Concat,
+		BalanceType<T>,
+		AssetBalanceOf<T>,
+		ValueQuery,
+	>;
+
+	/// The pools paused by the pause origin and what is paused in them.
+	#[pallet::storage]
+	#[pallet::getter(fn paused_pool)]
+	pub type PausedPools<T: Config> = StorageMap<_, Blake2_128Concat, PoolKeyOf<T>, PauseFlags, ValueQuery>;
+
+	/// The weighted pools paused by the pause origin and what is paused in them.
+	#[pallet::storage]
+	#[pallet::getter(fn paused_weighted_pool)]
+	pub type PausedWeightedPools<T: Config> = StorageMap<_, Blake2_128Concat, WeightedPoolId, PauseFlags, ValueQuery>;
+
+	/// The concentrated liquidity pools paused by the pause origin and what is paused in them.
+	#[pallet::storage]
+	#[pallet::getter(fn paused_concentrated_pool)]
+	pub type PausedConcentratedPools<T: Config> =
+		StorageMap<_, Blake2_128Concat, ConcentratedPoolId, PauseFlags, ValueQuery>;
+
+	/// What is paused in all the pools, including the weighted and concentrated liquidity pools.
+	#[pallet::storage]
+	#[pallet::getter(fn global_pause)]
+	pub type GlobalPause<T: Config> = StorageValue<_, PauseFlags, ValueQuery>;
+
+	/// The fee tiers and the pools a chain starts with, e.g. so a dev chain comes up with funded pools.
+	/// The assets and the balances of the LP holders have to be set up by the genesis of their pallets.
+	#[pallet::genesis_config]
+	#[derive(frame_support::DefaultNoBound)]
+	pub struct GenesisConfig<T: Config>
+	where
+		AssetIdOf<T>: Serialize + for<'a> Deserialize<'a>,
+		AssetBalanceOf<T>: Serialize + for<'a> Deserialize<'a>,
+	{
+		/// The fee tiers allowed on top of `DefaultFeeTier`.
+		pub fee_tiers: Vec<Permill>,
+		/// The constant product pools, created in order from the two assets, the fee tier and the initial
+		/// amounts of the account that provides them and receives the LP tokens.
+		pub pools: Vec<(BalanceType<T>, BalanceType<T>, Permill, AssetBalanceOf<T>, AssetBalanceOf<T>, T::AccountId)>,
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> BuildGenesisConfig for GenesisConfig<T>
+	where
+		AssetIdOf<T>: Serialize + for<'a> Deserialize<'a>,
+		AssetBalanceOf<T>: Serialize + for<'a> Deserialize<'a>,
+	{
+		fn build(&self) {
+			let fee_tiers: BoundedVec<Permill, T::MaxFeeTiers> =
+				self.fee_tiers.clone().try_into().expect("at most `MaxFeeTiers` fee tiers at genesis");
+			FeeTiers::<T>::put(fee_tiers);
+
+			for (asset_a, asset_b, fee, amount_a, amount_b, lp_holder) in &self.pools {
+				Pallet::<T>::do_create_pool(
+					lp_holder,
+					asset_a.clone(),
+					asset_b.clone(),
+					*fee,
+					PoolCurve::ConstantProduct,
+					*amount_a,
+					*amount_b,
+				)
+				.expect("the genesis pools are valid and their LP holders hold the initial amounts");
+			}
+		}
+	}
 
 	// Pallets use events to inform users when important changes are made.
 	// https://docs.substrate.io/main-docs/build/events-errors/
 	#[pallet::event]
 	#[pallet::generate_deposit(pub(super) fn deposit_event)]
 	pub enum Event<T: Config> {
-		/// A liquidity pool has been created.
-		PoolCreated { asset_a: AssetIdOf<T>, asset_b: AssetIdOf<T>, liquidity_id: AssetIdOf<T> },
-		LiquidityAdded { asset_a: AssetIdOf<T>, asset_b: AssetIdOf<T>, liquidity_id: AssetIdOf<T> },
-		LiquidityRemoved { asset_a: AssetIdOf<T>, asset_b: AssetIdOf<T>, liquidity_id: AssetIdOf<T>, amount_liq: AssetBalanceOf<T>},
-		PoolRemoved { asset_a: AssetIdOf<T>, asset_b: AssetIdOf<T>, liquidity_id: AssetIdOf<T> },
+		/// A liquidity pool has been created with the amounts deposited by `who`, who got `amount_liq` LP tokens.
+		/// The events of a pool of a pair carry its `pool_id`, the id of its LP token. It is drawn from a counter,
+		/// so a pool created again for the same pair and fee tier gets a new id.
+		PoolCreated {
+			who: T::AccountId,
+			pool_id: AssetIdOf<T>,
+			asset_a: BalanceType<T>,
+			asset_b: BalanceType<T>,
+			fee: Permill,
+			amount_a: AssetBalanceOf<T>,
+			amount_b: AssetBalanceOf<T>,
+			amount_liq: AssetBalanceOf<T>,
+		},
+		/// Liquidity has been added to a pool, the amounts are the ones actually deposited.
+		LiquidityAdded {
+			who: T::AccountId,
+			pool_id: AssetIdOf<T>,
+			asset_a: BalanceType<T>,
+			asset_b: BalanceType<T>,
+			fee: Permill,
+			amount_a: AssetBalanceOf<T>,
+			amount_b: AssetBalanceOf<T>,
+			amount_liq: AssetBalanceOf<T>,
+		},
+		/// LP tokens have been burned for their share of the reserves of a pool.
+		LiquidityRemoved {
+			who: T::AccountId,
+			pool_id: AssetIdOf<T>,
+			asset_a: BalanceType<T>,
+			asset_b: BalanceType<T>,
+			fee: Permill,
+			amount_liq: AssetBalanceOf<T>,
+			amount_a: AssetBalanceOf<T>,
+			amount_b: AssetBalanceOf<T>,
+		},
+		/// A pool has been removed, its balances have been paid out for the LP tokens that were left.
+		/// A pool emptied by `remove_liquidity` is removed with zero amounts, after its `LiquidityRemoved`.
+		PoolRemoved {
+			who: T::AccountId,
+			pool_id: AssetIdOf<T>,
+			asset_a: BalanceType<T>,
+			asset_b: BalanceType<T>,
+			fee: Permill,
+			amount_liq: AssetBalanceOf<T>,
+			amount_a: AssetBalanceOf<T>,
+			amount_b: AssetBalanceOf<T>,
+		},
+		/// Two assets have been swapped in a pool of a pair. `fee` is the part of `amount_in` the pool kept as
+		/// its fee, the reserves are the tracked reserves of the pool after the swap.
+		Swapped {
+			who: T::AccountId,
+			pool_id: AssetIdOf<T>,
+			asset_in: BalanceType<T>,
+			asset_out: BalanceType<T>,
+			amount_in: AssetBalanceOf<T>,
+			amount_out: AssetBalanceOf<T>,
+			fee: AssetBalanceOf<T>,
+			reserve_in_after: AssetBalanceOf<T>,
+			reserve_out_after: AssetBalanceOf<T>,
+		},
+		/// The balances of the pool account exceeding the tracked reserves have been transferred out.
+		PoolSkimmed {
+			who: T::AccountId,
+			pool_id: AssetIdOf<T>,
+			asset_a: BalanceType<T>,
+			asset_b: BalanceType<T>,
+			fee: Permill,
+			amount_a: AssetBalanceOf<T>,
+			amount_b: AssetBalanceOf<T>,
+		},
+		/// The tracked reserves have been set to the balances of the pool account.
+		PoolSynced {
+			who: T::AccountId,
+			pool_id: AssetIdOf<T>,
+			asset_a: BalanceType<T>,
+			asset_b: BalanceType<T>,
+			fee: Permill,
+			reserve_a: AssetBalanceOf<T>,
+			reserve_b: AssetBalanceOf<T>,
+		},
+		/// The protocol fee recipient or its share of the swap fees has been changed.
+		ProtocolFeeSet { recipient: Option<T::AccountId>, share: Percent },
+		/// The protocol's share of the swap fees of a pool has been minted as LP tokens to the recipient.
+		ProtocolFeeMinted {
+			pool_id: AssetIdOf<T>,
+			asset_a: BalanceType<T>,
+			asset_b: BalanceType<T>,
+			fee: Permill,
+			recipient: T::AccountId,
+			amount: AssetBalanceOf<T>,
+		},
+		/// The fee tiers allowed besides the default one have been changed.
+		FeeTiersSet { fee_tiers: BoundedVec<Permill, T::MaxFeeTiers> },
+		/// Assets have been borrowed from a pool and paid back within the same flash swap.
+		FlashSwapped {
+			who: T::AccountId,
+			pool_id: AssetIdOf<T>,
+			asset_a: BalanceType<T>,
+			asset_b: BalanceType<T>,
+			fee: Permill,
+			amount_a_out: AssetBalanceOf<T>,
+			amount_b_out: AssetBalanceOf<T>,
+			amount_a_in: AssetBalanceOf<T>,
+			amount_b_in: AssetBalanceOf<T>,
+		},
+		/// A weighted pool has been created with the amounts deposited by `who`, who got `amount_liq` LP tokens.
+		WeightedPoolCreated {
+			who: T::AccountId,
+			pool_id: WeightedPoolId,
+			liquidity_id: AssetIdOf<T>,
+			fee: Permill,
+			amounts: BoundedVec<(BalanceType<T>, AssetBalanceOf<T>), T::MaxWeightedPoolAssets>,
+			amount_liq: AssetBalanceOf<T>,
+		},
+		/// A single asset has been deposited into a weighted pool for LP tokens.
+		WeightedPoolJoined {
+			who: T::AccountId,
+			pool_id: WeightedPoolId,
+			asset: BalanceType<T>,
+			amount_in: AssetBalanceOf<T>,
+			amount_liq: AssetBalanceOf<T>,
+		},
+		/// LP tokens of a weighted pool have been burned for a single asset.
+		WeightedPoolExited {
+			who: T::AccountId,
+			pool_id: WeightedPoolId,
+			asset: BalanceType<T>,
+			amount_liq: AssetBalanceOf<T>,
+			amount_out: AssetBalanceOf<T>,
+		},
+		/// Two assets of a weighted pool have been swapped. `fee` is the part of `amount_in` the pool kept as
+		/// its fee, the reserves are the tracked reserves of both assets after the swap.
+		WeightedSwapped {
+			who: T::AccountId,
+			pool_id: WeightedPoolId,
+			asset_in: BalanceType<T>,
+			asset_out: BalanceType<T>,
+			amount_in: AssetBalanceOf<T>,
+			amount_out: AssetBalanceOf<T>,
+			fee: AssetBalanceOf<T>,
+			reserve_in_after: AssetBalanceOf<T>,
+			reserve_out_after: AssetBalanceOf<T>,
+		},
+		/// A concentrated liquidity pool has been created at the square root price `sqrt_price` in Q64.64.
+		ConcentratedPoolCreated {
+			who: T::AccountId,
+			pool_id: ConcentratedPoolId,
+			asset_0: BalanceType<T>,
+			asset_1: BalanceType<T>,
+			fee: Permill,
+			tick_spacing: u32,
+			sqrt_price: u128,
+		},
+		/// Liquidity has been provided to a concentrated liquidity pool between two ticks.
+		PositionMinted {
+			who: T::AccountId,
+			pool_id: ConcentratedPoolId,
+			position_id: PositionId,
+			tick_lower: i32,
+			tick_upper: i32,
+			liquidity: u128,
+			amount_0: AssetBalanceOf<T>,
+			amount_1: AssetBalanceOf<T>,
+		},
+		/// Liquidity has been removed from a position, the amounts can be collected.
+		LiquidityDecreased {
+			who: T::AccountId,
+			pool_id: ConcentratedPoolId,
+			position_id: PositionId,
+			liquidity: u128,
+			amount_0: AssetBalanceOf<T>,
+			amount_1: AssetBalanceOf<T>,
+		},
+		/// The fees and the removed liquidity of a position have been paid out.
+		PositionCollected {
+			who: T::AccountId,
+			pool_id: ConcentratedPoolId,
+			position_id: PositionId,
+			amount_0: AssetBalanceOf<T>,
+			amount_1: AssetBalanceOf<T>,
+		},
+		/// Two assets have been swapped in a concentrated liquidity pool. `fee` is the part of `amount_in` paid
+		/// to the liquidity in range. The pool doesn't track reserves, its state after the swap is its square
+		/// root price in Q64.64, the liquidity in range and the current tick, like in Uniswap V3.
+		ConcentratedSwapped {
+			who: T::AccountId,
+			pool_id: ConcentratedPoolId,
+			asset_in: BalanceType<T>,
+			asset_out: BalanceType<T>,
+			amount_in: AssetBalanceOf<T>,
+			amount_out: AssetBalanceOf<T>,
+			fee: AssetBalanceOf<T>,
+			sqrt_price_after: u128,
+			liquidity_after: u128,
+			tick_after: i32,
+		},
+		/// A limit order has been placed, its amount is held until it is filled or cancelled.
+		LimitOrderPlaced {
+			order_id: OrderId,
+			owner: T::AccountId,
+			pool_id: AssetIdOf<T>,
+			asset_in: BalanceType<T>,
+			asset_out: BalanceType<T>,
+			fee: Permill,
+			amount_in: AssetBalanceOf<T>,
+			limit_price: FixedU128,
+			expires_at: BlockNumberFor<T>,
+		},
+		/// A limit order has been swapped in its pool, the proceeds have been paid to its owner.
+		LimitOrderFilled {
+			order_id: OrderId,
+			owner: T::AccountId,
+			pool_id: AssetIdOf<T>,
+			amount_in: AssetBalanceOf<T>,
+			amount_out: AssetBalanceOf<T>,
+		},
+		/// A limit order has been cancelled, its amount has been paid back to its owner. The pool id is `None`
+		/// if the pool of the order has been removed since it was placed.
+		LimitOrderCancelled {
+			order_id: OrderId,
+			owner: T::AccountId,
+			pool_id: Option<AssetIdOf<T>>,
+			amount_in: AssetBalanceOf<T>,
+		},
+		/// A limit order has expired without being filled, its amount has been paid back to its owner. The pool
+		/// id is `None` if the pool of the order has been removed since it was placed.
+		LimitOrderExpired {
+			order_id: OrderId,
+			owner: T::AccountId,
+			pool_id: Option<AssetIdOf<T>>,
+			amount_in: AssetBalanceOf<T>,
+		},
+		/// The governance origin has switched the batch mode of a pool on or off.
+		BatchModeSet { pool_id: AssetIdOf<T>, asset_a: BalanceType<T>, asset_b: BalanceType<T>, fee: Permill, enabled: bool },
+		/// A swap intent has been submitted to a pool in batch mode, its amount is held until the end of the block.
+		SwapIntentSubmitted {
+			who: T::AccountId,
+			pool_id: AssetIdOf<T>,
+			asset_in: BalanceType<T>,
+			asset_out: BalanceType<T>,
+			fee: Permill,
+			amount_in: AssetBalanceOf<T>,
+			min_out: AssetBalanceOf<T>,
+		},
+		/// A swap intent has been cleared with its batch, its share has been paid out.
+		SwapIntentFilled {
+			who: T::AccountId,
+			pool_id: AssetIdOf<T>,
+			asset_in: BalanceType<T>,
+			asset_out: BalanceType<T>,
+			fee: Permill,
+			amount_in: AssetBalanceOf<T>,
+			amount_out: AssetBalanceOf<T>,
+		},
+		/// A swap intent has been paid back, because its share was below its slippage limit or its batch
+		/// couldn't be cleared. The pool id is `None` if the pool has been removed since the intent was submitted.
+		SwapIntentRefunded {
+			who: T::AccountId,
+			pool_id: Option<AssetIdOf<T>>,
+			asset_in: BalanceType<T>,
+			fee: Permill,
+			amount_in: AssetBalanceOf<T>,
+		},
+		/// A swap intent of a batch that couldn't be cleared couldn't be paid back either. Its amount stays in
+		/// the escrow account until `who` claims it with `claim_swap_intent_refund`.
+		SwapIntentRefundUnclaimed {
+			who: T::AccountId,
+			pool_id: Option<AssetIdOf<T>>,
+			asset_in: BalanceType<T>,
+			fee: Permill,
+			amount_in: AssetBalanceOf<T>,
+		},
+		/// The unclaimed amounts of the swap intents of `who` selling `asset` have been paid back.
+		SwapIntentRefundClaimed { who: T::AccountId, asset: BalanceType<T>, amount: AssetBalanceOf<T> },
+		/// The swap intents of a pool have been cleared at a uniform price. The pool only swapped the difference
+		/// between the amounts sold of both assets.
+		BatchCleared {
+			pool_id: AssetIdOf<T>,
+			asset_a: BalanceType<T>,
+			asset_b: BalanceType<T>,
+			fee: Permill,
+			amount_a_in: AssetBalanceOf<T>,
+			amount_b_in: AssetBalanceOf<T>,
+			price: FixedU128,
+		},
+		/// The pause origin has paused swaps, adds or both in a pool.
+		PoolPaused { pool_id: AssetIdOf<T>, asset_a: BalanceType<T>, asset_b: BalanceType<T>, fee: Permill, flags: PauseFlags },
+		/// The pause origin has lifted the pause of a pool.
+		PoolUnpaused { pool_id: AssetIdOf<T>, asset_a: BalanceType<T>, asset_b: BalanceType<T>, fee: Permill },
+		/// The pause origin has paused swaps, adds or both in all the pools.
+		AllPaused { flags: PauseFlags },
+		/// The pause origin has lifted the pause of all the pools, the pauses of single pools stay.
+		AllUnpaused,
+		/// The pause origin has paused swaps, adds or both in a weighted pool.
+		WeightedPoolPaused { pool_id: WeightedPoolId, flags: PauseFlags },
+		/// The pause origin has lifted the pause of a weighted pool.
+		WeightedPoolUnpaused { pool_id: WeightedPoolId },
+		/// The pause origin has paused swaps, adds or both in a concentrated liquidity pool.
+		ConcentratedPoolPaused { pool_id: ConcentratedPoolId, flags: PauseFlags },
+		/// The pause origin has lifted the pause of a concentrated liquidity pool.
+		ConcentratedPoolUnpaused { pool_id: ConcentratedPoolId },
 	}
 
 	// Errors inform users that something went wrong.
@@ -135,7 +970,98 @@ pub mod pallet {
 		NotEnoughLiquidityTokens,
 		/// Requested asset amount for widthdrawl exceeds the amount asset currently in the pool
 		RequestedExceedsPoolBalance,
+		/// The swap path must contain at least two assets and may not visit an asset twice
+		InvalidPath,
+		/// The TWAP window has to be at least one block
+		InvalidTwapWindow,
+		/// The pool doesn't have price observations that cover the requested TWAP window
+		InsufficientPriceHistory,
+		/// The fee tier is not in the allow-list
+		FeeTierNotAllowed,
+		/// The transaction was included after its deadline
+		DeadlineExpired,
+		/// The initial amounts of a pool have to be at least `MinPoolDeposit`
+		InitialDepositTooLow,
+		/// The initial liquidity of a pool has to be more than `MinimumLiquidity`, and a deposit has to be worth
+		/// at least one LP token
+		InsufficientLiquidityMinted,
+		/// A flash swap has to borrow some of at least one of the assets
+		FlashSwapAmountZero,
+		/// The constant product of the pool was not restored at the end of the flash swap
+		FlashSwapNotRepaid,
+		/// The pool was used by the flash swap handler while its assets were borrowed
+		PoolChangedDuringFlashSwap,
+		/// The amplification of a StableSwap pool has to be between 1 and `MaxAmplification`
+		InvalidAmplification,
+		/// A weighted pool needs at least two assets and may not contain an asset twice
+		InvalidWeightedPoolAssets,
+		/// The weights of a weighted pool have to be at least 1% each and add up to 100%
+		InvalidWeights,
+		/// The asset is not in the weighted pool
+		AssetNotInPool,
+		/// A trade with a weighted pool may take in at most half and pay out at most a third of a reserve
+		MaxRatioExceeded,
+		/// The tick spacing of a concentrated liquidity pool has to be between 1 and 16384
+		InvalidTickSpacing,
+		/// A position has to start below where it ends, at ticks within the bounds that are multiples of the tick spacing
+		InvalidTickRange,
+		/// The initial price of a concentrated liquidity pool is outside the prices the ticks can represent
+		InvalidPrice,
+		/// The pool has reached `MaxConcentratedTicks` initialized ticks
+		TooManyTicks,
+		/// The position doesn't exist or isn't owned by the caller
+		PositionDoesNotExist,
+		/// The position doesn't have that much liquidity
+		InsufficientPositionLiquidity,
+		/// There isn't enough liquidity in the ranges of the pool to swap the full amount
+		NotEnoughLiquidityInRange,
+		/// The limit order doesn't exist
+		OrderDoesNotExist,
+		/// Only the owner can cancel a limit order before it expires
+		NotOrderOwner,
+		/// The pool doesn't pay the limit price of the order
+		OrderNotFillable,
+		/// The account has reached `MaxLimitOrders` open limit orders
+		TooManyLimitOrders,
+		/// The pool is in batch mode, its swaps have to be submitted as swap intents
+		PoolInBatchMode,
+		/// The pool is not in batch mode, it swaps immediately
+		PoolNotInBatchMode,
+		/// The pool has reached `MaxSwapIntents` swap intents in this block
+		TooManySwapIntents,
+		/// The pool, or all the pools, are paused for this operation by the pause origin
+		PoolPaused,
+		/// A pause has to block swaps, adds or both
+		NothingToPause,
+		/// There is no free asset id for the LP token of a new pool within `LP_ASSET_ID_PROBES` ids of
+		/// `NextLpAssetId`, the range of the LP tokens is used by other assets
+		LpAssetIdsExhausted,
+		/// A batch of swap intents still has shares below their limit after the intents below their limit
+		/// were paid back, so all of its intents are paid back
+		BatchNotCleared,
+		/// The account has no unclaimed amount of swap intents selling the asset
+		NothingToClaim,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		/// Fills the limit orders whose limit price the pools pay and pays back the expired ones, with the
+		/// weight left in the block.
+		fn on_idle(now: BlockNumberFor<T>, remaining_weight: Weight) -> Weight {
+			Self::match_limit_orders(now, remaining_weight)
+		}
 
+		/// Clears the swap intents submitted to the pools in batch mode in this block. Their weight has been
+		/// paid when they were submitted.
+		fn on_finalize(_now: BlockNumberFor<T>) {
+			Self::clear_swap_batches();
+		}
+
+		/// Checks the invariants of the pools after every block of a `try-runtime` run.
+		#[cfg(feature = "try-runtime")]
+		fn try_state(_now: BlockNumberFor<T>) -> Result<(), sp_runtime::TryRuntimeError> {
+			Self::do_try_state()
+		}
 	}
 
 	// Dispatchable functions allows users to interact with the pallet and invoke state changes.
@@ -152,132 +1078,63 @@ pub mod pallet {
 		/// Registers new pool for a given asset pair a, b in the asset registry
   		/// Asset registry creates new id or returns previously created one if such pool existed before.
 		#[pallet::call_index(1)]
-		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1,1).ref_time())]
+		#[pallet::weight(T::WeightInfo::create_pool())]
 		pub fn create_pool(
 			origin: OriginFor<T>, 
-			asset_a: AssetIdOf<T>,
-			asset_b: AssetIdOf<T>,
+			asset_a: BalanceType<T>,
+			asset_b: BalanceType<T>,
+			fee: Permill,
 			amount_a: AssetBalanceOf<T>,
 			amount_b: AssetBalanceOf<T>,
 		) -> DispatchResult {
 			
 			// Check origin
 			let who = T::CreatePoolOrigin::ensure_origin(origin)?;
-			
-			// order assets_a and assets_b
-			let (asset_a, asset_b, amount_a, amount_b) = Self::order_asset_ids(asset_a, asset_b, amount_a, amount_b)?;
-
-			// Ensure the caller has enough balances in both of the assets, where he is providing liquidity
-			// and the that the balances of both assets are greater then minimum value required
-			Self::check_user_balances(&who, &asset_a, &asset_b, &amount_a, &amount_a)?;
-
-			// Create liquidity_id from asset_a and asset_b
-			let lp_asset_id: AssetIdOf<T> = Self::create_liquidity_id(asset_a.clone(), asset_b.clone());
-			
-			// Liquidity amount is equal to sqrt(amount_a * amount_b)
-			let amount_liq: AssetBalanceOf<T> = Self::get_sqrt_of_asset_balance(amount_a.checked_mul(&amount_b).ok_or(ArithmeticError::Overflow)?);
-			
-			// Check if the liquidity pool already exists
-			ensure!(!LiquidityPoolMap::<T>::contains_key(&(asset_a.clone(), asset_b.clone())), Error::<T>::PoolAlreadyExists);
-
-			// Get the pool account
-			let pool_account = Self::generate_account_from_asset_id_pair(asset_a.clone(), asset_b.clone());
-		
-			// native currency is transferd while creating this account
-			T::NativeBalance::transfer(&who, &pool_account, BalanceOf::<T>::from(1_000 as u32), Preservation::Expendable)?;
-			
-			// Save the new liquidity pool in the storage
-			LiquidityPoolMap::<T>::insert(&(asset_a.clone(), asset_b.clone()), pool_account.clone());
-
-			// create an event
-			Self::deposit_event(Event::<T>::PoolCreated {
-				asset_a: asset_a.clone(),
-				asset_b: asset_b.clone(),
-				liquidity_id: lp_asset_id.clone(),
-			});
-
-			// transfer the tokens from the users accout into pool account 
-			Self::transfer_assets_a_and_b(&who, &pool_account, &asset_a, &asset_b, &amount_a, &amount_b)?;
 
-			// check if the liquidity token already exists and if not create it
-			if !T::Fungibles::asset_exists(lp_asset_id.clone()) {
-				let pallet_account = Self::account_id();
-				T::Fungibles::create(lp_asset_id.clone(), pallet_account, false, One::one())?;
-			}
-			// mint the lp tokens into the users account
-			T::Fungibles::mint_into(lp_asset_id, &who, amount_liq.clone())?;
-
-			Ok(())
+			Self::do_create_pool(&who, asset_a, asset_b, fee, PoolCurve::ConstantProduct, amount_a, amount_b)
 		}
 
 
 		/// This function allow users to deposit tokens into an the existing liquidity pool.
-		/// It should calculate the appropriate amount of LP tokens to mint based on the
-		/// existing pool balances and the deposited amount, then transfer the LP tokens to the user's address.
+		/// Like `addLiquidity` in the Uniswap V2 router it deposits the two assets at the current ratio
+		/// of the reserves: the optimal amount of one asset is calculated from the desired amount of the
+		/// other one, so only what matches the ratio is transferred and nothing is donated to the pool.
+		/// The LP tokens are minted to the user's address.
 		/// 
 		/// This function fails if the pool doesn't exist or if the assets that are present in the account is equal to zero
-		/// First ensures that the user has enough assets to deposit their them and
+		///
+		/// # Arguments
+		///
+		/// * `origin` - The origin caller of the function, who is depositing the assets.
+		/// * `asset_a` - One of the assets in the liquidity pool.
+		/// * `asset_b` - The other asset in the liquidity pool.
+		/// * `fee` - The fee tier of the liquidity pool.
+		/// * `amount_a_desired` - The most of asset_a the user wants to deposit.
+		/// * `amount_b_desired` - The most of asset_b the user wants to deposit.
+		/// * `amount_a_min` - The least of asset_a the user accepts to deposit, it fails with `NotEnoughAssetsA` below it.
+		/// * `amount_b_min` - The least of asset_b the user accepts to deposit, it fails with `NotEnoughAssetsB` below it.
+		/// * `deadline` - The last block the deposit can be executed in, it fails with `DeadlineExpired` after it.
 		#[pallet::call_index(2)]
-		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1,1).ref_time())]
+		#[pallet::weight(T::WeightInfo::add_liquidity())]
 		pub fn add_liquidity(
 			origin: OriginFor<T>,
-			asset_a: AssetIdOf<T>,
-			asset_b: AssetIdOf<T>,
-			amount_a: AssetBalanceOf<T>,
-			amount_b: AssetBalanceOf<T>,
+			asset_a: BalanceType<T>,
+			asset_b: BalanceType<T>,
+			fee: Permill,
+			amount_a_desired: AssetBalanceOf<T>,
+			amount_b_desired: AssetBalanceOf<T>,
+			amount_a_min: AssetBalanceOf<T>,
+			amount_b_min: AssetBalanceOf<T>,
+			deadline: Option<BlockNumberFor<T>>,
 		) -> DispatchResult {
 			// Check origin
 			let who = T::CreatePoolOrigin::ensure_origin(origin)?;
 
-			// Order the asset ids
-			let (asset_a, asset_b, amount_a, amount_b) = Self::order_asset_ids(asset_a, asset_b, amount_a, amount_b)?;
-			
-			// Ensure the caller has enough balances in both of the assets, where he is providing liquidity
-			// and the that the balances of both assets are greater then minimum value required
-			Self::check_user_balances(&who, &asset_a, &asset_b, &amount_a, &amount_a)?;
-
-			// Create liquidity_id from asset_a and asset_b
-			let lp_asset_id: AssetIdOf<T> = Self::create_liquidity_id(asset_a.clone(), asset_b.clone());
-			
-			// Check if the liquidity pool already exists
-			ensure!(LiquidityPoolMap::<T>::contains_key(&(asset_a.clone(), asset_b.clone())), Error::<T>::PoolDoesNotExist);
-
-			// Get the pool account
-			let pool_account = Self::generate_account_from_asset_id_pair(asset_a.clone(), asset_b.clone());
-		
-			// Get the current reserves
-			let reserve_a = T::Fungibles::balance(asset_a.clone(), &pool_account);
-			let reserve_b = T::Fungibles::balance(asset_b.clone(), &pool_account);
-		
-			// Calculate liquidity tokens to mint
-			// LP tokens represent the amount of shares a LP provider has deposited.
-
-			// check that the pool is not empty
-			ensure!(!reserve_a.is_zero() || !reserve_b.is_zero(), Error::<T>::EmptyPool);
-
-			// get the total issuance of LP tokens 
-			let amount_a_in_reserves = amount_a.checked_mul(&T::Fungibles::total_issuance(lp_asset_id.clone())).ok_or(ArithmeticError::Overflow)?;
-			let lp_tokens_a = amount_a_in_reserves / reserve_a;
-	
-			let amount_b_in_reserves = amount_b.checked_mul(&T::Fungibles::total_issuance(lp_asset_id.clone())).ok_or(ArithmeticError::Overflow)?;
-			let lp_tokens_b = amount_b_in_reserves / reserve_b;
-	
-			// Use the smaller one to maintain the ratio
-			let lp_tokens = lp_tokens_a.min(lp_tokens_b);
-
-			// create an event
-			Self::deposit_event(Event::<T>::LiquidityAdded {
-				asset_a: asset_a.clone(),
-				asset_b: asset_b.clone(),
-				liquidity_id: lp_asset_id.clone(),
-			});
+			// Check the transaction didn't wait too long to be included
+			Self::ensure_deadline(deadline)?;
 
-			// transfer the tokens from the users accout into pool account 
-			Self::transfer_assets_a_and_b(&who, &pool_account, &asset_a, &asset_b, &amount_a, &amount_b)?;
+			Self::do_add_liquidity(&who, asset_a, asset_b, fee, amount_a_desired, amount_b_desired, amount_a_min, amount_b_min)?;
 
-			// Mint the liquidity tokens
-			T::Fungibles::mint_into(lp_asset_id, &who, lp_tokens)?;
-		
 			Ok(())
 		}
 
@@ -293,79 +1150,44 @@ pub mod pallet {
 		/// * `origin` - The origin caller of the function, who will be withdrawing liquidity.
 		/// * `asset_a` - One of the assets in the liquidity pool.
 		/// * `asset_b` - The other asset in the liquidity pool.
+		/// * `fee` - The fee tier of the liquidity pool.
 		/// * `min_amount_a` - The minimum amount of asset_a that the user expects to receive.
 		/// * `min_amount_b` - The minimum amount of asset_b that the user expects to receive.
 		/// * `liquidity` - The amount of liquidity tokens the user wishes to redeem.
+		/// * `deadline` - The last block the removal can be executed in, it fails with `DeadlineExpired` after it.
+		///
+		/// Burning the last LP tokens of a pool without a locked minimum liquidity removes the pool like
+		/// `remove_pool`, only such a removal pays the weight of `remove_pool` on top.
 		#[pallet::call_index(3)]
-		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1,1).ref_time())]
+		#[pallet::weight(T::WeightInfo::remove_liquidity().saturating_add(T::WeightInfo::remove_pool()))]
 		pub fn remove_liquidity(
 			origin: OriginFor<T>,
-			asset_a: AssetIdOf<T>,
-			asset_b: AssetIdOf<T>,
+			asset_a: BalanceType<T>,
+			asset_b: BalanceType<T>,
+			fee: Permill,
 			min_amount_a: AssetBalanceOf<T>,
 			min_amount_b: AssetBalanceOf<T>,
 			amount_liq: AssetBalanceOf<T>,
-		) -> DispatchResult {
+			deadline: Option<BlockNumberFor<T>>,
+		) -> DispatchResultWithPostInfo {
 			// Check origin
 			let who = T::CreatePoolOrigin::ensure_origin(origin)?;
 
-			//  Order the asset ids, orders the amounts as well
-			let (asset_a, asset_b, min_amount_a, min_amount_b) = Self::order_asset_ids(asset_a, asset_b, min_amount_a, min_amount_b)?;
-
-			// Create liquidity_id from asset_a and asset_b
-			let lp_asset_id: AssetIdOf<T> = Self::create_liquidity_id(asset_a.clone(), asset_b.clone());
-
-			// Checks if the user has enough liquidity tokens
-			ensure!(T::Fungibles::balance(lp_asset_id.clone(), &who) >= amount_liq, Error::<T>::NotEnoughLiquidityTokens);
-			ensure!(!amount_liq.is_zero(), "Balance of liquidity tokens has to be greater then 0");
-
-			// Check if the liquidity pool exists
-			ensure!(LiquidityPoolMap::<T>::contains_key(&(asset_a.clone(), asset_b.clone())), Error::<T>::PoolDoesNotExist);
-
-			// Get the pool account
-			let pool_account = Self::generate_account_from_asset_id_pair(asset_a.clone(), asset_b.clone());
-
-			// Get the current reserves
-			let reserve_a = T::Fungibles::balance(asset_a.clone(), &pool_account);
-			let reserve_b = T::Fungibles::balance(asset_b.clone(), &pool_account);
-
-			// Calculate the liquidity amount to remove
-			let total_liquidity = T::Fungibles::total_issuance(lp_asset_id.clone());
-			
-			// reserve_a * amount_liq / total_liquidity
-
-			let res_amount_a = reserve_a.checked_mul(&amount_liq).ok_or(ArithmeticError::Overflow)?;
-			let res_amount_b = reserve_b.checked_mul(&amount_liq).ok_or(ArithmeticError::Overflow)?;
-
-			let remove_amount_a = res_amount_a.checked_div(&total_liquidity).ok_or(ArithmeticError::Underflow)?;
-			let remove_amount_b = res_amount_b.checked_div(&total_liquidity).ok_or(ArithmeticError::Underflow)?;
-
-			// Ensure the user receives at least the minimum amounts they expect
-			ensure!(remove_amount_a >= min_amount_a, Error::<T>::NotEnoughAssetsA);
-			ensure!(remove_amount_b >= min_amount_b, Error::<T>::NotEnoughAssetsB);
-
-			// Transfer the tokens from the pool account to the user's account
-			Self::transfer_assets_a_and_b(&pool_account, &who, &asset_a, &asset_b, &remove_amount_a, &remove_amount_b)?;
-
-			// Burn the liquidity tokens from the user's account
-			T::Fungibles::burn_from(lp_asset_id.clone(), &who, amount_liq, Precision::BestEffort, Fortitude::Polite)?;
-
-			// Emit an event
-			Self::deposit_event(Event::<T>::LiquidityRemoved {
-				asset_a: asset_a.clone(),
-				asset_b: asset_b.clone(),
-				liquidity_id: lp_asset_id,
-				amount_liq,
-			});
+			// Check the transaction didn't wait too long to be included
+			Self::ensure_deadline(deadline)?;
 
-			Ok(())
+			let pool_removed = Self::do_remove_liquidity(&who, asset_a, asset_b, fee, min_amount_a, min_amount_b, amount_liq)?;
+			let actual_weight = if pool_removed { None } else { Some(T::WeightInfo::remove_liquidity()) };
+			Ok(actual_weight.into())
 		}
 
 
 		/// This function allows users to remove the pool and all the liquidity from an existing liquidity pool. 
 		/// Users don't have to specify the minimum amount of asset_a or asset_b or any liquidity tokens,
 		/// since all the balance that is in the pool is transfered into the callers account
-		/// The function first checks whether the pool exists and that the user has enough liquidity tokens.
+		/// The function first checks whether the pool exists and that the user has enough liquidity tokens,
+		/// which is all of them except the minimum liquidity locked at creation. The locked ones are burned
+		/// as well, so the pool is never left behind in an empty state.
 		/// 
 		/// The pool is removed from storage, the native asset that was locked in the pool is transfered to the
 		/// users account.
@@ -375,12 +1197,14 @@ pub mod pallet {
 		/// * `origin` - The origin caller of the function, who will be withdrawing liquidity.
 		/// * `asset_a` - One of the assets in the liquidity pool.
 		/// * `asset_b` - The other asset in the liquidity pool.
+		/// * `fee` - The fee tier of the liquidity pool.
 		#[pallet::call_index(4)]
-		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1,1).ref_time())]
+		#[pallet::weight(T::WeightInfo::remove_pool())]
 		pub fn remove_pool(
 			origin: OriginFor<T>,
-			asset_a: AssetIdOf<T>,
-			asset_b: AssetIdOf<T>,
+			asset_a: BalanceType<T>,
+			asset_b: BalanceType<T>,
+			fee: Permill,
 		) -> DispatchResult {
 			// Check origin
 			let who = T::CreatePoolOrigin::ensure_origin(origin)?;
@@ -388,41 +1212,43 @@ pub mod pallet {
 			//  Order the asset ids, orders the amounts as well
 			let (asset_a, asset_b, _, _) = Self::order_asset_ids(asset_a, asset_b, AssetBalanceOf::<T>::zero(), AssetBalanceOf::<T>::zero())?;
 
-			// Create liquidity_id from asset_a and asset_b
-			let lp_asset_id: AssetIdOf<T> = Self::create_liquidity_id(asset_a.clone(), asset_b.clone());
+			// Get the liquidity pool, this fails if it doesn't exist
+			let pool = LiquidityPoolMap::<T>::get(&(asset_a.clone(), asset_b.clone(), fee)).ok_or(Error::<T>::PoolDoesNotExist)?;
+			let pool_account = pool.pool_account;
+			let lp_asset_id = pool.liquidity_id;
 
-			// Check if the liquidity pool exists
-			ensure!(LiquidityPoolMap::<T>::contains_key(&(asset_a.clone(), asset_b.clone())), Error::<T>::PoolDoesNotExist);
-			
-			// Get the pool account
-			let pool_account = Self::generate_account_from_asset_id_pair(asset_a.clone(), asset_b.clone());
+			// Get the balances of the pool account, so that tokens donated to the pool are not left behind
+			let reserve_a = Self::pool_reserve(&asset_a, &pool_account);
+			let reserve_b = Self::pool_reserve(&asset_b, &pool_account);
 			
-			// Get the current reserves
-			let reserve_a = T::Fungibles::balance(asset_a.clone(), &pool_account);
-			let reserve_b = T::Fungibles::balance(asset_b.clone(), &pool_account);
-			
-			// Calculate the liquidity amount to remove
-			let total_liquidity = T::Fungibles::total_issuance(lp_asset_id.clone());
+			// Calculate the liquidity amount to remove, everything but the minimum liquidity locked in the pool account
+			let locked_liquidity = T::Fungibles::balance(lp_asset_id.clone(), &pool_account);
+			let user_liquidity = T::Fungibles::total_issuance(lp_asset_id.clone()).saturating_sub(locked_liquidity);
 			
 			// Checks if the user has enough liquidity tokens
-			ensure!(T::Fungibles::balance(lp_asset_id.clone(), &who) >= total_liquidity, Error::<T>::NotEnoughLiquidityTokens);
+			ensure!(!user_liquidity.is_zero(), Error::<T>::NotEnoughLiquidityTokens);
+			ensure!(T::Fungibles::balance(lp_asset_id.clone(), &who) >= user_liquidity, Error::<T>::NotEnoughLiquidityTokens);
 
 			// Remove the total liquidity
 			Self::transfer_assets_a_and_b(&pool_account, &who, &asset_a, &asset_b, &reserve_a, &reserve_b)?;
 
-			// TODO: remove asset from storage
-
-			// Burn the liquidity tokens from the user's account
-			T::Fungibles::burn_from(lp_asset_id.clone(), &who, total_liquidity, Precision::BestEffort, Fortitude::Polite)?;
+			// Burn the liquidity tokens from the user's account, and the locked ones as the pool is gone
+			T::Fungibles::burn_from(lp_asset_id.clone(), &who, user_liquidity, Precision::BestEffort, Fortitude::Polite)?;
+			T::Fungibles::burn_from(lp_asset_id.clone(), &pool_account, locked_liquidity, Precision::BestEffort, Fortitude::Polite)?;
 
-			// Return native tokens to the account issuer:
-			T::NativeBalance::transfer(&pool_account, &who, BalanceOf::<T>::from(1_000 as u32), Preservation::Expendable)?;
+			// Destroy the LP token, pay the deposits back and remove the pool from storage
+			Self::close_pool(&who, &(asset_a.clone(), asset_b.clone(), fee), lp_asset_id.clone(), &pool_account)?;
 
 			// Emit an event
 			Self::deposit_event(Event::<T>::PoolRemoved {
+				who,
+				pool_id: lp_asset_id,
 				asset_a: asset_a.clone(),
 				asset_b: asset_b.clone(),
-				liquidity_id: lp_asset_id,
+				fee,
+				amount_liq: user_liquidity,
+				amount_a: reserve_a,
+				amount_b: reserve_b,
 			});
 
 			Ok(())
@@ -433,137 +1259,2518 @@ pub mod pallet {
 		/// It takes as input the account of the user, the two tokens to be swapped, the exact amount 
 		/// of token to be swapped (in), and the minimum amount of token to be received (out).
 		/// 
-		/// We take a flat 1% fee from exact_in amount for processing the transaction.
+		/// The fee tier of the pool, `fee`, is taken from exact_in and stays in the pool.
+		///
+		/// Fails with `DeadlineExpired` if `deadline` is set and the current block is past it.
 		#[pallet::call_index(5)]
-		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1,1).ref_time())]
+		#[pallet::weight(T::WeightInfo::swap_exact_in_for_out())]
 		pub fn swap_exact_in_for_out(
 			origin: OriginFor<T>,
-			asset_in: AssetIdOf<T>,
-			asset_out: AssetIdOf<T>,
+			asset_in: BalanceType<T>,
+			asset_out: BalanceType<T>,
+			fee: Permill,
 			exact_in: AssetBalanceOf<T>,
 			min_out: AssetBalanceOf<T>,
+			deadline: Option<BlockNumberFor<T>>,
 		) -> DispatchResult {
 			let who = ensure_signed(origin)?;
 
-			//  Order the asset ids, orders the amounts as well
-			// Using asset_a and asset_b naming when order matters, but it doesn't matter which one is which
-			let (asset_a, asset_b, _, _) = Self::order_asset_ids(asset_in.clone(), asset_out.clone(), AssetBalanceOf::<T>::zero(), AssetBalanceOf::<T>::zero())?;
-
-			// check the pool exists
-			ensure!(LiquidityPoolMap::<T>::contains_key(&(asset_a.clone(), asset_b.clone())), Error::<T>::PoolDoesNotExist);
-			
-			// Get the pool account
-			let pool_account = Self::generate_account_from_asset_id_pair(asset_a, asset_b);
-			
-			// check user has enough balance
-			ensure!(T::Fungibles::balance(asset_in.clone(), &who) >= exact_in, Error::<T>::InsufficientBalance);
-
-			// take a flat fee of 1% for processing the transaction
-			let percent_remaining = Percent::from_rational(100u32 - T::FeePercentage::get(), 100u32); // (100 - FEE_PERCENTAGE)%
-			let reduced_for_fee_in = percent_remaining * exact_in;
-
-			// get pool balances
-			let pool_balance_in = T::Fungibles::balance(asset_in.clone(), &pool_account);
-			let pool_balance_out = T::Fungibles::balance(asset_out.clone(), &pool_account);
-
-			// calculate amount out
-			// formula: amount_out = pool_balance_out - (pool_balance_in * pool_balance_out) / (pool_balance_in + exact_in)
-			let part_1 = pool_balance_in.checked_mul(&pool_balance_out).ok_or(ArithmeticError::Overflow)?;
-			let part_2 = pool_balance_in.checked_add(&reduced_for_fee_in).ok_or(ArithmeticError::Overflow)?;
-			let part_3 = part_1.checked_div(&part_2).ok_or(ArithmeticError::Underflow)?;
-			let amount_out = pool_balance_out.checked_sub(&part_3).ok_or(ArithmeticError::Underflow)?;
-
-			// check minimum output
-			ensure!(amount_out >= min_out, Error::<T>::InsufficientBalance);
-
-			// update pool balances and user balances
-			// transfer from balance into pool
-			T::Fungibles::transfer(asset_in, &who, &pool_account, exact_in, Preservation::Expendable)?;
-			// transfer from pool to balance
-			T::Fungibles::transfer(asset_out,  &pool_account, &who, amount_out, Preservation::Expendable)?;
+			// Check the transaction didn't wait too long to be included
+			Self::ensure_deadline(deadline)?;
 
-			Ok(())
+			Self::do_swap_exact_in_for_out(&who, asset_in, asset_out, fee, exact_in, min_out)
 		}
 
 		/// SwapInForExactOut: This function allows users to swap one token for another.
 		/// It takes as input the account of the user, the two tokens to be swapped, the maximum amount 
 		/// of token to be swapped (in), and the exact amount of token to be received (out).
 		/// 
-		/// We take a flat 1% fee from max_in amount for processing the transaction.
+		/// The fee tier of the pool, `fee`, is added on top of the amount in and stays in the pool.
+		///
+		/// Fails with `DeadlineExpired` if `deadline` is set and the current block is past it.
 		#[pallet::call_index(6)]
-		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1,1).ref_time())]
+		#[pallet::weight(T::WeightInfo::swap_in_for_exact_out())]
 		pub fn swap_in_for_exact_out(
 			origin: OriginFor<T>,
-			asset_in: AssetIdOf<T>,
-			asset_out: AssetIdOf<T>,
+			asset_in: BalanceType<T>,
+			asset_out: BalanceType<T>,
+			fee: Permill,
 			max_in: AssetBalanceOf<T>,
 			exact_out: AssetBalanceOf<T>,
+			deadline: Option<BlockNumberFor<T>>,
 		) -> DispatchResult {
 			let who = ensure_signed(origin)?;
 
-			// Order the asset ids, orders the amounts as well
-			let (asset_a, asset_b, _, _) = Self::order_asset_ids(asset_in.clone(), asset_out.clone(), AssetBalanceOf::<T>::zero(), AssetBalanceOf::<T>::zero())?;
-		
-			// Check the pool exists
-			ensure!(LiquidityPoolMap::<T>::contains_key(&(asset_a.clone(), asset_b.clone())), Error::<T>::PoolDoesNotExist);
-		
-			// Get the pool account
-			let pool_account = Self::generate_account_from_asset_id_pair(asset_a, asset_b);
-		
-			// Check user has enough balance
-			ensure!(T::Fungibles::balance(asset_in.clone(), &who) >= max_in, Error::<T>::InsufficientBalance);
-		
-			// Get pool balances
-			let pool_balance_in = T::Fungibles::balance(asset_in.clone(), &pool_account);
-			let pool_balance_out = T::Fungibles::balance(asset_out.clone(), &pool_account);
+			// Check the transaction didn't wait too long to be included
+			Self::ensure_deadline(deadline)?;
 
-			// Check if the max_in or exact_out exceed pool balances
-			ensure!(pool_balance_out > exact_out, Error::<T>::RequestedExceedsPoolBalance);
-		
-			// Calculate amount in
-			// formula: amount_in = (pool_balance_in * pool_balance_out) / (pool_balance_out - exact_out) - pool_balance_in
-			let part_1 = pool_balance_in.checked_mul(&pool_balance_out).ok_or(ArithmeticError::Overflow)?;
-			let part_2 = pool_balance_out.checked_sub(&exact_out).ok_or(ArithmeticError::Underflow)?;
-			let part_3 = part_1.checked_div(&part_2).ok_or(ArithmeticError::Underflow)?;
-			let amount_in_before_fee = part_3.checked_sub(&pool_balance_in).ok_or(ArithmeticError::Underflow)?;
-		
-			// Take a flat fee of 1% for processing the transaction
-			let percent_fee = Percent::from_rational(T::FeePercentage::get(), 100u32); // FEE_PERCENTAGE%
-			//let fee = percent_fee * amount_in_before_fee;
-			let fee = percent_fee * amount_in_before_fee;
-			let amount_in = amount_in_before_fee.checked_add(&fee).ok_or(ArithmeticError::Overflow)?;
-		
-			// Add a max in amount 
-			ensure!(amount_in <= max_in, Error::<T>::InsufficientBalance);
-		
-			// Update pool balances and user balances
-			// Transfer from balance into pool
-			T::Fungibles::transfer(asset_in, &who, &pool_account, amount_in, Preservation::Expendable)?;
-			// Transfer from pool to balance
-			T::Fungibles::transfer(asset_out, &pool_account, &who, exact_out, Preservation::Expendable)?;
-		
-			Ok(())
+			Self::do_swap_in_for_exact_out(&who, asset_in, asset_out, fee, max_in, exact_out)
 		}
-	}
-}
-
-use frame_support::sp_runtime::traits::Hash;
-use sp_runtime::{
-	traits::{CheckedMul, TrailingZeroInput},
-	ArithmeticError, Percent,
-};
-
-use frame_support::traits::{
-	fungible::Mutate,
-	fungibles::{Create, Inspect, Mutate as FSMutate},
-	tokens::{Precision, Fortitude, Preservation}
-};
+
+		/// SwapExactInForOutViaPath: This function allows users to swap one token for another through
+		/// a path of pools, e.g. [1, 3, 2] swaps asset 1 for asset 3 and then asset 3 for asset 2.
+		/// All the hops are executed atomically and only the final output is checked against `min_out`.
+		///
+		/// The fee is taken from the input amount of every hop.
+		///
+		/// # Arguments
+		///
+		/// * `origin` - The origin caller of the function, who is swapping.
+		/// * `path` - The assets to swap through, starting with the asset in and ending with the asset out.
+		/// * `fees` - The fee tier of the pool of every hop, one less than the number of assets in the path.
+		/// * `exact_in` - The exact amount of the first asset in the path the user is paying.
+		/// * `min_out` - The minimum amount of the last asset in the path that the user expects to receive.
+		/// * `deadline` - The last block the swap can be executed in, it fails with `DeadlineExpired` after it.
+		#[pallet::call_index(7)]
+		#[pallet::weight(T::WeightInfo::swap_exact_in_for_out_via_path(path.len() as u32))]
+		pub fn swap_exact_in_for_out_via_path(
+			origin: OriginFor<T>,
+			path: BoundedVec<BalanceType<T>, T::MaxSwapPathLength>,
+			fees: BoundedVec<Permill, T::MaxSwapPathLength>,
+			exact_in: AssetBalanceOf<T>,
+			min_out: AssetBalanceOf<T>,
+			deadline: Option<BlockNumberFor<T>>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			// Check the transaction didn't wait too long to be included
+			Self::ensure_deadline(deadline)?;
+
+			// Calculate the amounts of every hop, this fails if one of the pools doesn't exist
+			let amounts = Self::get_amounts_out(&path, &fees, exact_in)?;
+
+			// Check user has enough balance
+			ensure!(Self::asset_balance(&path[0], &who) >= exact_in, Error::<T>::InsufficientBalance);
+
+			// Check minimum output of the last hop
+			let amount_out = *amounts.last().ok_or(Error::<T>::InvalidPath)?;
+			ensure!(amount_out >= min_out, Error::<T>::InsufficientBalance);
+
+			Self::swap_along_path(&who, &path, &fees, &amounts, true)
+		}
+
+		/// SwapInForExactOutViaPath: This function allows users to swap one token for another through
+		/// a path of pools, e.g. [1, 3, 2] swaps asset 1 for asset 3 and then asset 3 for asset 2.
+		/// All the hops are executed atomically and only the total input is checked against `max_in`.
+		///
+		/// The fee is added to the input amount of every hop.
+		///
+		/// # Arguments
+		///
+		/// * `origin` - The origin caller of the function, who is swapping.
+		/// * `path` - The assets to swap through, starting with the asset in and ending with the asset out.
+		/// * `fees` - The fee tier of the pool of every hop, one less than the number of assets in the path.
+		/// * `max_in` - The maximum amount of the first asset in the path the user is willing to pay.
+		/// * `exact_out` - The exact amount of the last asset in the path the user wants to receive.
+		/// * `deadline` - The last block the swap can be executed in, it fails with `DeadlineExpired` after it.
+		#[pallet::call_index(8)]
+		#[pallet::weight(T::WeightInfo::swap_in_for_exact_out_via_path(path.len() as u32))]
+		pub fn swap_in_for_exact_out_via_path(
+			origin: OriginFor<T>,
+			path: BoundedVec<BalanceType<T>, T::MaxSwapPathLength>,
+			fees: BoundedVec<Permill, T::MaxSwapPathLength>,
+			max_in: AssetBalanceOf<T>,
+			exact_out: AssetBalanceOf<T>,
+			deadline: Option<BlockNumberFor<T>>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			// Check the transaction didn't wait too long to be included
+			Self::ensure_deadline(deadline)?;
+
+			// Calculate the amounts of every hop, this fails if one of the pools doesn't exist
+			let amounts = Self::get_amounts_in(&path, &fees, exact_out)?;
+
+			// Check user has enough balance
+			ensure!(Self::asset_balance(&path[0], &who) >= max_in, Error::<T>::InsufficientBalance);
+
+			// Check maximum input of the first hop
+			ensure!(amounts[0] <= max_in, Error::<T>::InsufficientBalance);
+
+			Self::swap_along_path(&who, &path, &fees, &amounts, false)
+		}
+
+		/// Skim: Transfers the balances of the pool account that exceed the tracked reserves to the caller,
+		/// e.g. tokens that were transferred straight to the pool account. The price of the pool doesn't change.
+		///
+		/// # Arguments
+		///
+		/// * `origin` - The origin caller of the function, who receives the excess balances.
+		/// * `asset_a` - One of the assets in the liquidity pool.
+		/// * `asset_b` - The other asset in the liquidity pool.
+		/// * `fee` - The fee tier of the liquidity pool.
+		#[pallet::call_index(9)]
+		#[pallet::weight(T::WeightInfo::skim())]
+		pub fn skim(
+			origin: OriginFor<T>,
+			asset_a: BalanceType<T>,
+			asset_b: BalanceType<T>,
+			fee: Permill,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			//  Order the asset ids
+			let (asset_a, asset_b, _, _) = Self::order_asset_ids(asset_a, asset_b, AssetBalanceOf::<T>::zero(), AssetBalanceOf::<T>::zero())?;
+
+			// Get the liquidity pool, this fails if it doesn't exist
+			let pool = LiquidityPoolMap::<T>::get(&(asset_a.clone(), asset_b.clone(), fee)).ok_or(Error::<T>::PoolDoesNotExist)?;
+
+			// The excess is whatever the pool account holds on top of the tracked reserves
+			let amount_a = Self::pool_reserve(&asset_a, &pool.pool_account).saturating_sub(pool.reserve_a);
+			let amount_b = Self::pool_reserve(&asset_b, &pool.pool_account).saturating_sub(pool.reserve_b);
+
+			Self::transfer_assets_a_and_b(&pool.pool_account, &who, &asset_a, &asset_b, &amount_a, &amount_b)?;
+
+			Self::deposit_event(Event::<T>::PoolSkimmed {
+				who,
+				pool_id: pool.liquidity_id,
+				asset_a,
+				asset_b,
+				fee,
+				amount_a,
+				amount_b,
+			});
+
+			Ok(())
+		}
+
+		/// Sync: Sets the tracked reserves of the pool to the balances of the pool account, so tokens
+		/// that were transferred straight to the pool account become part of the reserves.
+		///
+		/// # Arguments
+		///
+		/// * `origin` - The origin caller of the function.
+		/// * `asset_a` - One of the assets in the liquidity pool.
+		/// * `asset_b` - The other asset in the liquidity pool.
+		/// * `fee` - The fee tier of the liquidity pool.
+		#[pallet::call_index(10)]
+		#[pallet::weight(T::WeightInfo::sync())]
+		pub fn sync(
+			origin: OriginFor<T>,
+			asset_a: BalanceType<T>,
+			asset_b: BalanceType<T>,
+			fee: Permill,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			//  Order the asset ids
+			let (asset_a, asset_b, _, _) = Self::order_asset_ids(asset_a, asset_b, AssetBalanceOf::<T>::zero(), AssetBalanceOf::<T>::zero())?;
+
+			// Get the liquidity pool, this fails if it doesn't exist
+			let pool = LiquidityPoolMap::<T>::get(&(asset_a.clone(), asset_b.clone(), fee)).ok_or(Error::<T>::PoolDoesNotExist)?;
+
+			let reserve_a = Self::pool_reserve(&asset_a, &pool.pool_account);
+			let reserve_b = Self::pool_reserve(&asset_b, &pool.pool_account);
+			Self::set_reserves(asset_a.clone(), asset_b.clone(), fee, reserve_a, reserve_b)?;
+
+			Self::deposit_event(Event::<T>::PoolSynced {
+				who,
+				pool_id: pool.liquidity_id,
+				asset_a,
+				asset_b,
+				fee,
+				reserve_a,
+				reserve_b,
+			});
+
+			Ok(())
+		}
+
+		/// SetProtocolFee: Switches the protocol fee on or off, like `feeTo` in Uniswap V2.
+		/// While it is on, `share` of the swap fees is minted as LP tokens to `recipient` on every
+		/// liquidity event, the rest stays with the liquidity providers.
+		///
+		/// # Arguments
+		///
+		/// * `origin` - Has to be the governance origin.
+		/// * `recipient` - The account receiving the protocol fee, `None` switches the protocol fee off.
+		/// * `share` - The share of the swap fees that goes to the protocol, zero switches the protocol fee off.
+		#[pallet::call_index(11)]
+		#[pallet::weight(T::WeightInfo::set_protocol_fee())]
+		pub fn set_protocol_fee(
+			origin: OriginFor<T>,
+			recipient: Option<T::AccountId>,
+			share: Percent,
+		) -> DispatchResult {
+			T::GovernanceOrigin::ensure_origin(origin)?;
+
+			ProtocolFeeRecipient::<T>::set(recipient.clone());
+			ProtocolFeeShare::<T>::put(share);
+
+			Self::deposit_event(Event::<T>::ProtocolFeeSet { recipient, share });
+
+			Ok(())
+		}
+
+		/// SetFeeTiers: Replaces the allow-list of fee tiers new pools can be created with.
+		/// `T::DefaultFeeTier` is always allowed, existing pools keep their fee tier.
+		///
+		/// # Arguments
+		///
+		/// * `origin` - Has to be the governance origin.
+		/// * `fee_tiers` - The fee tiers allowed besides the default one, e.g. 0.05% for stablecoin pairs.
+		#[pallet::call_index(12)]
+		#[pallet::weight(T::WeightInfo::set_fee_tiers())]
+		pub fn set_fee_tiers(
+			origin: OriginFor<T>,
+			fee_tiers: BoundedVec<Permill, T::MaxFeeTiers>,
+		) -> DispatchResult {
+			T::GovernanceOrigin::ensure_origin(origin)?;
+
+			FeeTiers::<T>::put(fee_tiers.clone());
+
+			Self::deposit_event(Event::<T>::FeeTiersSet { fee_tiers });
+
+			Ok(())
+		}
+
+		/// FlashSwap: Borrows assets from a pool without collateral, like a flash swap in Uniswap V2.
+		/// The amounts are transferred to the caller and `T::FlashSwapHandler` runs, e.g. an arbitrage
+		/// across other pools. Afterwards the pool checks that enough was paid back to restore its constant
+		/// product, including the fee on the amounts paid back, otherwise the whole call fails.
+		///
+		/// # Arguments
+		///
+		/// * `origin` - The origin caller of the function, who borrows the assets.
+		/// * `asset_a` - One of the assets in the liquidity pool.
+		/// * `asset_b` - The other asset in the liquidity pool.
+		/// * `fee` - The fee tier of the liquidity pool.
+		/// * `amount_a_out` - The amount of asset_a to borrow.
+		/// * `amount_b_out` - The amount of asset_b to borrow.
+		#[pallet::call_index(13)]
+		#[pallet::weight(T::WeightInfo::flash_swap())]
+		pub fn flash_swap(
+			origin: OriginFor<T>,
+			asset_a: BalanceType<T>,
+			asset_b: BalanceType<T>,
+			fee: Permill,
+			amount_a_out: AssetBalanceOf<T>,
+			amount_b_out: AssetBalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			Self::do_flash_swap(&who, asset_a, asset_b, fee, amount_a_out, amount_b_out)
+		}
+
+		/// Creates a pool that keeps the StableSwap invariant of Curve instead of the constant product,
+		/// for pegged assets like two stablecoins. Around the peg it trades with far lower slippage, the
+		/// higher `amplification` the flatter the curve. Liquidity is added, removed and swapped with the
+		/// same extrinsics as in any other pool.
+		///
+		/// This function fails if a pool of the pair with the fee tier already exists, of either curve.
+		#[pallet::call_index(14)]
+		#[pallet::weight(T::WeightInfo::create_stable_pool())]
+		pub fn create_stable_pool(
+			origin: OriginFor<T>,
+			asset_a: BalanceType<T>,
+			asset_b: BalanceType<T>,
+			fee: Permill,
+			amplification: u32,
+			amount_a: AssetBalanceOf<T>,
+			amount_b: AssetBalanceOf<T>,
+		) -> DispatchResult {
+			let who = T::CreatePoolOrigin::ensure_origin(origin)?;
+
+			// The curve degenerates without amplification and gets too flat to handle imbalances above the maximum
+			ensure!(amplification >= 1 && amplification <= T::MaxAmplification::get(), Error::<T>::InvalidAmplification);
+
+			Self::do_create_pool(&who, asset_a, asset_b, fee, PoolCurve::StableSwap { amplification }, amount_a, amount_b)
+		}
+
+		/// Creates a weighted pool like in Balancer, of 2 up to `MaxWeightedPoolAssets` assets with weights that
+		/// add up to 100%, e.g. an 80/20 pool of a governance token or a basket of assets. Swaps keep
+		/// `prod(reserve_i ^ weight_i)` constant, so the value of the reserves stays split by the weights.
+		///
+		/// # Arguments
+		///
+		/// * `origin` - The origin caller of the function, who deposits the initial amounts.
+		/// * `assets` - Every asset of the pool with its weight, at least 1%, and its initial amount.
+		/// * `fee` - The fee tier of the pool.
+		#[pallet::call_index(15)]
+		#[pallet::weight(T::WeightInfo::create_weighted_pool(assets.len() as u32))]
+		pub fn create_weighted_pool(
+			origin: OriginFor<T>,
+			assets: BoundedVec<(BalanceType<T>, Perbill, AssetBalanceOf<T>), T::MaxWeightedPoolAssets>,
+			fee: Permill,
+		) -> DispatchResult {
+			let who = T::CreatePoolOrigin::ensure_origin(origin)?;
+
+			Self::do_create_weighted_pool(&who, assets.into_inner(), fee)?;
+			Ok(())
+		}
+
+		/// Deposits only `amount_in` of one asset into a weighted pool for LP tokens. The part of the deposit
+		/// that doesn't match the other assets of the pool pays the fee of the pool.
+		///
+		/// # Arguments
+		///
+		/// * `origin` - The origin caller of the function, who is depositing the asset.
+		/// * `pool_id` - The weighted pool.
+		/// * `asset` - The asset to deposit, one of the assets of the pool.
+		/// * `amount_in` - The amount to deposit, at most half of the reserve of the asset.
+		/// * `min_liquidity` - The least LP tokens the user accepts, it fails with `NotEnoughLiquidityTokens` below it.
+		/// * `deadline` - The last block the deposit can be executed in, it fails with `DeadlineExpired` after it.
+		#[pallet::call_index(16)]
+		#[pallet::weight(T::WeightInfo::join_weighted_pool())]
+		pub fn join_weighted_pool(
+			origin: OriginFor<T>,
+			pool_id: WeightedPoolId,
+			asset: BalanceType<T>,
+			amount_in: AssetBalanceOf<T>,
+			min_liquidity: AssetBalanceOf<T>,
+			deadline: Option<BlockNumberFor<T>>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_deadline(deadline)?;
+
+			Self::do_join_weighted_pool(&who, pool_id, asset, amount_in, min_liquidity)?;
+			Ok(())
+		}
+
+		/// Burns `amount_liq` LP tokens of a weighted pool for only one of its assets. The part of the
+		/// withdrawal that doesn't match the other assets of the pool pays the fee of the pool.
+		///
+		/// # Arguments
+		///
+		/// * `origin` - The origin caller of the function, who is burning the LP tokens.
+		/// * `pool_id` - The weighted pool.
+		/// * `asset` - The asset to withdraw, one of the assets of the pool.
+		/// * `amount_liq` - The LP tokens to burn, the withdrawal may be at most a third of the reserve of the asset.
+		/// * `min_amount_out` - The least of the asset the user accepts, it fails with `InsufficientBalance` below it.
+		/// * `deadline` - The last block the withdrawal can be executed in, it fails with `DeadlineExpired` after it.
+		#[pallet::call_index(17)]
+		#[pallet::weight(T::WeightInfo::exit_weighted_pool())]
+		pub fn exit_weighted_pool(
+			origin: OriginFor<T>,
+			pool_id: WeightedPoolId,
+			asset: BalanceType<T>,
+			amount_liq: AssetBalanceOf<T>,
+			min_amount_out: AssetBalanceOf<T>,
+			deadline: Option<BlockNumberFor<T>>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_deadline(deadline)?;
+
+			Self::do_exit_weighted_pool(&who, pool_id, asset, amount_liq, min_amount_out)?;
+			Ok(())
+		}
+
+		/// Swaps exactly `amount_in` of one asset of a weighted pool for at least `min_out` of another one,
+		/// priced with the weighted-product formula.
+		#[pallet::call_index(18)]
+		#[pallet::weight(T::WeightInfo::swap_weighted())]
+		pub fn swap_weighted(
+			origin: OriginFor<T>,
+			pool_id: WeightedPoolId,
+			asset_in: BalanceType<T>,
+			asset_out: BalanceType<T>,
+			amount_in: AssetBalanceOf<T>,
+			min_out: AssetBalanceOf<T>,
+			deadline: Option<BlockNumberFor<T>>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_deadline(deadline)?;
+
+			Self::do_swap_weighted(&who, pool_id, asset_in, asset_out, amount_in, min_out)?;
+			Ok(())
+		}
+
+		/// Creates a concentrated liquidity pool like in Uniswap V3. Instead of across all prices, liquidity
+		/// is provided within price ranges between two ticks, the price at tick `i` is `1.0001^i`. The pool
+		/// starts without liquidity, it is added with `mint_position`.
+		///
+		/// # Arguments
+		///
+		/// * `origin` - The origin caller of the function.
+		/// * `asset_a` - One asset of the pool.
+		/// * `asset_b` - The other asset of the pool.
+		/// * `fee` - The fee tier of the pool.
+		/// * `tick_spacing` - Positions can only start and end at multiples of it, between 1 and 16384.
+		/// * `initial_price` - The price of `asset_a` in `asset_b` the pool starts at.
+		#[pallet::call_index(19)]
+		#[pallet::weight(T::WeightInfo::create_concentrated_pool())]
+		pub fn create_concentrated_pool(
+			origin: OriginFor<T>,
+			asset_a: BalanceType<T>,
+			asset_b: BalanceType<T>,
+			fee: Permill,
+			tick_spacing: u32,
+			initial_price: FixedU128,
+		) -> DispatchResult {
+			let who = T::CreatePoolOrigin::ensure_origin(origin)?;
+
+			Self::do_create_concentrated_pool(&who, asset_a, asset_b, fee, tick_spacing, initial_price)?;
+			Ok(())
+		}
+
+		/// Provides liquidity to a concentrated liquidity pool between `tick_lower` and `tick_upper` as a new
+		/// position of the caller. The position only earns fees while the price is in its range. Below the
+		/// range it is made of asset_0 only, above the range of asset_1 only.
+		///
+		/// # Arguments
+		///
+		/// * `origin` - The origin caller of the function, who owns the position.
+		/// * `pool_id` - The concentrated liquidity pool.
+		/// * `tick_lower` - The tick the range starts at, a multiple of the tick spacing.
+		/// * `tick_upper` - The tick the range ends at, a multiple of the tick spacing above `tick_lower`.
+		/// * `amount_0_desired` - The most of asset_0 to deposit.
+		/// * `amount_1_desired` - The most of asset_1 to deposit.
+		/// * `amount_0_min` - The least of asset_0 to deposit, it fails with `NotEnoughAssetsA` below it.
+		/// * `amount_1_min` - The least of asset_1 to deposit, it fails with `NotEnoughAssetsB` below it.
+		/// * `deadline` - The last block the deposit can be executed in, it fails with `DeadlineExpired` after it.
+		#[pallet::call_index(20)]
+		#[pallet::weight(T::WeightInfo::mint_position())]
+		pub fn mint_position(
+			origin: OriginFor<T>,
+			pool_id: ConcentratedPoolId,
+			tick_lower: i32,
+			tick_upper: i32,
+			amount_0_desired: AssetBalanceOf<T>,
+			amount_1_desired: AssetBalanceOf<T>,
+			amount_0_min: AssetBalanceOf<T>,
+			amount_1_min: AssetBalanceOf<T>,
+			deadline: Option<BlockNumberFor<T>>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_deadline(deadline)?;
+
+			Self::do_mint_position(
+				&who,
+				pool_id,
+				tick_lower,
+				tick_upper,
+				(amount_0_desired, amount_1_desired),
+				(amount_0_min, amount_1_min),
+			)?;
+			Ok(())
+		}
+
+		/// Removes `liquidity` from a position of the caller. The amounts of the removed liquidity are added
+		/// to the fees of the position and are paid out with `collect`.
+		///
+		/// # Arguments
+		///
+		/// * `origin` - The origin caller of the function, who owns the position.
+		/// * `position_id` - The position.
+		/// * `liquidity` - The liquidity to remove, at most the liquidity of the position.
+		/// * `amount_0_min` - The least of asset_0 the user accepts, it fails with `NotEnoughAssetsA` below it.
+		/// * `amount_1_min` - The least of asset_1 the user accepts, it fails with `NotEnoughAssetsB` below it.
+		/// * `deadline` - The last block the removal can be executed in, it fails with `DeadlineExpired` after it.
+		#[pallet::call_index(21)]
+		#[pallet::weight(T::WeightInfo::decrease_liquidity())]
+		pub fn decrease_liquidity(
+			origin: OriginFor<T>,
+			position_id: PositionId,
+			liquidity: u128,
+			amount_0_min: AssetBalanceOf<T>,
+			amount_1_min: AssetBalanceOf<T>,
+			deadline: Option<BlockNumberFor<T>>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_deadline(deadline)?;
+
+			Self::do_decrease_liquidity(&who, position_id, liquidity, (amount_0_min, amount_1_min))?;
+			Ok(())
+		}
+
+		/// Pays out the fees earned by a position of the caller and the amounts of the liquidity removed from
+		/// it. A position without liquidity is removed once it has been collected.
+		#[pallet::call_index(22)]
+		#[pallet::weight(T::WeightInfo::collect())]
+		pub fn collect(origin: OriginFor<T>, position_id: PositionId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			Self::do_collect(&who, position_id)?;
+			Ok(())
+		}
+
+		/// Swaps exactly `amount_in` of one asset of a concentrated liquidity pool for at least `min_out` of the
+		/// other one. The swap crosses as many ticks as needed, it fails if the liquidity in range runs out.
+		#[pallet::call_index(23)]
+		#[pallet::weight(T::WeightInfo::swap_concentrated(T::MaxConcentratedTicks::get()))]
+		pub fn swap_concentrated(
+			origin: OriginFor<T>,
+			pool_id: ConcentratedPoolId,
+			asset_in: BalanceType<T>,
+			amount_in: AssetBalanceOf<T>,
+			min_out: AssetBalanceOf<T>,
+			deadline: Option<BlockNumberFor<T>>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_deadline(deadline)?;
+
+			Self::do_swap_concentrated(&who, pool_id, asset_in, amount_in, min_out)?;
+			Ok(())
+		}
+
+		/// Places an order to sell `amount_in` of `asset_in` for at least `limit_price` of `asset_out` per unit in
+		/// the pool with the fee tier. The amount is held in the escrow account and the order is filled at once
+		/// in `on_idle` or by anyone calling `fill_order` as soon as the pool pays the limit price. A pool in
+		/// batch mode or with paused swaps doesn't take orders.
+		///
+		/// # Arguments
+		///
+		/// * `origin` - The origin caller of the function, who owns the order.
+		/// * `asset_in` - The asset to sell.
+		/// * `asset_out` - The asset to buy.
+		/// * `fee` - The fee tier of the pool to fill the order in.
+		/// * `amount_in` - The amount to sell.
+		/// * `limit_price` - The least amount of `asset_out` per unit of `asset_in`.
+		/// * `expires_at` - The last block the order can be filled in, it is paid back after it.
+		#[pallet::call_index(24)]
+		#[pallet::weight(T::WeightInfo::place_limit_order())]
+		pub fn place_limit_order(
+			origin: OriginFor<T>,
+			asset_in: BalanceType<T>,
+			asset_out: BalanceType<T>,
+			fee: Permill,
+			amount_in: AssetBalanceOf<T>,
+			limit_price: FixedU128,
+			expires_at: BlockNumberFor<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			Self::do_place_limit_order(&who, asset_in, asset_out, fee, amount_in, limit_price, expires_at)?;
+			Ok(())
+		}
+
+		/// Cancels a limit order and pays its amount back. The owner can cancel an order at any time, anyone
+		/// can clean up an order once it has expired.
+		#[pallet::call_index(25)]
+		#[pallet::weight(T::WeightInfo::cancel_limit_order())]
+		pub fn cancel_limit_order(origin: OriginFor<T>, order_id: OrderId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			let order = LimitOrders::<T>::get(order_id).ok_or(Error::<T>::OrderDoesNotExist)?;
+			let expired = frame_system::Pallet::<T>::block_number() > order.expires_at;
+			ensure!(order.owner == who || expired, Error::<T>::NotOrderOwner);
+
+			Self::refund_limit_order(order_id, &order)?;
+			let pool_id = Self::lp_token_id(order.asset_in, order.asset_out, order.fee);
+			let (owner, amount_in) = (order.owner, order.amount_in);
+			if expired {
+				Self::deposit_event(Event::<T>::LimitOrderExpired { order_id, owner, pool_id, amount_in });
+			} else {
+				Self::deposit_event(Event::<T>::LimitOrderCancelled { order_id, owner, pool_id, amount_in });
+			}
+			Ok(())
+		}
+
+		/// Fills a limit order whose limit price the pool pays, for keepers who don't want to wait for
+		/// `on_idle`. It fails with `OrderNotFillable` if the pool doesn't pay the limit price.
+		#[pallet::call_index(26)]
+		#[pallet::weight(T::WeightInfo::fill_order())]
+		pub fn fill_order(origin: OriginFor<T>, order_id: OrderId) -> DispatchResult {
+			ensure_signed(origin)?;
+
+			Self::do_fill_order(order_id)?;
+			Ok(())
+		}
+
+		/// SetBatchMode: Switches a pool between immediate swaps and batch auctions. In batch mode the swaps
+		/// of a block are submitted as intents with `submit_swap_intent` and cleared together at a uniform
+		/// price in `on_finalize`, so the order of the swaps within the block can't be used for sandwich
+		/// attacks. Immediate swaps, multi-hop swaps through the pool and flash swaps fail while it is on.
+		///
+		/// # Arguments
+		///
+		/// * `origin` - Has to be the governance origin.
+		/// * `asset_a` - One of the assets in the liquidity pool.
+		/// * `asset_b` - The other asset in the liquidity pool.
+		/// * `fee` - The fee tier of the liquidity pool.
+		/// * `enabled` - Whether the pool only swaps in batches.
+		#[pallet::call_index(27)]
+		#[pallet::weight(T::WeightInfo::set_batch_mode())]
+		pub fn set_batch_mode(
+			origin: OriginFor<T>,
+			asset_a: BalanceType<T>,
+			asset_b: BalanceType<T>,
+			fee: Permill,
+			enabled: bool,
+		) -> DispatchResult {
+			T::GovernanceOrigin::ensure_origin(origin)?;
+
+			// Order the asset ids, the pool has to exist
+			let (asset_a, asset_b, _, _) = Self::order_asset_ids(asset_a, asset_b, AssetBalanceOf::<T>::zero(), AssetBalanceOf::<T>::zero())?;
+			let pool_key = (asset_a.clone(), asset_b.clone(), fee);
+			let pool = LiquidityPoolMap::<T>::get(&pool_key).ok_or(Error::<T>::PoolDoesNotExist)?;
+
+			if enabled {
+				BatchModePools::<T>::insert(&pool_key, true);
+			} else {
+				BatchModePools::<T>::remove(&pool_key);
+			}
+
+			Self::deposit_event(Event::<T>::BatchModeSet { pool_id: pool.liquidity_id, asset_a, asset_b, fee, enabled });
+
+			Ok(())
+		}
+
+		/// SubmitSwapIntent: Submits a swap of exactly `amount_in` of `asset_in` for at least `min_out` of
+		/// `asset_out` to a pool in batch mode. The amount is held in the escrow account and the intent is
+		/// cleared with the other intents of the pool at the end of the block. It is paid back if its share
+		/// of the batch is below `min_out`.
+		///
+		/// # Arguments
+		///
+		/// * `origin` - The origin caller of the function, who will be swapping.
+		/// * `asset_in` - The asset to sell.
+		/// * `asset_out` - The asset to buy.
+		/// * `fee` - The fee tier of the pool.
+		/// * `amount_in` - The amount to sell.
+		/// * `min_out` - The least amount of `asset_out` to receive.
+		/// * `deadline` - The last block in which the intent can be submitted.
+		#[pallet::call_index(28)]
+		#[pallet::weight(Pallet::<T>::swap_intent_weight())]
+		pub fn submit_swap_intent(
+			origin: OriginFor<T>,
+			asset_in: BalanceType<T>,
+			asset_out: BalanceType<T>,
+			fee: Permill,
+			amount_in: AssetBalanceOf<T>,
+			min_out: AssetBalanceOf<T>,
+			deadline: Option<BlockNumberFor<T>>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_deadline(deadline)?;
+
+			Self::do_submit_swap_intent(&who, asset_in, asset_out, fee, amount_in, min_out)?;
+			Ok(())
+		}
+
+		/// PausePool: Pauses swaps, adds or both in a pool in an emergency, e.g. when an exploit is found.
+		/// `remove_liquidity` stays open so LPs can always exit. Replaces the current pause of the pool.
+		///
+		/// # Arguments
+		///
+		/// * `origin` - Has to be the pause origin.
+		/// * `asset_a` - One of the assets in the liquidity pool.
+		/// * `asset_b` - The other asset in the liquidity pool.
+		/// * `fee` - The fee tier of the liquidity pool.
+		/// * `flags` - What is paused in the pool.
+		#[pallet::call_index(29)]
+		#[pallet::weight(T::WeightInfo::pause_pool())]
+		pub fn pause_pool(
+			origin: OriginFor<T>,
+			asset_a: BalanceType<T>,
+			asset_b: BalanceType<T>,
+			fee: Permill,
+			flags: PauseFlags,
+		) -> DispatchResult {
+			T::PauseOrigin::ensure_origin(origin)?;
+			ensure!(flags.swaps || flags.adds, Error::<T>::NothingToPause);
+
+			// Order the asset ids, the pool has to exist
+			let (asset_a, asset_b, _, _) = Self::order_asset_ids(asset_a, asset_b, AssetBalanceOf::<T>::zero(), AssetBalanceOf::<T>::zero())?;
+			let pool_key = (asset_a.clone(), asset_b.clone(), fee);
+			let pool = LiquidityPoolMap::<T>::get(&pool_key).ok_or(Error::<T>::PoolDoesNotExist)?;
+
+			PausedPools::<T>::insert(&pool_key, flags);
+
+			Self::deposit_event(Event::<T>::PoolPaused { pool_id: pool.liquidity_id, asset_a, asset_b, fee, flags });
+
+			Ok(())
+		}
+
+		/// UnpausePool: Lifts the pause of a pool. A pause of all the pools still applies to it.
+		///
+		/// # Arguments
+		///
+		/// * `origin` - Has to be the pause origin.
+		/// * `asset_a` - One of the assets in the liquidity pool.
+		/// * `asset_b` - The other asset in the liquidity pool.
+		/// * `fee` - The fee tier of the liquidity pool.
+		#[pallet::call_index(30)]
+		#[pallet::weight(T::WeightInfo::unpause_pool())]
+		pub fn unpause_pool(
+			origin: OriginFor<T>,
+			asset_a: BalanceType<T>,
+			asset_b: BalanceType<T>,
+			fee: Permill,
+		) -> DispatchResult {
+			T::PauseOrigin::ensure_origin(origin)?;
+
+			// Order the asset ids, the pool has to exist
+			let (asset_a, asset_b, _, _) = Self::order_asset_ids(asset_a, asset_b, AssetBalanceOf::<T>::zero(), AssetBalanceOf::<T>::zero())?;
+			let pool_key = (asset_a.clone(), asset_b.clone(), fee);
+			let pool = LiquidityPoolMap::<T>::get(&pool_key).ok_or(Error::<T>::PoolDoesNotExist)?;
+
+			PausedPools::<T>::remove(&pool_key);
+
+			Self::deposit_event(Event::<T>::PoolUnpaused { pool_id: pool.liquidity_id, asset_a, asset_b, fee });
+
+			Ok(())
+		}
+
+		/// PauseAll: Pauses swaps, adds or both in all the pools, including the weighted and concentrated
+		/// liquidity pools. Exits stay open. Replaces the current pause of all the pools.
+		///
+		/// # Arguments
+		///
+		/// * `origin` - Has to be the pause origin.
+		/// * `flags` - What is paused in all the pools.
+		#[pallet::call_index(31)]
+		#[pallet::weight(T::WeightInfo::pause_all())]
+		pub fn pause_all(origin: OriginFor<T>, flags: PauseFlags) -> DispatchResult {
+			T::PauseOrigin::ensure_origin(origin)?;
+			ensure!(flags.swaps || flags.adds, Error::<T>::NothingToPause);
+
+			GlobalPause::<T>::put(flags);
+
+			Self::deposit_event(Event::<T>::AllPaused { flags });
+
+			Ok(())
+		}
+
+		/// UnpauseAll: Lifts the pause of all the pools. The pauses of single pools stay.
+		///
+		/// # Arguments
+		///
+		/// * `origin` - Has to be the pause origin.
+		#[pallet::call_index(32)]
+		#[pallet::weight(T::WeightInfo::unpause_all())]
+		pub fn unpause_all(origin: OriginFor<T>) -> DispatchResult {
+			T::PauseOrigin::ensure_origin(origin)?;
+
+			GlobalPause::<T>::kill();
+
+			Self::deposit_event(Event::<T>::AllUnpaused);
+
+			Ok(())
+		}
+
+		/// PauseWeightedPool: Pauses swaps, adds or both in a weighted pool, like `pause_pool`. Exits stay
+		/// open. Replaces the current pause of the pool.
+		///
+		/// # Arguments
+		///
+		/// * `origin` - Has to be the pause origin.
+		/// * `pool_id` - The weighted pool.
+		/// * `flags` - What is paused in the pool.
+		#[pallet::call_index(33)]
+		#[pallet::weight(T::WeightInfo::pause_weighted_pool())]
+		pub fn pause_weighted_pool(origin: OriginFor<T>, pool_id: WeightedPoolId, flags: PauseFlags) -> DispatchResult {
+			T::PauseOrigin::ensure_origin(origin)?;
+			ensure!(flags.swaps || flags.adds, Error::<T>::NothingToPause);
+			ensure!(WeightedPools::<T>::contains_key(pool_id), Error::<T>::PoolDoesNotExist);
+
+			PausedWeightedPools::<T>::insert(pool_id, flags);
+
+			Self::deposit_event(Event::<T>::WeightedPoolPaused { pool_id, flags });
+
+			Ok(())
+		}
+
+		/// UnpauseWeightedPool: Lifts the pause of a weighted pool. A pause of all the pools still applies to it.
+		///
+		/// # Arguments
+		///
+		/// * `origin` - Has to be the pause origin.
+		/// * `pool_id` - The weighted pool.
+		#[pallet::call_index(34)]
+		#[pallet::weight(T::WeightInfo::unpause_weighted_pool())]
+		pub fn unpause_weighted_pool(origin: OriginFor<T>, pool_id: WeightedPoolId) -> DispatchResult {
+			T::PauseOrigin::ensure_origin(origin)?;
+			ensure!(WeightedPools::<T>::contains_key(pool_id), Error::<T>::PoolDoesNotExist);
+
+			PausedWeightedPools::<T>::remove(pool_id);
+
+			Self::deposit_event(Event::<T>::WeightedPoolUnpaused { pool_id });
+
+			Ok(())
+		}
+
+		/// PauseConcentratedPool: Pauses swaps, adds or both in a concentrated liquidity pool, like
+		/// `pause_pool`. `decrease_liquidity` and `collect` stay open. Replaces the current pause of the pool.
+		///
+		/// # Arguments
+		///
+		/// * `origin` - Has to be the pause origin.
+		/// * `pool_id` - The concentrated liquidity pool.
+		/// * `flags` - What is paused in the pool.
+		#[pallet::call_index(35)]
+		#[pallet::weight(T::WeightInfo::pause_concentrated_pool())]
+		pub fn pause_concentrated_pool(
+			origin: OriginFor<T>,
+			pool_id: ConcentratedPoolId,
+			flags: PauseFlags,
+		) -> DispatchResult {
+			T::PauseOrigin::ensure_origin(origin)?;
+			ensure!(flags.swaps || flags.adds, Error::<T>::NothingToPause);
+			ensure!(ConcentratedPools::<T>::contains_key(pool_id), Error::<T>::PoolDoesNotExist);
+
+			PausedConcentratedPools::<T>::insert(pool_id, flags);
+
+			Self::deposit_event(Event::<T>::ConcentratedPoolPaused { pool_id, flags });
+
+			Ok(())
+		}
+
+		/// UnpauseConcentratedPool: Lifts the pause of a concentrated liquidity pool. A pause of all the pools
+		/// still applies to it.
+		///
+		/// # Arguments
+		///
+		/// * `origin` - Has to be the pause origin.
+		/// * `pool_id` - The concentrated liquidity pool.
+		#[pallet::call_index(36)]
+		#[pallet::weight(T::WeightInfo::unpause_concentrated_pool())]
+		pub fn unpause_concentrated_pool(origin: OriginFor<T>, pool_id: ConcentratedPoolId) -> DispatchResult {
+			T::PauseOrigin::ensure_origin(origin)?;
+			ensure!(ConcentratedPools::<T>::contains_key(pool_id), Error::<T>::PoolDoesNotExist);
+
+			PausedConcentratedPools::<T>::remove(pool_id);
+
+			Self::deposit_event(Event::<T>::ConcentratedPoolUnpaused { pool_id });
+
+			Ok(())
+		}
+
+		/// ClaimSwapIntentRefund: Pays back the amounts of the swap intents of the caller selling `asset` that
+		/// couldn't be paid back in `on_finalize`, e.g. because the asset was frozen at the time.
+		///
+		/// # Arguments
+		///
+		/// * `origin` - The account that submitted the intents.
+		/// * `asset` - The asset the intents sold.
+		#[pallet::call_index(37)]
+		#[pallet::weight(T::WeightInfo::claim_swap_intent_refund())]
+		pub fn claim_swap_intent_refund(origin: OriginFor<T>, asset: BalanceType<T>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			let amount = UnclaimedRefunds::<T>::take(&who, &asset);
+			ensure!(!amount.is_zero(), Error::<T>::NothingToClaim);
+			Self::transfer_asset(&asset, &Self::escrow_account(), &who, amount)?;
+
+			Self::deposit_event(Event::<T>::SwapIntentRefundClaimed { who, asset, amount });
+
+			Ok(())
+		}
+	}
+}
+
+use frame_support::sp_runtime::traits::Hash;
+use frame_support::storage::with_storage_layer;
+use sp_runtime::{
+	traits::{CheckedMul, TrailingZeroInput, UniqueSaturatedInto},
+	ArithmeticError, FixedPointNumber, FixedU128, Perbill, Percent, Permill,
+};
+
+use frame_support::traits::{
+	fungible::{Inspect as FInspect, Mutate},
+	fungibles::{
+		metadata::{Inspect as MetadataInspect, Mutate as MetadataMutate},
+		Create, Destroy, Inspect, Mutate as FSMutate,
+	},
+	tokens::{Precision, Fortitude, Preservation}
+};
 
 use frame_support::dispatch::Vec;
 
-use sp_arithmetic::traits::IntegerSquareRoot;
+use frame_system::pallet_prelude::BlockNumberFor;
+
+use sp_arithmetic::{
+	helpers_128bit::multiply_by_rational_with_rounding,
+	per_things::Rounding,
+	traits::IntegerSquareRoot,
+};
+
+use sp_core::U256;
+
+use core::fmt::Write;
+
+/// Collects formatted text as bytes, for the metadata of LP tokens.
+struct MetadataWriter(Vec<u8>);
+
+impl Write for MetadataWriter {
+	fn write_str(&mut self, s: &str) -> core::fmt::Result {
+		self.0.extend_from_slice(s.as_bytes());
+		Ok(())
+	}
+}
+
+/// How a batch of swap intents clears at a uniform price, see `Pallet::batch_clearing`.
+struct BatchClearing {
+	/// The total amount of asset_a sold by the intents.
+	sold_a: u128,
+	/// The total amount of asset_b sold by the intents.
+	sold_b: u128,
+	/// Whether the pool takes in asset_a, the side selling more swaps the difference with the pool.
+	pool_takes_a: bool,
+	/// The amount the pool takes in.
+	pool_in: u128,
+	/// The amount of the other asset the pool pays out for it.
+	pool_out: u128,
+	/// The amount every intent receives, in the order of the intents.
+	amounts_out: Vec<u128>,
+	/// The price of asset_a in asset_b every intent trades at.
+	price: FixedU128,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Creates the pool of `asset_a` and `asset_b` on `curve` with the liquidity of `who`.
+	pub fn do_create_pool(
+		who: &T::AccountId,
+		asset_a: BalanceType<T>,
+		asset_b: BalanceType<T>,
+		fee: Permill,
+		curve: PoolCurve,
+		amount_a: AssetBalanceOf<T>,
+		amount_b: AssetBalanceOf<T>,
+	) -> DispatchResult {
+		// order assets_a and assets_b
+		let (asset_a, asset_b, amount_a, amount_b) = Self::order_asset_ids(asset_a, asset_b, amount_a, amount_b)?;
+
+		// Ensure the caller has enough balances in both of the assets, where he is providing liquidity
+		// and the that the balances of both assets are greater then minimum value required
+		Self::check_user_balances(who, &asset_a, &asset_b, &amount_a, &amount_b)?;
+
+		// Only the fee tiers in the allow-list can be used
+		ensure!(Self::is_fee_tier_allowed(fee), Error::<T>::FeeTierNotAllowed);
+
+		// Both initial amounts have to be at least the minimal deposit
+		let min_deposit = AssetBalanceOf::<T>::from(T::MinPoolDeposit::get());
+		ensure!(amount_a >= min_deposit && amount_b >= min_deposit, Error::<T>::InitialDepositTooLow);
+
+		// Liquidity amount is equal to sqrt(k), sqrt(amount_a * amount_b) for the constant product
+		let amount_liq: AssetBalanceOf<T> = Self::get_sqrt_of_asset_balance(Self::curve_k(&curve, amount_a, amount_b)?);
+
+		// The minimum liquidity is locked, the creator gets the rest
+		let locked_liq = T::MinimumLiquidity::get();
+		let creator_liq = amount_liq.checked_sub(&locked_liq).filter(|liq| !liq.is_zero()).ok_or(Error::<T>::InsufficientLiquidityMinted)?;
+		
+		// Check if the liquidity pool already exists
+		ensure!(!LiquidityPoolMap::<T>::contains_key(&(asset_a.clone(), asset_b.clone(), fee)), Error::<T>::PoolAlreadyExists);
+
+		// The LP token takes the next free LP token id
+		let lp_asset_id = Self::take_lp_asset_id()?;
+
+		// Get the pool account
+		let pool_account = Self::generate_account_from_asset_id_pair(asset_a.clone(), asset_b.clone(), fee);
+	
+		// native currency is transferd while creating this account
+		T::NativeBalance::transfer(who, &pool_account, BalanceOf::<T>::from(POOL_ACCOUNT_DEPOSIT), Preservation::Expendable)?;
+		
+		// The protocol fee is accrued from the first liquidity event on
+		let k_last = if Self::active_protocol_fee_recipient().is_some() {
+			Self::curve_k(&curve, amount_a, amount_b)?
+		} else {
+			Zero::zero()
+		};
+
+		// Save the new liquidity pool in the storage, from now on its reserves are tracked there
+		let now = frame_system::Pallet::<T>::block_number();
+		LiquidityPoolMap::<T>::insert(&(asset_a.clone(), asset_b.clone(), fee), LiquidityPool {
+			pool_account: pool_account.clone(),
+			liquidity_id: lp_asset_id.clone(),
+			reserve_a: amount_a,
+			reserve_b: amount_b,
+			creator: who.clone(),
+			created_at: now,
+			fee,
+			curve,
+			price_a_cumulative: FixedU128::zero(),
+			price_b_cumulative: FixedU128::zero(),
+			last_oracle_update: now,
+			k_last,
+		});
+
+		// The price history of the pool starts now
+		Self::record_price_observation(&(asset_a.clone(), asset_b.clone(), fee), PriceObservation {
+			block: now,
+			price_a_cumulative: FixedU128::zero(),
+			price_b_cumulative: FixedU128::zero(),
+		});
+
+		// create an event
+		Self::deposit_event(Event::<T>::PoolCreated {
+			who: who.clone(),
+			pool_id: lp_asset_id.clone(),
+			asset_a: asset_a.clone(),
+			asset_b: asset_b.clone(),
+			fee,
+			amount_a,
+			amount_b,
+			amount_liq: creator_liq,
+		});
+
+		// transfer the tokens from the users accout into pool account 
+		Self::transfer_assets_a_and_b(who, &pool_account, &asset_a, &asset_b, &amount_a, &amount_b)?;
+
+		let metadata = Self::lp_token_metadata(&asset_a, &asset_b, fee);
+		Self::mint_initial_liquidity(lp_asset_id, metadata, who, &pool_account, locked_liq, creator_liq)
+	}
+
+	/// Creates the LP token of a new pool with its name and symbol, locks `locked_liq` of it in the account
+	/// of the pool and mints `creator_liq` to the creator of the pool.
+	fn mint_initial_liquidity(
+		lp_asset_id: AssetIdOf<T>,
+		(name, symbol): (Vec<u8>, Vec<u8>),
+		creator: &T::AccountId,
+		pool_account: &T::AccountId,
+		locked_liq: AssetBalanceOf<T>,
+		creator_liq: AssetBalanceOf<T>,
+	) -> DispatchResult {
+		// the pallet account owns the liquidity token, only the pallet can mint and burn it
+		let pallet_account = Self::account_id();
+		T::Fungibles::create(lp_asset_id.clone(), pallet_account.clone(), false, One::one())?;
+
+		// the pallet account has to exist to keep the metadata deposit reserved
+		Self::ensure_account_funded(&pallet_account, creator)?;
+
+		// the creator pays the metadata deposit, it is reserved from the pallet account as the owner
+		T::NativeBalance::transfer(creator, &pallet_account, T::LpMetadataDeposit::get(), Preservation::Expendable)?;
+		T::Fungibles::set(lp_asset_id.clone(), &pallet_account, name, symbol, LP_TOKEN_DECIMALS)?;
+
+		// lock the minimum liquidity in the pool account, nobody can ever redeem it. It is kept apart from
+		// everything else the pallet holds, so removing the pool burns exactly the locked share
+		T::Fungibles::mint_into(lp_asset_id.clone(), pool_account, locked_liq)?;
+
+		// mint the rest of the lp tokens into the users account
+		T::Fungibles::mint_into(lp_asset_id, creator, creator_liq)?;
+
+		Ok(())
+	}
+
+	/// Creates a weighted pool of `assets`, given with their weights and initial amounts, with the liquidity
+	/// of `who`. Returns the id of the new pool.
+	pub fn do_create_weighted_pool(
+		who: &T::AccountId,
+		assets: Vec<(BalanceType<T>, Perbill, AssetBalanceOf<T>)>,
+		fee: Permill,
+	) -> Result<WeightedPoolId, DispatchError> {
+		// A pool needs at least two different assets, the maximum is enforced by the bounded call argument
+		ensure!(assets.len() >= 2, Error::<T>::InvalidWeightedPoolAssets);
+		for (i, (asset, _, _)) in assets.iter().enumerate() {
+			ensure!(!assets[i + 1..].iter().any(|(other, _, _)| other == asset), Error::<T>::InvalidWeightedPoolAssets);
+		}
+
+		// The weights have to be normalized
+		ensure!(assets.iter().all(|(_, weight, _)| *weight >= WEIGHTED_MIN_WEIGHT), Error::<T>::InvalidWeights);
+		let total_weight: u64 = assets.iter().map(|(_, weight, _)| u64::from(weight.deconstruct())).sum();
+		ensure!(total_weight == u64::from(Perbill::one().deconstruct()), Error::<T>::InvalidWeights);
+
+		// Only the fee tiers in the allow-list can be used
+		ensure!(Self::is_fee_tier_allowed(fee), Error::<T>::FeeTierNotAllowed);
+
+		// All the initial amounts have to be at least the minimal deposit and the caller has to hold them
+		let min_deposit = AssetBalanceOf::<T>::from(T::MinPoolDeposit::get());
+		for (asset, _, amount) in assets.iter() {
+			ensure!(*amount >= min_deposit, Error::<T>::InitialDepositTooLow);
+			ensure!(Self::asset_balance(asset, who) >= *amount, Error::<T>::InsufficientBalance);
+		}
+
+		// The initial liquidity is the invariant prod(amount_i ^ weight_i), the minimum liquidity is locked
+		let balances = assets
+			.iter()
+			.map(|(_, weight, amount)| ((*amount).unique_saturated_into(), *weight))
+			.collect::<Vec<(u128, Perbill)>>();
+		let amount_liq = weighted::invariant(&balances).ok_or(ArithmeticError::Overflow)?;
+		let amount_liq = AssetBalanceOf::<T>::try_from(amount_liq).map_err(|_| ArithmeticError::Overflow)?;
+		let locked_liq = T::MinimumLiquidity::get();
+		let creator_liq = amount_liq.checked_sub(&locked_liq).filter(|liq| !liq.is_zero()).ok_or(Error::<T>::InsufficientLiquidityMinted)?;
+
+		let pool_id = NextWeightedPoolId::<T>::get();
+		NextWeightedPoolId::<T>::put(pool_id.checked_add(1).ok_or(Error::<T>::StorageOverflow)?);
+		let lp_asset_id = Self::take_lp_asset_id()?;
+		let pool_account = Self::weighted_pool_account(pool_id);
+
+		// native currency is transferd while creating this account
+		T::NativeBalance::transfer(who, &pool_account, BalanceOf::<T>::from(POOL_ACCOUNT_DEPOSIT), Preservation::Expendable)?;
+
+		// transfer the initial amounts into the pool account
+		for (asset, _, amount) in assets.iter() {
+			Self::transfer_asset(asset, who, &pool_account, *amount)?;
+		}
+
+		let amounts = assets.iter().map(|(asset, _, amount)| (asset.clone(), *amount)).collect::<Vec<_>>();
+		let amounts = BoundedVec::try_from(amounts).map_err(|_| Error::<T>::InvalidWeightedPoolAssets)?;
+		let weighted_assets = assets
+			.into_iter()
+			.map(|(asset, weight, reserve)| WeightedAsset { asset, weight, reserve })
+			.collect::<Vec<_>>();
+		WeightedPools::<T>::insert(pool_id, WeightedPool {
+			pool_account: pool_account.clone(),
+			liquidity_id: lp_asset_id.clone(),
+			assets: BoundedVec::try_from(weighted_assets).map_err(|_| Error::<T>::InvalidWeightedPoolAssets)?,
+			fee,
+			creator: who.clone(),
+		});
+
+		Self::deposit_event(Event::<T>::WeightedPoolCreated {
+			who: who.clone(),
+			pool_id,
+			liquidity_id: lp_asset_id.clone(),
+			fee,
+			amounts,
+			amount_liq: creator_liq,
+		});
+
+		let metadata = Self::weighted_lp_token_metadata(pool_id);
+		Self::mint_initial_liquidity(lp_asset_id, metadata, who, &pool_account, locked_liq, creator_liq)?;
+
+		Ok(pool_id)
+	}
+
+	/// Deposits only `amount_in` of `asset` into the weighted pool on behalf of `who`. Returns the minted LP tokens.
+	pub fn do_join_weighted_pool(
+		who: &T::AccountId,
+		pool_id: WeightedPoolId,
+		asset: BalanceType<T>,
+		amount_in: AssetBalanceOf<T>,
+		min_liquidity: AssetBalanceOf<T>,
+	) -> Result<AssetBalanceOf<T>, DispatchError> {
+		let mut pool = WeightedPools::<T>::get(pool_id).ok_or(Error::<T>::PoolDoesNotExist)?;
+		ensure!(!Self::with_global_pause(PausedWeightedPools::<T>::get(pool_id)).adds, Error::<T>::PoolPaused);
+		let index = Self::weighted_asset_index(&pool, &asset)?;
+		let WeightedAsset { weight, reserve, .. } = pool.assets[index].clone();
+
+		ensure!(!amount_in.is_zero() && Self::asset_balance(&asset, who) >= amount_in, Error::<T>::InsufficientBalance);
+		ensure!(amount_in <= reserve / AssetBalanceOf::<T>::from(WEIGHTED_MAX_IN_RATIO), Error::<T>::MaxRatioExceeded);
+
+		// supply * ((1 + amount_in / reserve)^weight - 1), the unmatched part of amount_in pays the fee
+		let supply = T::Fungibles::total_issuance(pool.liquidity_id.clone());
+		let amount_liq = weighted::liquidity_out_for_join(
+			reserve.unique_saturated_into(),
+			weight,
+			supply.unique_saturated_into(),
+			amount_in.unique_saturated_into(),
+			pool.fee,
+		)
+		.ok_or(ArithmeticError::Overflow)?;
+		let amount_liq = AssetBalanceOf::<T>::try_from(amount_liq).map_err(|_| ArithmeticError::Overflow)?;
+		ensure!(!amount_liq.is_zero() && amount_liq >= min_liquidity, Error::<T>::NotEnoughLiquidityTokens);
+
+		Self::transfer_asset(&asset, who, &pool.pool_account, amount_in)?;
+		T::Fungibles::mint_into(pool.liquidity_id.clone(), who, amount_liq)?;
+
+		// update the tracked reserve
+		pool.assets[index].reserve = reserve.checked_add(&amount_in).ok_or(ArithmeticError::Overflow)?;
+		WeightedPools::<T>::insert(pool_id, pool);
+
+		Self::deposit_event(Event::<T>::WeightedPoolJoined { who: who.clone(), pool_id, asset, amount_in, amount_liq });
+
+		Ok(amount_liq)
+	}
+
+	/// Burns `amount_liq` LP tokens of the weighted pool of `who` for only `asset`. Returns the amount paid out.
+	pub fn do_exit_weighted_pool(
+		who: &T::AccountId,
+		pool_id: WeightedPoolId,
+		asset: BalanceType<T>,
+		amount_liq: AssetBalanceOf<T>,
+		min_amount_out: AssetBalanceOf<T>,
+	) -> Result<AssetBalanceOf<T>, DispatchError> {
+		let mut pool = WeightedPools::<T>::get(pool_id).ok_or(Error::<T>::PoolDoesNotExist)?;
+		let index = Self::weighted_asset_index(&pool, &asset)?;
+		let WeightedAsset { weight, reserve, .. } = pool.assets[index].clone();
+
+		// Checks if the user has enough liquidity tokens
+		ensure!(
+			!amount_liq.is_zero() && T::Fungibles::balance(pool.liquidity_id.clone(), who) >= amount_liq,
+			Error::<T>::NotEnoughLiquidityTokens
+		);
+
+		// reserve * (1 - (1 - amount_liq / supply)^(1 / weight)), the unmatched part pays the fee
+		let supply = T::Fungibles::total_issuance(pool.liquidity_id.clone());
+		let amount_out = weighted::amount_out_for_exit(
+			reserve.unique_saturated_into(),
+			weight,
+			supply.unique_saturated_into(),
+			amount_liq.unique_saturated_into(),
+			pool.fee,
+		)
+		.ok_or(ArithmeticError::Overflow)?;
+		let amount_out = AssetBalanceOf::<T>::try_from(amount_out).map_err(|_| ArithmeticError::Overflow)?;
+		ensure!(amount_out <= reserve / AssetBalanceOf::<T>::from(WEIGHTED_MAX_OUT_RATIO), Error::<T>::MaxRatioExceeded);
+		ensure!(amount_out >= min_amount_out, Error::<T>::InsufficientBalance);
+
+		T::Fungibles::burn_from(pool.liquidity_id.clone(), who, amount_liq, Precision::Exact, Fortitude::Polite)?;
+		Self::transfer_asset(&asset, &pool.pool_account, who, amount_out)?;
+
+		// update the tracked reserve
+		pool.assets[index].reserve = reserve.checked_sub(&amount_out).ok_or(ArithmeticError::Underflow)?;
+		WeightedPools::<T>::insert(pool_id, pool);
+
+		Self::deposit_event(Event::<T>::WeightedPoolExited { who: who.clone(), pool_id, asset, amount_liq, amount_out });
+
+		Ok(amount_out)
+	}
+
+	/// Swaps exactly `amount_in` of `asset_in` for at least `min_out` of `asset_out` in the weighted pool on
+	/// behalf of `who`. Returns the amount paid out.
+	pub fn do_swap_weighted(
+		who: &T::AccountId,
+		pool_id: WeightedPoolId,
+		asset_in: BalanceType<T>,
+		asset_out: BalanceType<T>,
+		amount_in: AssetBalanceOf<T>,
+		min_out: AssetBalanceOf<T>,
+	) -> Result<AssetBalanceOf<T>, DispatchError> {
+		let mut pool = WeightedPools::<T>::get(pool_id).ok_or(Error::<T>::PoolDoesNotExist)?;
+		ensure!(!Self::with_global_pause(PausedWeightedPools::<T>::get(pool_id)).swaps, Error::<T>::PoolPaused);
+		let index_in = Self::weighted_asset_index(&pool, &asset_in)?;
+		let index_out = Self::weighted_asset_index(&pool, &asset_out)?;
+		ensure!(index_in != index_out, Error::<T>::InvalidPath);
+
+		// check user has enough balance
+		ensure!(Self::asset_balance(&asset_in, who) >= amount_in, Error::<T>::InsufficientBalance);
+
+		// calculate amount out with the weighted-product formula, the fee is taken from amount_in
+		let amount_out = Self::weighted_amount_out(&pool, index_in, index_out, amount_in)?;
+
+		// check minimum output
+		ensure!(amount_out >= min_out, Error::<T>::InsufficientBalance);
+
+		Self::transfer_asset(&asset_in, who, &pool.pool_account, amount_in)?;
+		Self::transfer_asset(&asset_out, &pool.pool_account, who, amount_out)?;
+
+		// update the tracked reserves
+		let reserve_in_after = pool.assets[index_in].reserve.checked_add(&amount_in).ok_or(ArithmeticError::Overflow)?;
+		let reserve_out_after = pool.assets[index_out].reserve.checked_sub(&amount_out).ok_or(ArithmeticError::Underflow)?;
+		pool.assets[index_in].reserve = reserve_in_after;
+		pool.assets[index_out].reserve = reserve_out_after;
+		let fee = amount_in.saturating_sub(pool.fee.left_from_one() * amount_in);
+		WeightedPools::<T>::insert(pool_id, pool);
+
+		Self::deposit_event(Event::<T>::WeightedSwapped {
+			who: who.clone(),
+			pool_id,
+			asset_in,
+			asset_out,
+			amount_in,
+			amount_out,
+			fee,
+			reserve_in_after,
+			reserve_out_after,
+		});
+
+		Ok(amount_out)
+	}
+
+	/// Returns the position of `asset` in the assets of the weighted pool.
+	fn weighted_asset_index(pool: &WeightedPool<T>, asset: &BalanceType<T>) -> Result<usize, DispatchError> {
+		pool.assets.iter().position(|weighted| weighted.asset == *asset).ok_or(Error::<T>::AssetNotInPool.into())
+	}
+
+	/// Calculates the amount of the asset at `index_out` the weighted pool pays out for `amount_in` of the
+	/// asset at `index_in`, within the bounds of `WEIGHTED_MAX_IN_RATIO` and `WEIGHTED_MAX_OUT_RATIO`.
+	fn weighted_amount_out(
+		pool: &WeightedPool<T>,
+		index_in: usize,
+		index_out: usize,
+		amount_in: AssetBalanceOf<T>,
+	) -> Result<AssetBalanceOf<T>, DispatchError> {
+		let (asset_in, asset_out) = (&pool.assets[index_in], &pool.assets[index_out]);
+		ensure!(amount_in <= asset_in.reserve / AssetBalanceOf::<T>::from(WEIGHTED_MAX_IN_RATIO), Error::<T>::MaxRatioExceeded);
+
+		let amount_out = weighted::amount_out(
+			asset_in.reserve.unique_saturated_into(),
+			asset_in.weight,
+			asset_out.reserve.unique_saturated_into(),
+			asset_out.weight,
+			amount_in.unique_saturated_into(),
+			pool.fee,
+		)
+		.ok_or(ArithmeticError::Overflow)?;
+		let amount_out = AssetBalanceOf::<T>::try_from(amount_out).map_err(|_| ArithmeticError::Overflow)?;
+		ensure!(amount_out <= asset_out.reserve / AssetBalanceOf::<T>::from(WEIGHTED_MAX_OUT_RATIO), Error::<T>::MaxRatioExceeded);
+
+		Ok(amount_out)
+	}
+
+	/// Creates a concentrated liquidity pool of `asset_a` and `asset_b` at `initial_price`, the price of `asset_a`
+	/// in `asset_b`. Returns the id of the new pool.
+	pub fn do_create_concentrated_pool(
+		who: &T::AccountId,
+		asset_a: BalanceType<T>,
+		asset_b: BalanceType<T>,
+		fee: Permill,
+		tick_spacing: u32,
+		initial_price: FixedU128,
+	) -> Result<ConcentratedPoolId, DispatchError> {
+		// order the assets, the price is inverted if asset_b becomes asset_0
+		let (asset_0, asset_1, _, _) = Self::order_asset_ids(asset_a.clone(), asset_b, Zero::zero(), Zero::zero())?;
+		let price = if asset_0 == asset_a { Some(initial_price) } else { initial_price.reciprocal() };
+
+		// Only the fee tiers in the allow-list can be used
+		ensure!(Self::is_fee_tier_allowed(fee), Error::<T>::FeeTierNotAllowed);
+		ensure!(tick_spacing >= 1 && tick_spacing <= concentrated::MAX_TICK_SPACING, Error::<T>::InvalidTickSpacing);
+
+		// The price has to be between the prices of the lowest and the highest tick
+		let sqrt_price = price.and_then(concentrated::sqrt_price_from_price).ok_or(Error::<T>::InvalidPrice)?;
+		let tick = concentrated::tick_at_sqrt_price(sqrt_price)
+			.filter(|tick| *tick < concentrated::MAX_TICK)
+			.ok_or(Error::<T>::InvalidPrice)?;
+
+		let pool_id = NextConcentratedPoolId::<T>::get();
+		NextConcentratedPoolId::<T>::put(pool_id.checked_add(1).ok_or(Error::<T>::StorageOverflow)?);
+		let pool_account = Self::concentrated_pool_account(pool_id);
+
+		// native currency is transferd while creating this account
+		T::NativeBalance::transfer(who, &pool_account, BalanceOf::<T>::from(POOL_ACCOUNT_DEPOSIT), Preservation::Expendable)?;
+
+		ConcentratedPools::<T>::insert(pool_id, ConcentratedPool {
+			pool_account,
+			asset_0: asset_0.clone(),
+			asset_1: asset_1.clone(),
+			fee,
+			tick_spacing,
+			sqrt_price,
+			tick,
+			liquidity: 0,
+			fee_growth_global_0: 0,
+			fee_growth_global_1: 0,
+			creator: who.clone(),
+		});
+
+		Self::deposit_event(Event::<T>::ConcentratedPoolCreated {
+			who: who.clone(),
+			pool_id,
+			asset_0,
+			asset_1,
+			fee,
+			tick_spacing,
+			sqrt_price,
+		});
+
+		Ok(pool_id)
+	}
+
+	/// Provides liquidity between `tick_lower` and `tick_upper` as a new position of `who`, for at most the
+	/// desired amounts of asset_0 and asset_1. Returns the id of the position.
+	pub fn do_mint_position(
+		who: &T::AccountId,
+		pool_id: ConcentratedPoolId,
+		tick_lower: i32,
+		tick_upper: i32,
+		(amount_0_desired, amount_1_desired): (AssetBalanceOf<T>, AssetBalanceOf<T>),
+		(amount_0_min, amount_1_min): (AssetBalanceOf<T>, AssetBalanceOf<T>),
+	) -> Result<PositionId, DispatchError> {
+		let mut pool = ConcentratedPools::<T>::get(pool_id).ok_or(Error::<T>::PoolDoesNotExist)?;
+		ensure!(!Self::with_global_pause(PausedConcentratedPools::<T>::get(pool_id)).adds, Error::<T>::PoolPaused);
+		let (sqrt_lower, sqrt_upper) = Self::check_tick_range(&pool, tick_lower, tick_upper)?;
+
+		// the most liquidity the desired amounts pay for at the current price
+		let liquidity = concentrated::liquidity_for_amounts(
+			pool.sqrt_price,
+			sqrt_lower,
+			sqrt_upper,
+			amount_0_desired.unique_saturated_into(),
+			amount_1_desired.unique_saturated_into(),
+		)
+		.ok_or(ArithmeticError::Overflow)?;
+		ensure!(liquidity > 0, Error::<T>::InsufficientLiquidityMinted);
+		let liquidity_delta = i128::try_from(liquidity).map_err(|_| ArithmeticError::Overflow)?;
+
+		// the amounts paid into the pool are rounded up
+		let (amount_0, amount_1) = Self::position_amounts(&pool, sqrt_lower, sqrt_upper, liquidity, true)?;
+		ensure!(amount_0 >= amount_0_min, Error::<T>::NotEnoughAssetsA);
+		ensure!(amount_1 >= amount_1_min, Error::<T>::NotEnoughAssetsB);
+
+		// the fee growth inside the range is taken after its ticks have been initialized
+		Self::update_position_ticks(pool_id, &mut pool, tick_lower, tick_upper, liquidity_delta)?;
+		let (fee_growth_inside_0, fee_growth_inside_1) = Self::fee_growth_inside(pool_id, &pool, tick_lower, tick_upper);
+
+		// transfer the tokens from the users account into the pool account
+		Self::transfer_asset(&pool.asset_0, who, &pool.pool_account, amount_0)?;
+		Self::transfer_asset(&pool.asset_1, who, &pool.pool_account, amount_1)?;
+
+		let position_id = NextPositionId::<T>::get();
+		NextPositionId::<T>::put(position_id.checked_add(1).ok_or(Error::<T>::StorageOverflow)?);
+		Positions::<T>::insert(who, position_id, Position {
+			pool_id,
+			tick_lower,
+			tick_upper,
+			liquidity,
+			fee_growth_inside_0_last: fee_growth_inside_0,
+			fee_growth_inside_1_last: fee_growth_inside_1,
+			tokens_owed_0: Zero::zero(),
+			tokens_owed_1: Zero::zero(),
+		});
+		ConcentratedPools::<T>::insert(pool_id, pool);
+
+		Self::deposit_event(Event::<T>::PositionMinted {
+			who: who.clone(),
+			pool_id,
+			position_id,
+			tick_lower,
+			tick_upper,
+			liquidity,
+			amount_0,
+			amount_1,
+		});
+
+		Ok(position_id)
+	}
+
+	/// Removes `liquidity` from the position of `who`, the amounts of asset_0 and asset_1 it was made of are
+	/// owed to the position until they are collected. Returns the amounts.
+	pub fn do_decrease_liquidity(
+		who: &T::AccountId,
+		position_id: PositionId,
+		liquidity: u128,
+		(amount_0_min, amount_1_min): (AssetBalanceOf<T>, AssetBalanceOf<T>),
+	) -> Result<(AssetBalanceOf<T>, AssetBalanceOf<T>), DispatchError> {
+		let mut position = Positions::<T>::get(who, position_id).ok_or(Error::<T>::PositionDoesNotExist)?;
+		let mut pool = ConcentratedPools::<T>::get(position.pool_id).ok_or(Error::<T>::PoolDoesNotExist)?;
+		ensure!(liquidity > 0 && liquidity <= position.liquidity, Error::<T>::InsufficientPositionLiquidity);
+		let liquidity_delta = i128::try_from(liquidity).map_err(|_| ArithmeticError::Overflow)?;
+
+		// the fees earned so far are owed before the liquidity earning them is removed
+		Self::accrue_position_fees(&pool, &mut position)?;
+
+		// the amounts paid out of the pool are rounded down
+		let (sqrt_lower, sqrt_upper) = Self::check_tick_range(&pool, position.tick_lower, position.tick_upper)?;
+		let (amount_0, amount_1) = Self::position_amounts(&pool, sqrt_lower, sqrt_upper, liquidity, false)?;
+		ensure!(amount_0 >= amount_0_min, Error::<T>::NotEnoughAssetsA);
+		ensure!(amount_1 >= amount_1_min, Error::<T>::NotEnoughAssetsB);
+
+		Self::update_position_ticks(position.pool_id, &mut pool, position.tick_lower, position.tick_upper, -liquidity_delta)?;
+
+		position.liquidity = position.liquidity.saturating_sub(liquidity);
+		position.tokens_owed_0 = position.tokens_owed_0.checked_add(&amount_0).ok_or(ArithmeticError::Overflow)?;
+		position.tokens_owed_1 = position.tokens_owed_1.checked_add(&amount_1).ok_or(ArithmeticError::Overflow)?;
+		let pool_id = position.pool_id;
+		ConcentratedPools::<T>::insert(pool_id, pool);
+		Positions::<T>::insert(who, position_id, position);
+
+		Self::deposit_event(Event::<T>::LiquidityDecreased {
+			who: who.clone(),
+			pool_id,
+			position_id,
+			liquidity,
+			amount_0,
+			amount_1,
+		});
+
+		Ok((amount_0, amount_1))
+	}
+
+	/// Pays out the fees and the removed liquidity owed to the position of `who`, the position is removed if it
+	/// has no liquidity left. Returns the amounts paid out.
+	pub fn do_collect(
+		who: &T::AccountId,
+		position_id: PositionId,
+	) -> Result<(AssetBalanceOf<T>, AssetBalanceOf<T>), DispatchError> {
+		let mut position = Positions::<T>::get(who, position_id).ok_or(Error::<T>::PositionDoesNotExist)?;
+		let pool = ConcentratedPools::<T>::get(position.pool_id).ok_or(Error::<T>::PoolDoesNotExist)?;
+
+		// the fees earned since the last update are owed as well
+		Self::accrue_position_fees(&pool, &mut position)?;
+		let (pool_id, amount_0, amount_1) = (position.pool_id, position.tokens_owed_0, position.tokens_owed_1);
+
+		Self::transfer_asset(&pool.asset_0, &pool.pool_account, who, amount_0)?;
+		Self::transfer_asset(&pool.asset_1, &pool.pool_account, who, amount_1)?;
+
+		if position.liquidity == 0 {
+			Positions::<T>::remove(who, position_id);
+		} else {
+			position.tokens_owed_0 = Zero::zero();
+			position.tokens_owed_1 = Zero::zero();
+			Positions::<T>::insert(who, position_id, position);
+		}
+
+		Self::deposit_event(Event::<T>::PositionCollected { who: who.clone(), pool_id, position_id, amount_0, amount_1 });
+
+		Ok((amount_0, amount_1))
+	}
+
+	/// Swaps exactly `amount_in` of `asset_in` for at least `min_out` of the other asset of the concentrated
+	/// liquidity pool on behalf of `who`, like `swap` in Uniswap V3. Returns the amount paid out.
+	pub fn do_swap_concentrated(
+		who: &T::AccountId,
+		pool_id: ConcentratedPoolId,
+		asset_in: BalanceType<T>,
+		amount_in: AssetBalanceOf<T>,
+		min_out: AssetBalanceOf<T>,
+	) -> Result<AssetBalanceOf<T>, DispatchError> {
+		let mut pool = ConcentratedPools::<T>::get(pool_id).ok_or(Error::<T>::PoolDoesNotExist)?;
+		ensure!(!Self::with_global_pause(PausedConcentratedPools::<T>::get(pool_id)).swaps, Error::<T>::PoolPaused);
+
+		// asset_0 in moves the price down, asset_1 in moves it up
+		ensure!(asset_in == pool.asset_0 || asset_in == pool.asset_1, Error::<T>::AssetNotInPool);
+		let zero_for_one = asset_in == pool.asset_0;
+		let asset_out = if zero_for_one { pool.asset_1.clone() } else { pool.asset_0.clone() };
+
+		// check user has enough balance
+		ensure!(!amount_in.is_zero() && Self::asset_balance(&asset_in, who) >= amount_in, Error::<T>::InsufficientBalance);
+
+		let ticks = InitializedTicks::<T>::get(pool_id);
+		let mut amount_remaining: u128 = amount_in.unique_saturated_into();
+		let (mut amount_out, mut fee) = (0u128, 0u128);
+		while amount_remaining > 0 {
+			// the next initialized tick in the direction of the swap, there is no liquidity beyond the last one
+			let next_tick = if zero_for_one {
+				ticks.iter().rev().find(|tick| **tick <= pool.tick)
+			} else {
+				ticks.iter().find(|tick| **tick > pool.tick)
+			}
+			.copied()
+			.ok_or(Error::<T>::NotEnoughLiquidityInRange)?;
+			let sqrt_price_target = concentrated::sqrt_price_at_tick(next_tick).ok_or(ArithmeticError::Overflow)?;
+
+			// swap within the range up to the next tick
+			let step = concentrated::compute_swap_step(pool.sqrt_price, sqrt_price_target, pool.liquidity, amount_remaining, pool.fee)
+				.ok_or(ArithmeticError::Overflow)?;
+			let step_amount_in = step.amount_in.checked_add(step.fee_amount).ok_or(ArithmeticError::Overflow)?;
+			amount_remaining = amount_remaining.checked_sub(step_amount_in).ok_or(ArithmeticError::Underflow)?;
+			amount_out = amount_out.checked_add(step.amount_out).ok_or(ArithmeticError::Overflow)?;
+			fee = fee.checked_add(step.fee_amount).ok_or(ArithmeticError::Overflow)?;
+
+			// the fee is shared by the liquidity in range
+			let fee_growth = concentrated::fee_growth(step.fee_amount, pool.liquidity);
+			if zero_for_one {
+				pool.fee_growth_global_0 = pool.fee_growth_global_0.wrapping_add(fee_growth);
+			} else {
+				pool.fee_growth_global_1 = pool.fee_growth_global_1.wrapping_add(fee_growth);
+			}
+
+			pool.sqrt_price = step.sqrt_price_next;
+			if step.sqrt_price_next == sqrt_price_target {
+				// the positions starting at the tick come into range and the ones ending at it go out of range
+				let liquidity_net = Self::cross_tick(pool_id, &pool, next_tick)?;
+				let liquidity_delta = if zero_for_one { liquidity_net.checked_neg().ok_or(ArithmeticError::Overflow)? } else { liquidity_net };
+				pool.liquidity = concentrated::add_delta(pool.liquidity, liquidity_delta).ok_or(ArithmeticError::Overflow)?;
+				pool.tick = if zero_for_one { next_tick - 1 } else { next_tick };
+			} else {
+				pool.tick = concentrated::tick_at_sqrt_price(pool.sqrt_price).ok_or(ArithmeticError::Overflow)?;
+			}
+		}
+
+		// check minimum output
+		let amount_out = AssetBalanceOf::<T>::try_from(amount_out).map_err(|_| ArithmeticError::Overflow)?;
+		ensure!(amount_out >= min_out, Error::<T>::InsufficientBalance);
+		let fee = AssetBalanceOf::<T>::try_from(fee).map_err(|_| ArithmeticError::Overflow)?;
+
+		Self::transfer_asset(&asset_in, who, &pool.pool_account, amount_in)?;
+		Self::transfer_asset(&asset_out, &pool.pool_account, who, amount_out)?;
+		let (sqrt_price_after, liquidity_after, tick_after) = (pool.sqrt_price, pool.liquidity, pool.tick);
+		ConcentratedPools::<T>::insert(pool_id, pool);
+
+		Self::deposit_event(Event::<T>::ConcentratedSwapped {
+			who: who.clone(),
+			pool_id,
+			asset_in,
+			asset_out,
+			amount_in,
+			amount_out,
+			fee,
+			sqrt_price_after,
+			liquidity_after,
+			tick_after,
+		});
+
+		Ok(amount_out)
+	}
+
+	/// Checks that a position of the pool can range from `tick_lower` to `tick_upper`. Returns the square
+	/// root prices of the ticks.
+	fn check_tick_range(
+		pool: &ConcentratedPool<T>,
+		tick_lower: i32,
+		tick_upper: i32,
+	) -> Result<(u128, u128), DispatchError> {
+		let tick_spacing = i32::try_from(pool.tick_spacing).map_err(|_| Error::<T>::InvalidTickSpacing)?;
+		ensure!(
+			tick_lower < tick_upper && tick_lower % tick_spacing == 0 && tick_upper % tick_spacing == 0,
+			Error::<T>::InvalidTickRange
+		);
+		let sqrt_lower = concentrated::sqrt_price_at_tick(tick_lower).ok_or(Error::<T>::InvalidTickRange)?;
+		let sqrt_upper = concentrated::sqrt_price_at_tick(tick_upper).ok_or(Error::<T>::InvalidTickRange)?;
+		Ok((sqrt_lower, sqrt_upper))
+	}
+
+	/// Returns the amounts of asset_0 and asset_1 of `liquidity` between two square root prices at the
+	/// current price of the pool.
+	fn position_amounts(
+		pool: &ConcentratedPool<T>,
+		sqrt_lower: u128,
+		sqrt_upper: u128,
+		liquidity: u128,
+		round_up: bool,
+	) -> Result<(AssetBalanceOf<T>, AssetBalanceOf<T>), DispatchError> {
+		let (amount_0, amount_1) =
+			concentrated::amounts_for_liquidity(pool.sqrt_price, sqrt_lower, sqrt_upper, liquidity, round_up)
+				.ok_or(ArithmeticError::Overflow)?;
+		Ok((
+			AssetBalanceOf::<T>::try_from(amount_0).map_err(|_| ArithmeticError::Overflow)?,
+			AssetBalanceOf::<T>::try_from(amount_1).map_err(|_| ArithmeticError::Overflow)?,
+		))
+	}
+
+	/// Adds `liquidity_delta` to the ticks of a position and to the liquidity in range of the pool if the
+	/// position contains the current price.
+	fn update_position_ticks(
+		pool_id: ConcentratedPoolId,
+		pool: &mut ConcentratedPool<T>,
+		tick_lower: i32,
+		tick_upper: i32,
+		liquidity_delta: i128,
+	) -> DispatchResult {
+		Self::update_tick(pool_id, pool, tick_lower, liquidity_delta, false)?;
+		Self::update_tick(pool_id, pool, tick_upper, liquidity_delta, true)?;
+		if pool.tick >= tick_lower && pool.tick < tick_upper {
+			pool.liquidity = concentrated::add_delta(pool.liquidity, liquidity_delta).ok_or(ArithmeticError::Overflow)?;
+		}
+		Ok(())
+	}
+
+	/// Adds `liquidity_delta` to a tick where positions start, or end if `upper`. A tick is initialized while
+	/// at least one position starts or ends at it.
+	fn update_tick(
+		pool_id: ConcentratedPoolId,
+		pool: &ConcentratedPool<T>,
+		tick: i32,
+		liquidity_delta: i128,
+		upper: bool,
+	) -> DispatchResult {
+		let mut info = Ticks::<T>::get(pool_id, tick).unwrap_or_default();
+		let was_initialized = info.liquidity_gross > 0;
+
+		// by convention all the fees so far have been earned below a new tick at or below the current price
+		if !was_initialized && tick <= pool.tick {
+			info.fee_growth_outside_0 = pool.fee_growth_global_0;
+			info.fee_growth_outside_1 = pool.fee_growth_global_1;
+		}
+		info.liquidity_gross = concentrated::add_delta(info.liquidity_gross, liquidity_delta).ok_or(ArithmeticError::Overflow)?;
+		let net_delta = if upper { liquidity_delta.checked_neg() } else { Some(liquidity_delta) };
+		info.liquidity_net = net_delta
+			.and_then(|delta| info.liquidity_net.checked_add(delta))
+			.ok_or(ArithmeticError::Overflow)?;
+
+		if info.liquidity_gross == 0 {
+			Ticks::<T>::remove(pool_id, tick);
+			InitializedTicks::<T>::mutate(pool_id, |ticks| ticks.retain(|initialized| *initialized != tick));
+		} else {
+			Ticks::<T>::insert(pool_id, tick, info);
+			if !was_initialized {
+				InitializedTicks::<T>::try_mutate(pool_id, |ticks| {
+					let index = ticks.binary_search(&tick).unwrap_or_else(|index| index);
+					ticks.try_insert(index, tick).map_err(|_| Error::<T>::TooManyTicks)
+				})?;
+			}
+		}
+		Ok(())
+	}
+
+	/// Flips the fee growth outside of a tick to its other side when the price crosses it. Returns the
+	/// liquidity net of the tick.
+	fn cross_tick(pool_id: ConcentratedPoolId, pool: &ConcentratedPool<T>, tick: i32) -> Result<i128, DispatchError> {
+		Ticks::<T>::try_mutate(pool_id, tick, |info| -> Result<i128, DispatchError> {
+			let info = info.as_mut().ok_or(Error::<T>::NotEnoughLiquidityInRange)?;
+			info.fee_growth_outside_0 = pool.fee_growth_global_0.wrapping_sub(info.fee_growth_outside_0);
+			info.fee_growth_outside_1 = pool.fee_growth_global_1.wrapping_sub(info.fee_growth_outside_1);
+			Ok(info.liquidity_net)
+		})
+	}
+
+	/// Returns the fees of asset_0 and asset_1 earned per unit of liquidity between two ticks, from the global
+	/// fee growth and the fee growth outside of the ticks.
+	fn fee_growth_inside(
+		pool_id: ConcentratedPoolId,
+		pool: &ConcentratedPool<T>,
+		tick_lower: i32,
+		tick_upper: i32,
+	) -> (u128, u128) {
+		let lower = Ticks::<T>::get(pool_id, tick_lower).unwrap_or_default();
+		let upper = Ticks::<T>::get(pool_id, tick_upper).unwrap_or_default();
+		let (global_0, global_1) = (pool.fee_growth_global_0, pool.fee_growth_global_1);
+
+		// the fee growth below the lower tick and above the upper tick
+		let (below_0, below_1) = if pool.tick >= tick_lower {
+			(lower.fee_growth_outside_0, lower.fee_growth_outside_1)
+		} else {
+			(global_0.wrapping_sub(lower.fee_growth_outside_0), global_1.wrapping_sub(lower.fee_growth_outside_1))
+		};
+		let (above_0, above_1) = if pool.tick < tick_upper {
+			(upper.fee_growth_outside_0, upper.fee_growth_outside_1)
+		} else {
+			(global_0.wrapping_sub(upper.fee_growth_outside_0), global_1.wrapping_sub(upper.fee_growth_outside_1))
+		};
+
+		(global_0.wrapping_sub(below_0).wrapping_sub(above_0), global_1.wrapping_sub(below_1).wrapping_sub(above_1))
+	}
+
+	/// Adds the fees earned by the liquidity of the position since its last update to what it is owed.
+	fn accrue_position_fees(pool: &ConcentratedPool<T>, position: &mut Position<T>) -> DispatchResult {
+		let (inside_0, inside_1) = Self::fee_growth_inside(position.pool_id, pool, position.tick_lower, position.tick_upper);
+		let fees_0 = concentrated::fees_owed(inside_0.wrapping_sub(position.fee_growth_inside_0_last), position.liquidity);
+		let fees_1 = concentrated::fees_owed(inside_1.wrapping_sub(position.fee_growth_inside_1_last), position.liquidity);
+		let fees_0 = AssetBalanceOf::<T>::try_from(fees_0).map_err(|_| ArithmeticError::Overflow)?;
+		let fees_1 = AssetBalanceOf::<T>::try_from(fees_1).map_err(|_| ArithmeticError::Overflow)?;
+
+		position.tokens_owed_0 = position.tokens_owed_0.checked_add(&fees_0).ok_or(ArithmeticError::Overflow)?;
+		position.tokens_owed_1 = position.tokens_owed_1.checked_add(&fees_1).ok_or(ArithmeticError::Overflow)?;
+		position.fee_growth_inside_0_last = inside_0;
+		position.fee_growth_inside_1_last = inside_1;
+		Ok(())
+	}
+
+	/// Adds the liquidity of `who` to the existing pool of `asset_a` and `asset_b`, at most the desired
+	/// amounts at the ratio of the reserves. Returns the deposited amounts and the minted LP tokens,
+	/// with the amounts in the order of the assets of the pool.
+	pub fn do_add_liquidity(
+		who: &T::AccountId,
+		asset_a: BalanceType<T>,
+		asset_b: BalanceType<T>,
+		fee: Permill,
+		amount_a_desired: AssetBalanceOf<T>,
+		amount_b_desired: AssetBalanceOf<T>,
+		amount_a_min: AssetBalanceOf<T>,
+		amount_b_min: AssetBalanceOf<T>,
+	) -> Result<(AssetBalanceOf<T>, AssetBalanceOf<T>, AssetBalanceOf<T>), DispatchError> {
+		// Order the asset ids, orders the desired and minimum amounts as well
+		let (ordered_a, ordered_b, amount_a_desired, amount_b_desired) =
+			Self::order_asset_ids(asset_a.clone(), asset_b.clone(), amount_a_desired, amount_b_desired)?;
+		let (_, _, amount_a_min, amount_b_min) = Self::order_asset_ids(asset_a, asset_b, amount_a_min, amount_b_min)?;
+		let (asset_a, asset_b) = (ordered_a, ordered_b);
+
+		// Get the liquidity pool, this fails if it doesn't exist
+		let pool = LiquidityPoolMap::<T>::get(&(asset_a.clone(), asset_b.clone(), fee)).ok_or(Error::<T>::PoolDoesNotExist)?;
+		ensure!(!Self::pause_flags(&(asset_a.clone(), asset_b.clone(), fee)).adds, Error::<T>::PoolPaused);
+
+		// Get the tracked reserves
+		let reserve_a = pool.reserve_a;
+		let reserve_b = pool.reserve_b;
+
+		// check that the pool is not empty
+		ensure!(!reserve_a.is_zero() && !reserve_b.is_zero(), Error::<T>::EmptyPool);
+
+		// Deposit at the ratio of the reserves, the side that would be over-supplied is reduced
+		let (amount_a, amount_b) = Self::optimal_liquidity_amounts(
+			reserve_a,
+			reserve_b,
+			amount_a_desired,
+			amount_b_desired,
+			amount_a_min,
+			amount_b_min,
+		)?;
+
+		// Ensure the caller has enough balances in both of the assets, where he is providing liquidity
+		// and the that the balances of both assets are greater then minimum value required
+		Self::check_user_balances(who, &asset_a, &asset_b, &amount_a, &amount_b)?;
+
+		// Mint the protocol fee before the LP tokens of the user, so the user doesn't pay for it
+		let fee_on = Self::mint_protocol_fee(&(asset_a.clone(), asset_b.clone(), fee), &pool)?;
+
+		let lp_asset_id = pool.liquidity_id;
+		let pool_account = pool.pool_account;
+	
+		// Calculate liquidity tokens to mint
+		// LP tokens represent the amount of shares a LP provider has deposited.
+
+		// get the total issuance of LP tokens 
+		let amount_a_in_reserves = amount_a.checked_mul(&T::Fungibles::total_issuance(lp_asset_id.clone())).ok_or(ArithmeticError::Overflow)?;
+		let lp_tokens_a = amount_a_in_reserves / reserve_a;
+
+		let amount_b_in_reserves = amount_b.checked_mul(&T::Fungibles::total_issuance(lp_asset_id.clone())).ok_or(ArithmeticError::Overflow)?;
+		let lp_tokens_b = amount_b_in_reserves / reserve_b;
+
+		// Use the smaller one to maintain the ratio
+		let lp_tokens = lp_tokens_a.min(lp_tokens_b);
+
+		// A deposit that rounds down to no LP tokens would be donated to the pool
+		ensure!(!lp_tokens.is_zero(), Error::<T>::InsufficientLiquidityMinted);
+
+		// transfer the tokens from the users accout into pool account 
+		Self::transfer_assets_a_and_b(who, &pool_account, &asset_a, &asset_b, &amount_a, &amount_b)?;
+
+		// Mint the liquidity tokens
+		T::Fungibles::mint_into(lp_asset_id.clone(), who, lp_tokens)?;
+
+		// Update the tracked reserves
+		let new_reserve_a = reserve_a.checked_add(&amount_a).ok_or(ArithmeticError::Overflow)?;
+		let new_reserve_b = reserve_b.checked_add(&amount_b).ok_or(ArithmeticError::Overflow)?;
+		Self::set_reserves(asset_a.clone(), asset_b.clone(), fee, new_reserve_a, new_reserve_b)?;
+		Self::update_k_last(&(asset_a.clone(), asset_b.clone(), fee), fee_on)?;
+
+		// create an event
+		Self::deposit_event(Event::<T>::LiquidityAdded {
+			who: who.clone(),
+			pool_id: lp_asset_id,
+			asset_a,
+			asset_b,
+			fee,
+			amount_a,
+			amount_b,
+			amount_liq: lp_tokens,
+		});
+
+		Ok((amount_a, amount_b, lp_tokens))
+	}
+
+	/// Burns `amount_liq` LP tokens of `who` in exchange for their share of the pool reserves.
+	///
+	/// Pools migrated from before storage version 1 didn't lock a minimum liquidity, so their last LP tokens
+	/// can be burned. Such a pool is removed with them, as it couldn't be seeded again. Returns whether the
+	/// pool has been removed.
+	pub fn do_remove_liquidity(
+		who: &T::AccountId,
+		asset_a: BalanceType<T>,
+		asset_b: BalanceType<T>,
+		fee: Permill,
+		min_amount_a: AssetBalanceOf<T>,
+		min_amount_b: AssetBalanceOf<T>,
+		amount_liq: AssetBalanceOf<T>,
+	) -> Result<bool, DispatchError> {
+		//  Order the asset ids, orders the amounts as well
+		let (asset_a, asset_b, min_amount_a, min_amount_b) = Self::order_asset_ids(asset_a, asset_b, min_amount_a, min_amount_b)?;
+
+		// Get the liquidity pool, this fails if it doesn't exist
+		let pool = LiquidityPoolMap::<T>::get(&(asset_a.clone(), asset_b.clone(), fee)).ok_or(Error::<T>::PoolDoesNotExist)?;
+		let lp_asset_id = pool.liquidity_id.clone();
+
+		// Checks if the user has enough liquidity tokens
+		ensure!(T::Fungibles::balance(lp_asset_id.clone(), who) >= amount_liq, Error::<T>::NotEnoughLiquidityTokens);
+		ensure!(!amount_liq.is_zero(), "Balance of liquidity tokens has to be greater then 0");
+
+		// Mint the protocol fee before calculating the share of the user
+		let fee_on = Self::mint_protocol_fee(&(asset_a.clone(), asset_b.clone(), fee), &pool)?;
+
+		let pool_account = pool.pool_account;
+
+		// Get the tracked reserves
+		let reserve_a = pool.reserve_a;
+		let reserve_b = pool.reserve_b;
+
+		// Calculate the liquidity amount to remove
+		let total_liquidity = T::Fungibles::total_issuance(lp_asset_id.clone());
+		
+		// reserve_a * amount_liq / total_liquidity
+
+		let res_amount_a = reserve_a.checked_mul(&amount_liq).ok_or(ArithmeticError::Overflow)?;
+		let res_amount_b = reserve_b.checked_mul(&amount_liq).ok_or(ArithmeticError::Overflow)?;
+
+		let remove_amount_a = res_amount_a.checked_div(&total_liquidity).ok_or(ArithmeticError::Underflow)?;
+		let remove_amount_b = res_amount_b.checked_div(&total_liquidity).ok_or(ArithmeticError::Underflow)?;
+
+		// Ensure the user receives at least the minimum amounts they expect
+		ensure!(remove_amount_a >= min_amount_a, Error::<T>::NotEnoughAssetsA);
+		ensure!(remove_amount_b >= min_amount_b, Error::<T>::NotEnoughAssetsB);
+
+		// Transfer the tokens from the pool account to the user's account
+		Self::transfer_assets_a_and_b(&pool_account, who, &asset_a, &asset_b, &remove_amount_a, &remove_amount_b)?;
+
+		// Burn the liquidity tokens from the user's account
+		T::Fungibles::burn_from(lp_asset_id.clone(), who, amount_liq, Precision::BestEffort, Fortitude::Polite)?;
+
+		// Update the tracked reserves
+		let new_reserve_a = reserve_a.checked_sub(&remove_amount_a).ok_or(ArithmeticError::Underflow)?;
+		let new_reserve_b = reserve_b.checked_sub(&remove_amount_b).ok_or(ArithmeticError::Underflow)?;
+		Self::set_reserves(asset_a.clone(), asset_b.clone(), fee, new_reserve_a, new_reserve_b)?;
+		Self::update_k_last(&(asset_a.clone(), asset_b.clone(), fee), fee_on)?;
+
+		// Emit an event
+		Self::deposit_event(Event::<T>::LiquidityRemoved {
+			who: who.clone(),
+			pool_id: lp_asset_id,
+			asset_a: asset_a.clone(),
+			asset_b: asset_b.clone(),
+			fee,
+			amount_liq,
+			amount_a: remove_amount_a,
+			amount_b: remove_amount_b,
+		});
+
+		// The last LP tokens took the whole reserves, the empty pool is removed
+		if !T::Fungibles::total_issuance(lp_asset_id.clone()).is_zero() {
+			return Ok(false)
+		}
+		Self::close_pool(who, &(asset_a.clone(), asset_b.clone(), fee), lp_asset_id.clone(), &pool_account)?;
+		Self::deposit_event(Event::<T>::PoolRemoved {
+			who: who.clone(),
+			pool_id: lp_asset_id,
+			asset_a,
+			asset_b,
+			fee,
+			amount_liq: Zero::zero(),
+			amount_a: Zero::zero(),
+			amount_b: Zero::zero(),
+		});
+
+		Ok(true)
+	}
+
+	/// Swaps exactly `exact_in` of `asset_in` for at least `min_out` of `asset_out` on behalf of `who`.
+	pub fn do_swap_exact_in_for_out(
+		who: &T::AccountId,
+		asset_in: BalanceType<T>,
+		asset_out: BalanceType<T>,
+		fee: Permill,
+		exact_in: AssetBalanceOf<T>,
+		min_out: AssetBalanceOf<T>,
+	) -> DispatchResult {
+		Self::swap_exact_in_for_out_paid_by(who, who, asset_in, asset_out, fee, exact_in, min_out)
+	}
+
+	/// Swaps exactly `exact_in` of `asset_in`, paid by `payer`, for at least `min_out` of `asset_out` on behalf
+	/// of `who`, who gets the amount out. A limit order is paid by the escrow account holding its amount.
+	fn swap_exact_in_for_out_paid_by(
+		payer: &T::AccountId,
+		who: &T::AccountId,
+		asset_in: BalanceType<T>,
+		asset_out: BalanceType<T>,
+		fee: Permill,
+		exact_in: AssetBalanceOf<T>,
+		min_out: AssetBalanceOf<T>,
+	) -> DispatchResult {
+		// get the pool and its reserves, this fails if the pool doesn't exist
+		let (pool, pool_balance_in, pool_balance_out) = Self::get_reserves(asset_in.clone(), asset_out.clone(), fee)?;
+		Self::ensure_not_in_batch_mode(&asset_in, &asset_out, fee)?;
+		Self::ensure_swaps_not_paused(&asset_in, &asset_out, fee)?;
+
+		// check user has enough balance
+		ensure!(Self::asset_balance(&asset_in, payer) >= exact_in, Error::<T>::InsufficientBalance);
+
+		// calculate amount out, the fee is taken from exact_in
+		let amount_out = Self::get_amount_out(exact_in, pool_balance_in, pool_balance_out, pool.fee, &pool.curve)?;
+
+		// check minimum output
+		ensure!(amount_out >= min_out, Error::<T>::InsufficientBalance);
+
+		// update pool balances and user balances
+		// transfer from balance into pool
+		Self::transfer_asset(&asset_in, payer, &pool.pool_account, exact_in)?;
+		// transfer from pool to balance
+		Self::transfer_asset(&asset_out, &pool.pool_account, who, amount_out)?;
+
+		// update the tracked reserves
+		let new_balance_in = pool_balance_in.checked_add(&exact_in).ok_or(ArithmeticError::Overflow)?;
+		let new_balance_out = pool_balance_out.checked_sub(&amount_out).ok_or(ArithmeticError::Underflow)?;
+		Self::set_reserves(asset_in.clone(), asset_out.clone(), fee, new_balance_in, new_balance_out)?;
+
+		Self::deposit_event(Event::<T>::Swapped {
+			who: who.clone(),
+			pool_id: pool.liquidity_id.clone(),
+			asset_in,
+			asset_out,
+			amount_in: exact_in,
+			amount_out,
+			fee: Self::swap_fee_amount(&pool, exact_in, amount_out, pool_balance_in, pool_balance_out, true),
+			reserve_in_after: new_balance_in,
+			reserve_out_after: new_balance_out,
+		});
+
+		Ok(())
+	}
+
+	/// Swaps at most `max_in` of `asset_in` for exactly `exact_out` of `asset_out` on behalf of `who`.
+	pub fn do_swap_in_for_exact_out(
+		who: &T::AccountId,
+		asset_in: BalanceType<T>,
+		asset_out: BalanceType<T>,
+		fee: Permill,
+		max_in: AssetBalanceOf<T>,
+		exact_out: AssetBalanceOf<T>,
+	) -> DispatchResult {
+		// Get the pool and its reserves, this fails if the pool doesn't exist
+		let (pool, pool_balance_in, pool_balance_out) = Self::get_reserves(asset_in.clone(), asset_out.clone(), fee)?;
+		Self::ensure_not_in_batch_mode(&asset_in, &asset_out, fee)?;
+		Self::ensure_swaps_not_paused(&asset_in, &asset_out, fee)?;
+
+		// Check user has enough balance
+		ensure!(Self::asset_balance(&asset_in, who) >= max_in, Error::<T>::InsufficientBalance);
+
+		// Calculate amount in, the fee is added on top of it
+		let amount_in = Self::get_amount_in(exact_out, pool_balance_in, pool_balance_out, pool.fee, &pool.curve)?;
+
+		// Add a max in amount 
+		ensure!(amount_in <= max_in, Error::<T>::InsufficientBalance);
+	
+		// Update pool balances and user balances
+		// Transfer from balance into pool
+		Self::transfer_asset(&asset_in, who, &pool.pool_account, amount_in)?;
+		// Transfer from pool to balance
+		Self::transfer_asset(&asset_out, &pool.pool_account, who, exact_out)?;
+
+		// Update the tracked reserves
+		let new_balance_in = pool_balance_in.checked_add(&amount_in).ok_or(ArithmeticError::Overflow)?;
+		let new_balance_out = pool_balance_out.checked_sub(&exact_out).ok_or(ArithmeticError::Underflow)?;
+		Self::set_reserves(asset_in.clone(), asset_out.clone(), fee, new_balance_in, new_balance_out)?;
+
+		Self::deposit_event(Event::<T>::Swapped {
+			who: who.clone(),
+			pool_id: pool.liquidity_id.clone(),
+			asset_in,
+			asset_out,
+			amount_in,
+			amount_out: exact_out,
+			fee: Self::swap_fee_amount(&pool, amount_in, exact_out, pool_balance_in, pool_balance_out, false),
+			reserve_in_after: new_balance_in,
+			reserve_out_after: new_balance_out,
+		});
+
+		Ok(())
+	}
+
+	/// Places a limit order of `who` and holds its amount in the escrow account. Returns the id of the order.
+	pub fn do_place_limit_order(
+		who: &T::AccountId,
+		asset_in: BalanceType<T>,
+		asset_out: BalanceType<T>,
+		fee: Permill,
+		amount_in: AssetBalanceOf<T>,
+		limit_price: FixedU128,
+		expires_at: BlockNumberFor<T>,
+	) -> Result<OrderId, DispatchError> {
+		// The order has to be fillable in an existing pool before it expires
+		let (pool, _, _) = Self::get_reserves(asset_in.clone(), asset_out.clone(), fee)?;
+		ensure!(expires_at >= frame_system::Pallet::<T>::block_number(), Error::<T>::DeadlineExpired);
+
+		// Orders are filled with immediate swaps, which a pool in batch mode or with paused swaps doesn't take
+		Self::ensure_not_in_batch_mode(&asset_in, &asset_out, fee)?;
+		Self::ensure_swaps_not_paused(&asset_in, &asset_out, fee)?;
+
+		// check user has enough balance
+		ensure!(!amount_in.is_zero() && Self::asset_balance(&asset_in, who) >= amount_in, Error::<T>::InsufficientBalance);
+
+		let open_orders = OpenOrdersOf::<T>::get(who);
+		ensure!(open_orders < T::MaxLimitOrders::get(), Error::<T>::TooManyLimitOrders);
+
+		// the amount is held in the escrow account until the order is filled or cancelled
+		let escrow_account = Self::escrow_account();
+		Self::ensure_account_funded(&escrow_account, who)?;
+		Self::transfer_asset(&asset_in, who, &escrow_account, amount_in)?;
+
+		let order_id = NextOrderId::<T>::get();
+		NextOrderId::<T>::put(order_id.checked_add(1).ok_or(Error::<T>::StorageOverflow)?);
+		OpenOrdersOf::<T>::insert(who, open_orders + 1);
+		LimitOrders::<T>::insert(order_id, LimitOrder {
+			owner: who.clone(),
+			asset_in: asset_in.clone(),
+			asset_out: asset_out.clone(),
+			fee,
+			amount_in,
+			limit_price,
+			expires_at,
+		});
+
+		Self::deposit_event(Event::<T>::LimitOrderPlaced {
+			order_id,
+			owner: who.clone(),
+			pool_id: pool.liquidity_id,
+			asset_in,
+			asset_out,
+			fee,
+			amount_in,
+			limit_price,
+			expires_at,
+		});
+
+		Ok(order_id)
+	}
+
+	/// Swaps the amount of a limit order in its pool if the pool pays the limit price, the proceeds are paid to
+	/// the owner of the order. Returns the amount paid out.
+	pub fn do_fill_order(order_id: OrderId) -> Result<AssetBalanceOf<T>, DispatchError> {
+		let order = LimitOrders::<T>::get(order_id).ok_or(Error::<T>::OrderDoesNotExist)?;
+		ensure!(frame_system::Pallet::<T>::block_number() <= order.expires_at, Error::<T>::DeadlineExpired);
+
+		// the spot price of the pool has to have crossed the limit price
+		let min_out = order.limit_price.saturating_mul_int(order.amount_in.unique_saturated_into());
+		let min_out = AssetBalanceOf::<T>::try_from(min_out).map_err(|_| ArithmeticError::Overflow)?;
+		let spot_out = Self::get_price(order.asset_in.clone(), order.asset_out.clone(), order.fee, order.amount_in)?;
+		ensure!(spot_out >= min_out, Error::<T>::OrderNotFillable);
+
+		// the fee and the slippage of the swap may still take the output below the limit price
+		let amount_out = Self::quote_exact_in(order.asset_in.clone(), order.asset_out.clone(), order.fee, order.amount_in)
+			.filter(|amount_out| *amount_out >= min_out)
+			.ok_or(Error::<T>::OrderNotFillable)?;
+
+		// the held amount is swapped on behalf of the owner, who gets the proceeds
+		let pool_id = Self::lp_token_id(order.asset_in.clone(), order.asset_out.clone(), order.fee).ok_or(Error::<T>::PoolDoesNotExist)?;
+		Self::swap_exact_in_for_out_paid_by(
+			&Self::escrow_account(),
+			&order.owner,
+			order.asset_in.clone(),
+			order.asset_out.clone(),
+			order.fee,
+			order.amount_in,
+			amount_out,
+		)?;
+		Self::remove_limit_order(order_id, &order.owner);
+
+		Self::deposit_event(Event::<T>::LimitOrderFilled {
+			order_id,
+			owner: order.owner,
+			pool_id,
+			amount_in: order.amount_in,
+			amount_out,
+		});
+
+		Ok(amount_out)
+	}
+
+	/// Pays the held amount of a limit order back to its owner and removes the order.
+	fn refund_limit_order(order_id: OrderId, order: &LimitOrder<T>) -> DispatchResult {
+		Self::transfer_asset(&order.asset_in, &Self::escrow_account(), &order.owner, order.amount_in)?;
+		Self::remove_limit_order(order_id, &order.owner);
+		Ok(())
+	}
+
+	fn remove_limit_order(order_id: OrderId, owner: &T::AccountId) {
+		LimitOrders::<T>::remove(order_id);
+		OpenOrdersOf::<T>::mutate_exists(owner, |open_orders| {
+			*open_orders = open_orders.and_then(|open_orders| open_orders.checked_sub(1)).filter(|open_orders| *open_orders > 0);
+		});
+	}
+
+	/// Checks as many open limit orders as the remaining weight allows, starting after the last order checked
+	/// in a previous block. Orders the pools pay the limit price for are filled, expired orders are paid back
+	/// and the rest stays open. Returns the weight used.
+	fn match_limit_orders(now: BlockNumberFor<T>, remaining_weight: Weight) -> Weight {
+		// every checked order is charged as if it was filled
+		let order_weight = T::WeightInfo::fill_order();
+		let mut used_weight = T::DbWeight::get().reads_writes(1, 1);
+		let max_orders = match remaining_weight
+			.checked_sub(&used_weight)
+			.and_then(|weight| weight.checked_div_per_component(&order_weight))
+		{
+			Some(max_orders) if max_orders > 0 => max_orders,
+			_ => return Weight::zero(),
+		};
+
+		let mut orders = match OrderMatchingCursor::<T>::get() {
+			Some(cursor) => LimitOrders::<T>::iter_from(cursor.into_inner()),
+			None => LimitOrders::<T>::iter(),
+		};
+		let batch = orders.by_ref().take(max_orders as usize).collect::<Vec<_>>();
+
+		// the next block continues after the last checked order, or from the start once all have been checked
+		if (batch.len() as u64) < max_orders {
+			OrderMatchingCursor::<T>::kill();
+		} else {
+			OrderMatchingCursor::<T>::set(BoundedVec::try_from(orders.last_raw_key().to_vec()).ok());
+		}
+
+		for (order_id, order) in batch {
+			used_weight = used_weight.saturating_add(order_weight);
+			// a failed order leaves no changes behind and stays open
+			let _ = with_storage_layer(|| -> DispatchResult {
+				if now > order.expires_at {
+					Self::refund_limit_order(order_id, &order)?;
+					Self::deposit_event(Event::<T>::LimitOrderExpired {
+						order_id,
+						owner: order.owner.clone(),
+						pool_id: Self::lp_token_id(order.asset_in.clone(), order.asset_out.clone(), order.fee),
+						amount_in: order.amount_in,
+					});
+				} else {
+					Self::do_fill_order(order_id)?;
+				}
+				Ok(())
+			});
+		}
+
+		used_weight
+	}
+
+	/// The weight of a swap intent, including its share of clearing a full batch in `on_finalize` in the worst
+	/// case, twice on the StableSwap curve.
+	pub fn swap_intent_weight() -> Weight {
+		let max_intents = T::MaxSwapIntents::get().max(1);
+		let clearing = T::WeightInfo::clear_swap_batch(max_intents).checked_div(max_intents as u64).unwrap_or_default();
+		T::WeightInfo::submit_swap_intent().saturating_add(clearing)
+	}
+
+	/// Fails if the pool of the pair with the fee tier is in batch mode, its swaps are cleared in `on_finalize`.
+	fn ensure_not_in_batch_mode(asset_x: &BalanceType<T>, asset_y: &BalanceType<T>, fee: Permill) -> DispatchResult {
+		let (asset_a, asset_b, _, _) = Self::order_asset_ids(asset_x.clone(), asset_y.clone(), AssetBalanceOf::<T>::zero(), AssetBalanceOf::<T>::zero())?;
+		ensure!(!BatchModePools::<T>::get(&(asset_a, asset_b, fee)), Error::<T>::PoolInBatchMode);
+		Ok(())
+	}
+
+	/// What is paused in a pool, by its own pause or by the pause of all the pools.
+	pub fn pause_flags(pool_key: &PoolKeyOf<T>) -> PauseFlags {
+		Self::with_global_pause(PausedPools::<T>::get(pool_key))
+	}
+
+	/// Adds the pause of all the pools to the pause of a single pool.
+	fn with_global_pause(pool: PauseFlags) -> PauseFlags {
+		let global = GlobalPause::<T>::get();
+		PauseFlags { swaps: global.swaps || pool.swaps, adds: global.adds || pool.adds }
+	}
+
+	/// Fails if swaps in the pool of the pair with the fee tier are paused.
+	fn ensure_swaps_not_paused(asset_x: &BalanceType<T>, asset_y: &BalanceType<T>, fee: Permill) -> DispatchResult {
+		let (asset_a, asset_b, _, _) = Self::order_asset_ids(asset_x.clone(), asset_y.clone(), AssetBalanceOf::<T>::zero(), AssetBalanceOf::<T>::zero())?;
+		ensure!(!Self::pause_flags(&(asset_a, asset_b, fee)).swaps, Error::<T>::PoolPaused);
+		Ok(())
+	}
+
+	/// Submits a swap intent of `who` to a pool in batch mode and holds its amount in the escrow account.
+	pub fn do_submit_swap_intent(
+		who: &T::AccountId,
+		asset_in: BalanceType<T>,
+		asset_out: BalanceType<T>,
+		fee: Permill,
+		amount_in: AssetBalanceOf<T>,
+		min_out: AssetBalanceOf<T>,
+	) -> DispatchResult {
+		// the pool has to exist and be in batch mode
+		let (asset_a, asset_b, _, _) = Self::order_asset_ids(asset_in.clone(), asset_out.clone(), AssetBalanceOf::<T>::zero(), AssetBalanceOf::<T>::zero())?;
+		let pool_key = (asset_a, asset_b, fee);
+		let pool = LiquidityPoolMap::<T>::get(&pool_key).ok_or(Error::<T>::PoolDoesNotExist)?;
+		ensure!(BatchModePools::<T>::get(&pool_key), Error::<T>::PoolNotInBatchMode);
+		ensure!(!Self::pause_flags(&pool_key).swaps, Error::<T>::PoolPaused);
+
+		// check user has enough balance
+		ensure!(!amount_in.is_zero() && Self::asset_balance(&asset_in, who) >= amount_in, Error::<T>::InsufficientBalance);
+
+		SwapIntents::<T>::try_append(&pool_key, SwapIntent { who: who.clone(), asset_in: asset_in.clone(), amount_in, min_out })
+			.map_err(|_| Error::<T>::TooManySwapIntents)?;
+
+		// the amount is held in the escrow account until the batch is cleared
+		let escrow_account = Self::escrow_account();
+		Self::ensure_account_funded(&escrow_account, who)?;
+		Self::transfer_asset(&asset_in, who, &escrow_account, amount_in)?;
+
+		Self::deposit_event(Event::<T>::SwapIntentSubmitted {
+			who: who.clone(),
+			pool_id: pool.liquidity_id,
+			asset_in,
+			asset_out,
+			fee,
+			amount_in,
+			min_out,
+		});
+
+		Ok(())
+	}
+
+	/// Clears the swap intents submitted to the pools in batch mode in this block. The intents of a batch
+	/// that can't be cleared, e.g. because its pool has been removed, are paid back. An intent that can't be
+	/// paid back either is kept in `UnclaimedRefunds` for its account to claim.
+	fn clear_swap_batches() {
+		for (pool_key, intents) in SwapIntents::<T>::drain() {
+			let intents = intents.into_inner();
+			let cleared = with_storage_layer(|| Self::clear_swap_batch(&pool_key, intents.clone()));
+			if cleared.is_err() {
+				for intent in &intents {
+					if with_storage_layer(|| Self::refund_swap_intent(&pool_key, intent)).is_err() {
+						Self::keep_unclaimed_refund(&pool_key, intent);
+					}
+				}
+			}
+		}
+	}
+
+	/// Clears the swap intents of a pool at a uniform price. Intents whose share is below their slippage
+	/// limit are paid back once and the batch is cleared again without them. If a share is still below
+	/// its limit then, the batch isn't cleared and every intent is paid back, so a batch is cleared at
+	/// most twice.
+	fn clear_swap_batch(pool_key: &PoolKeyOf<T>, intents: Vec<SwapIntent<T>>) -> DispatchResult {
+		// a batch of a pool paused within the block is paid back
+		ensure!(!Self::pause_flags(pool_key).swaps, Error::<T>::PoolPaused);
+
+		let pool = LiquidityPoolMap::<T>::get(pool_key).ok_or(Error::<T>::PoolDoesNotExist)?;
+		let meets_limits = |intents: &[SwapIntent<T>], clearing: &BatchClearing| {
+			intents
+				.iter()
+				.zip(&clearing.amounts_out)
+				.map(|(intent, amount_out)| *amount_out >= intent.min_out.unique_saturated_into())
+				.collect::<Vec<_>>()
+		};
+
+		let clearing = Self::batch_clearing(&pool, &pool_key.0, &intents)?;
+		let meets_limit = meets_limits(&intents, &clearing);
+		if !meets_limit.contains(&false) {
+			return Self::settle_swap_batch(pool_key, &pool, &intents, clearing);
+		}
+
+		// the intents below their limit are paid back and the rest is cleared without them
+		let mut remaining = Vec::new();
+		for (intent, meets_limit) in intents.into_iter().zip(meets_limit) {
+			if meets_limit {
+				remaining.push(intent);
+			} else {
+				Self::refund_swap_intent(pool_key, &intent)?;
+			}
+		}
+		if remaining.is_empty() {
+			return Ok(());
+		}
+
+		let clearing = Self::batch_clearing(&pool, &pool_key.0, &remaining)?;
+		ensure!(!meets_limits(&remaining, &clearing).contains(&false), Error::<T>::BatchNotCleared);
+		Self::settle_swap_batch(pool_key, &pool, &remaining, clearing)
+	}
+
+	/// Finds the uniform price a batch of swap intents clears at. The intents selling one asset are matched
+	/// with the intents selling the other one, and the side selling more than the other side buys swaps
+	/// the difference with the pool. It sells the pool the largest amount `pool_in` for which the price the
+	/// pool pays is at least the price the other side pays for the rest, i.e.
+	/// `pool_out * (sold_in - pool_in) >= sold_out * pool_in`. Both sides then share what they receive
+	/// pro rata to what they sold.
+	fn batch_clearing(
+		pool: &LiquidityPool<T>,
+		asset_a: &BalanceType<T>,
+		intents: &[SwapIntent<T>],
+	) -> Result<BatchClearing, DispatchError> {
+		// the total amounts sold of both assets
+		let (mut sold_a, mut sold_b) = (0u128, 0u128);
+		for intent in intents {
+			let sold = if intent.asset_in == *asset_a { &mut sold_a } else { &mut sold_b };
+			*sold = sold.checked_add(intent.amount_in.unique_saturated_into()).ok_or(ArithmeticError::Overflow)?;
+		}
+
+		// the side selling more than the other side buys at the price of the pool swaps with the pool
+		let reserve_a: u128 = pool.reserve_a.unique_saturated_into();
+		let reserve_b: u128 = pool.reserve_b.unique_saturated_into();
+		let pool_takes_a = U256::from(sold_a) * U256::from(reserve_b) >= U256::from(sold_b) * U256::from(reserve_a);
+		let (sold_in, sold_out, reserve_in, reserve_out) = if pool_takes_a {
+			(sold_a, sold_b, pool.reserve_a, pool.reserve_b)
+		} else {
+			(sold_b, sold_a, pool.reserve_b, pool.reserve_a)
+		};
+
+		let amount_out = |amount_in: u128| -> Result<u128, DispatchError> {
+			let amount_in = AssetBalanceOf::<T>::try_from(amount_in).map_err(|_| ArithmeticError::Overflow)?;
+			let amount_out = Self::get_amount_out(amount_in, reserve_in, reserve_out, pool.fee, &pool.curve)?;
+			Ok(amount_out.unique_saturated_into())
+		};
+
+		// binary search for the largest amount the pool can take in at a price the other side matches
+		let (mut low, mut high) = (0u128, sold_in);
+		while low < high {
+			let mid = low + (high - low + 1) / 2;
+			if U256::from(amount_out(mid)?) * U256::from(sold_in - mid) >= U256::from(sold_out) * U256::from(mid) {
+				low = mid;
+			} else {
+				high = mid - 1;
+			}
+		}
+		let pool_in = low;
+		let pool_out = if pool_in.is_zero() { 0 } else { amount_out(pool_in)? };
+
+		// the side selling more shares what the pool pays out and what the other side sells,
+		// the other side shares the rest of what the side selling more sells
+		let paid_to_in = pool_out.checked_add(sold_out).ok_or(ArithmeticError::Overflow)?;
+		let paid_to_out = sold_in - pool_in;
+		let amounts_out = intents
+			.iter()
+			.map(|intent| {
+				let (paid, sold) =
+					if (intent.asset_in == *asset_a) == pool_takes_a { (paid_to_in, sold_in) } else { (paid_to_out, sold_out) };
+				multiply_by_rational_with_rounding(intent.amount_in.unique_saturated_into(), paid, sold, Rounding::Down)
+					.ok_or(ArithmeticError::Overflow)
+			})
+			.collect::<Result<Vec<_>, _>>()?;
+
+		// the price of asset_a in asset_b, from the side that sold asset_a if there is one
+		let price = if pool_takes_a {
+			FixedU128::checked_from_rational(paid_to_in, sold_in)
+		} else if !sold_out.is_zero() {
+			FixedU128::checked_from_rational(paid_to_out, sold_out)
+		} else {
+			FixedU128::checked_from_rational(sold_in, paid_to_in)
+		}
+		.unwrap_or_default();
+
+		Ok(BatchClearing { sold_a, sold_b, pool_takes_a, pool_in, pool_out, amounts_out, price })
+	}
+
+	/// Swaps the difference of a cleared batch with the pool and pays every intent its share. What is left
+	/// over from rounding the shares down is added to the reserves of the pool.
+	fn settle_swap_batch(
+		pool_key: &PoolKeyOf<T>,
+		pool: &LiquidityPool<T>,
+		intents: &[SwapIntent<T>],
+		clearing: BatchClearing,
+	) -> DispatchResult {
+		let (asset_a, asset_b, fee) = pool_key.clone();
+		let to_balance = |amount: u128| AssetBalanceOf::<T>::try_from(amount).map_err(|_| ArithmeticError::Overflow);
+		let (asset_in, asset_out, reserve_in, reserve_out, sold_in, sold_out) = if clearing.pool_takes_a {
+			(asset_a.clone(), asset_b.clone(), pool.reserve_a, pool.reserve_b, clearing.sold_a, clearing.sold_b)
+		} else {
+			(asset_b.clone(), asset_a.clone(), pool.reserve_b, pool.reserve_a, clearing.sold_b, clearing.sold_a)
+		};
+
+		// pay every intent its share out of the escrow account
+		let escrow_account = Self::escrow_account();
+		let mut left_in = sold_in - clearing.pool_in;
+		let mut left_out = clearing.pool_out.checked_add(sold_out).ok_or(ArithmeticError::Overflow)?;
+		let mut payouts = Vec::new();
+		for (intent, amount_out) in intents.iter().zip(&clearing.amounts_out) {
+			let (intent_asset_out, left) = if intent.asset_in == asset_in { (&asset_out, &mut left_out) } else { (&asset_in, &mut left_in) };
+			*left = left.checked_sub(*amount_out).ok_or(ArithmeticError::Underflow)?;
+			payouts.push((intent, intent_asset_out, to_balance(*amount_out)?));
+		}
+
+		// the pool swaps the difference, the rounding dust of both assets goes to the pool as well
+		let pool_in = to_balance(clearing.pool_in)?;
+		let pool_out = to_balance(clearing.pool_out)?;
+		let (dust_in, dust_out) = (to_balance(left_in)?, to_balance(left_out)?);
+		Self::transfer_asset(&asset_in, &escrow_account, &pool.pool_account, pool_in.checked_add(&dust_in).ok_or(ArithmeticError::Overflow)?)?;
+		Self::transfer_asset(&asset_out, &pool.pool_account, &escrow_account, pool_out)?;
+		Self::transfer_asset(&asset_out, &escrow_account, &pool.pool_account, dust_out)?;
+
+		for (intent, intent_asset_out, amount_out) in payouts {
+			Self::transfer_asset(intent_asset_out, &escrow_account, &intent.who, amount_out)?;
+			Self::deposit_event(Event::<T>::SwapIntentFilled {
+				who: intent.who.clone(),
+				pool_id: pool.liquidity_id.clone(),
+				asset_in: intent.asset_in.clone(),
+				asset_out: intent_asset_out.clone(),
+				fee,
+				amount_in: intent.amount_in,
+				amount_out,
+			});
+		}
+
+		// update the tracked reserves
+		let new_reserve_in = reserve_in
+			.checked_add(&pool_in)
+			.and_then(|reserve| reserve.checked_add(&dust_in))
+			.ok_or(ArithmeticError::Overflow)?;
+		let new_reserve_out = reserve_out
+			.checked_sub(&pool_out)
+			.and_then(|reserve| reserve.checked_add(&dust_out))
+			.ok_or(ArithmeticError::Underflow)?;
+		Self::set_reserves(asset_in, asset_out, fee, new_reserve_in, new_reserve_out)?;
+
+		Self::deposit_event(Event::<T>::BatchCleared {
+			pool_id: pool.liquidity_id.clone(),
+			asset_a,
+			asset_b,
+			fee,
+			amount_a_in: to_balance(clearing.sold_a)?,
+			amount_b_in: to_balance(clearing.sold_b)?,
+			price: clearing.price,
+		});
+
+		Ok(())
+	}
+
+	/// Keeps the held amount of a swap intent that couldn't be paid back in the escrow account, for the account
+	/// that submitted it to claim.
+	fn keep_unclaimed_refund(pool_key: &PoolKeyOf<T>, intent: &SwapIntent<T>) {
+		UnclaimedRefunds::<T>::mutate(&intent.who, &intent.asset_in, |amount| {
+			*amount = amount.saturating_add(intent.amount_in)
+		});
+		Self::deposit_event(Event::<T>::SwapIntentRefundUnclaimed {
+			who: intent.who.clone(),
+			pool_id: LiquidityPoolMap::<T>::get(pool_key).map(|pool| pool.liquidity_id),
+			asset_in: intent.asset_in.clone(),
+			fee: pool_key.2,
+			amount_in: intent.amount_in,
+		});
+	}
+
+	/// Pays the held amount of a swap intent back to the account that submitted it.
+	fn refund_swap_intent(pool_key: &PoolKeyOf<T>, intent: &SwapIntent<T>) -> DispatchResult {
+		Self::transfer_asset(&intent.asset_in, &Self::escrow_account(), &intent.who, intent.amount_in)?;
+		Self::deposit_event(Event::<T>::SwapIntentRefunded {
+			who: intent.who.clone(),
+			pool_id: LiquidityPoolMap::<T>::get(pool_key).map(|pool| pool.liquidity_id),
+			asset_in: intent.asset_in.clone(),
+			fee: pool_key.2,
+			amount_in: intent.amount_in,
+		});
+		Ok(())
+	}
 
-impl<T: Config> Pallet<T> {
 
 	/// Orders asset Ids
 	/// lp(a, b) == lp(b, a)
@@ -571,11 +3778,11 @@ impl<T: Config> Pallet<T> {
 	/// 2. we "sort" the assets 3. then create the id
 	/// you need to figure out what you want to return if anything
 	pub fn order_asset_ids(
-		asset_a: AssetIdOf<T>,
-		asset_b: AssetIdOf<T>,
+		asset_a: BalanceType<T>,
+		asset_b: BalanceType<T>,
 		amount_a: AssetBalanceOf<T>,
 		amount_b: AssetBalanceOf<T>
-	) -> Result<(AssetIdOf<T>, AssetIdOf<T>, AssetBalanceOf<T>, AssetBalanceOf<T>), DispatchError> {
+	) -> Result<(BalanceType<T>, BalanceType<T>, AssetBalanceOf<T>, AssetBalanceOf<T>), DispatchError> {
 		ensure!(asset_a != asset_b, "cant use the same id twice");
 		return if asset_a.encode() > asset_b.encode() {
 			Ok((asset_a, asset_b, amount_a, amount_b))
@@ -588,55 +3795,224 @@ impl<T: Config> Pallet<T> {
 	/// and checks if the balance is greater than the minimum required.
 	fn check_user_balances(
 		who: &T::AccountId, 
-		asset_a: &AssetIdOf<T>, 
-		asset_b: &AssetIdOf<T>,
+		asset_a: &BalanceType<T>, 
+		asset_b: &BalanceType<T>,
 		amount_a: &AssetBalanceOf<T>,
 		amount_b: &AssetBalanceOf<T>,
 	) -> DispatchResult {
-		ensure!(T::Fungibles::balance(asset_a.clone(), who) >= *amount_a, "Not enough balance for asset_a");
-		ensure!(T::Fungibles::balance(asset_b.clone(), who) >= *amount_b, "Not enough balance for asset_b");
+		ensure!(Self::asset_balance(asset_a, who) >= *amount_a, "Not enough balance for asset_a");
+		ensure!(Self::asset_balance(asset_b, who) >= *amount_b, "Not enough balance for asset_b");
 		ensure!(!amount_a.is_zero(), "Balance of asset_a has to be greater then the min value");
 		ensure!(!amount_b.is_zero(), "Balance of asset_b has to be greater then the min value");
 		Ok(())
 	}
 
+	/// Returns the amounts to deposit at the ratio of the reserves, like `_addLiquidity` in the Uniswap V2 router.
+	/// If `amount_b_desired` is enough to match `amount_a_desired` all of asset a is used, otherwise all of
+	/// asset b is used and the amount of asset a is reduced. Fails if an amount is below its minimum.
+	fn optimal_liquidity_amounts(
+		reserve_a: AssetBalanceOf<T>,
+		reserve_b: AssetBalanceOf<T>,
+		amount_a_desired: AssetBalanceOf<T>,
+		amount_b_desired: AssetBalanceOf<T>,
+		amount_a_min: AssetBalanceOf<T>,
+		amount_b_min: AssetBalanceOf<T>,
+	) -> Result<(AssetBalanceOf<T>, AssetBalanceOf<T>), DispatchError> {
+		let amount_b_optimal = Self::quote(amount_a_desired, reserve_a, reserve_b)?;
+		if amount_b_optimal <= amount_b_desired {
+			ensure!(amount_b_optimal >= amount_b_min, Error::<T>::NotEnoughAssetsB);
+			return Ok((amount_a_desired, amount_b_optimal))
+		}
+
+		let amount_a_optimal = Self::quote(amount_b_desired, reserve_b, reserve_a)?;
+		// amount_b_optimal > amount_b_desired implies amount_a_optimal <= amount_a_desired
+		ensure!(amount_a_optimal >= amount_a_min, Error::<T>::NotEnoughAssetsA);
+		Ok((amount_a_optimal, amount_b_desired))
+	}
+
+	/// Converts `amount_x` to the other asset of the pool at the ratio of the reserves, without a fee.
+	fn quote(
+		amount_x: AssetBalanceOf<T>,
+		reserve_x: AssetBalanceOf<T>,
+		reserve_y: AssetBalanceOf<T>,
+	) -> Result<AssetBalanceOf<T>, DispatchError> {
+		let part1 = amount_x.checked_mul(&reserve_y).ok_or(ArithmeticError::Overflow)?;
+		let amount_y = part1.checked_div(&reserve_x).ok_or(Error::<T>::EmptyPool)?;
+		Ok(amount_y)
+	}
+
 	// Transfer both assets A and B from one account (from) to another account (dest)
 	// This function is used to transfer the assets from the user's account to the pool account.
 	// Or from user's account to the pool account
 	fn transfer_assets_a_and_b(
 		from: &T::AccountId, 
 		dest: &T::AccountId,
-		asset_a: &AssetIdOf<T>, 
-		asset_b: &AssetIdOf<T>,
+		asset_a: &BalanceType<T>, 
+		asset_b: &BalanceType<T>,
 		amount_a: &AssetBalanceOf<T>,
 		amount_b: &AssetBalanceOf<T>,
 	) -> DispatchResult {
 		// It Preservation is expendable so it can transfer the full amount from one account, 
 		// even if the amount is less than zero
-		T::Fungibles::transfer(asset_a.clone(), from, dest, *amount_a, Preservation::Expendable)?;
-		T::Fungibles::transfer(asset_b.clone(), from, dest, *amount_b, Preservation::Expendable)?;
+		Self::transfer_asset(asset_a, from, dest, *amount_a)?;
+		Self::transfer_asset(asset_b, from, dest, *amount_b)?;
+		Ok(())
+	}
+
+	/// Returns the balance `who` holds of the given asset, which can be the native currency.
+	pub fn asset_balance(asset: &BalanceType<T>, who: &T::AccountId) -> AssetBalanceOf<T> {
+		match asset {
+			BalanceType::NativeBalance => T::NativeBalance::balance(who),
+			BalanceType::AssetBalance(asset_id) => T::Fungibles::balance(asset_id.clone(), who),
+		}
+	}
+
+	/// Returns the balance of the given asset that the pool account holds as reserves.
+	/// The native currency deposited while creating the pool account is not part of the reserves.
+	fn pool_reserve(asset: &BalanceType<T>, pool_account: &T::AccountId) -> AssetBalanceOf<T> {
+		match asset {
+			BalanceType::NativeBalance => T::NativeBalance::balance(pool_account)
+				.saturating_sub(BalanceOf::<T>::from(POOL_ACCOUNT_DEPOSIT)),
+			BalanceType::AssetBalance(asset_id) => T::Fungibles::balance(asset_id.clone(), pool_account),
+		}
+	}
+
+	/// Transfers an amount of the given asset, the native currency is moved through `T::NativeBalance`
+	/// and all the other assets through `T::Fungibles`.
+	pub fn transfer_asset(
+		asset: &BalanceType<T>,
+		from: &T::AccountId,
+		dest: &T::AccountId,
+		amount: AssetBalanceOf<T>,
+	) -> DispatchResult {
+		if amount.is_zero() {
+			return Ok(())
+		}
+		match asset {
+			BalanceType::NativeBalance => {
+				T::NativeBalance::transfer(from, dest, amount, Preservation::Expendable)?;
+			},
+			BalanceType::AssetBalance(asset_id) => {
+				T::Fungibles::transfer(asset_id.clone(), from, dest, amount, Preservation::Expendable)?;
+			},
+		}
 		Ok(())
 	}
 
-	// A function to generate unique liquidity_id from asset_a and asset_b
-	fn create_liquidity_id(asset_a: AssetIdOf<T>, asset_b: AssetIdOf<T>) -> AssetIdOf<T> {
-		// Generate a unique id based on asset_a and asset_b
-		// This is a very simplistic approach and might need to be enhanced based on your needs.
-		let bytes = T::Hashing::hash(&(asset_a, asset_b).encode());
-		let liq_id = AssetIdOf::<T>::decode(&mut TrailingZeroInput::new(&bytes.encode()))
-			.expect("in our PBA exam, we assume all bytes can be turned into some account id");
-		liq_id
+	/// The next free LP token id and its `u32` index, skipping the ids of existing assets. At most
+	/// `LP_ASSET_ID_PROBES` ids are looked at.
+	fn free_lp_asset_id() -> Result<(AssetIdOf<T>, u32), DispatchError> {
+		let start = NextLpAssetId::<T>::get().unwrap_or_else(T::LpAssetIdStart::get);
+		for offset in 0..LP_ASSET_ID_PROBES {
+			let index = start.checked_add(offset).ok_or(Error::<T>::LpAssetIdsExhausted)?;
+			let id = AssetIdOf::<T>::decode(&mut TrailingZeroInput::new(&index.encode()))
+				.map_err(|_| Error::<T>::LpAssetIdsExhausted)?;
+			if !T::Fungibles::asset_exists(id.clone()) {
+				return Ok((id, index));
+			}
+		}
+		Err(Error::<T>::LpAssetIdsExhausted.into())
+	}
+
+	/// Takes the next free LP token id for the LP token of a new pool.
+	fn take_lp_asset_id() -> Result<AssetIdOf<T>, DispatchError> {
+		let (id, index) = Self::free_lp_asset_id()?;
+		// the last id is taken once, the next pool finds it in use
+		NextLpAssetId::<T>::put(index.saturating_add(1));
+		Ok(id)
+	}
+
+	/// The name `LP-A-B` of the LP token of a pool after the symbols of its assets, and its symbol, which adds
+	/// the fee tier in basis points as a pair can have a pool per fee tier.
+	fn lp_token_metadata(asset_a: &BalanceType<T>, asset_b: &BalanceType<T>, fee: Permill) -> (Vec<u8>, Vec<u8>) {
+		let mut name = MetadataWriter(b"LP-".to_vec());
+		name.0.extend(Self::asset_label(asset_a));
+		name.0.push(b'-');
+		name.0.extend(Self::asset_label(asset_b));
+
+		let mut symbol = MetadataWriter(name.0.clone());
+		let _ = write!(symbol, "-{}", fee.deconstruct() / 100);
+		(name.0, symbol.0)
+	}
+
+	/// The name and the symbol of the LP token of a weighted pool, after the id of the pool.
+	fn weighted_lp_token_metadata(pool_id: WeightedPoolId) -> (Vec<u8>, Vec<u8>) {
+		let (mut name, mut symbol) = (MetadataWriter(Vec::new()), MetadataWriter(Vec::new()));
+		let _ = write!(name, "LP-WEIGHTED-{}", pool_id);
+		let _ = write!(symbol, "LPW-{}", pool_id);
+		(name.0, symbol.0)
+	}
+
+	/// The symbol of an asset in the metadata of LP tokens, or its id if it has no symbol.
+	fn asset_label(asset: &BalanceType<T>) -> Vec<u8> {
+		let mut label = match asset {
+			BalanceType::NativeBalance => b"NATIVE".to_vec(),
+			BalanceType::AssetBalance(id) => {
+				let symbol = T::Fungibles::symbol(id.clone());
+				if symbol.is_empty() {
+					let mut writer = MetadataWriter(Vec::new());
+					let _ = write!(writer, "{:?}", id);
+					writer.0
+				} else {
+					symbol
+				}
+			},
+		};
+		label.truncate(LP_TOKEN_LABEL_LIMIT);
+		label
+	}
+
+	/// Destroys the LP token of a removed pool, once all of it has been burned.
+	fn destroy_lp_token(lp_asset_id: AssetIdOf<T>) -> DispatchResult {
+		T::Fungibles::start_destroy(lp_asset_id.clone(), Some(Self::account_id()))?;
+		T::Fungibles::destroy_accounts(lp_asset_id.clone(), LP_TOKEN_DESTROY_LIMIT)?;
+		T::Fungibles::destroy_approvals(lp_asset_id.clone(), LP_TOKEN_DESTROY_LIMIT)?;
+		T::Fungibles::finish_destroy(lp_asset_id)
+	}
+
+	/// Removes a pool once its LP tokens have been burned and its reserves paid out. The LP token is destroyed,
+	/// and `who` gets the metadata deposit and the deposit of the pool account back.
+	fn close_pool(
+		who: &T::AccountId,
+		pool_key: &PoolKeyOf<T>,
+		lp_asset_id: AssetIdOf<T>,
+		pool_account: &T::AccountId,
+	) -> DispatchResult {
+		// Pools created before LP tokens had metadata didn't pay a metadata deposit
+		let paid_metadata = !T::Fungibles::name(lp_asset_id.clone()).is_empty();
+		Self::destroy_lp_token(lp_asset_id)?;
+		if paid_metadata {
+			T::NativeBalance::transfer(&Self::account_id(), who, T::LpMetadataDeposit::get(), Preservation::Expendable)?;
+		}
+
+		// Return native tokens to the account issuer:
+		T::NativeBalance::transfer(pool_account, who, BalanceOf::<T>::from(POOL_ACCOUNT_DEPOSIT), Preservation::Expendable)?;
+
+		// Remove the pool, its price history, its batch mode and its pauses from storage
+		LiquidityPoolMap::<T>::remove(pool_key);
+		PriceObservations::<T>::remove(pool_key);
+		BatchModePools::<T>::remove(pool_key);
+		PausedPools::<T>::remove(pool_key);
+		Ok(())
 	}
 
+	// Pool accounts are sub-accounts of the pallet id, so they can't clash with keyed accounts. The pool key is
+	// hashed, as it may be longer than the room a sub-account has for it.
 	// This function assumes asset_a and asset_b have already been sorted
 	fn generate_account_from_asset_id_pair(
-		asset_a: AssetIdOf<T>,
-		asset_b: AssetIdOf<T>,
+		asset_a: BalanceType<T>,
+		asset_b: BalanceType<T>,
+		fee: Permill,
 	) -> T::AccountId {
-		let bytes = T::Hashing::hash(&(asset_a, asset_b).encode());
-		let generated_account = T::AccountId::decode(&mut TrailingZeroInput::new(&bytes.encode()))
-			.expect("in our PBA exam, we assume all bytes can be turned into some account id");
-		generated_account
+		T::PalletId::get().into_sub_account_truncating(T::Hashing::hash_of(&(b"pool", asset_a, asset_b, fee)))
+	}
+
+	fn weighted_pool_account(pool_id: WeightedPoolId) -> T::AccountId {
+		T::PalletId::get().into_sub_account_truncating(T::Hashing::hash_of(&(b"weighted", pool_id)))
+	}
+
+	fn concentrated_pool_account(pool_id: ConcentratedPoolId) -> T::AccountId {
+		T::PalletId::get().into_sub_account_truncating(T::Hashing::hash_of(&(b"concentrated", pool_id)))
 	}
 
 	/// The account ID of the dex pallet. It can be used as an admin for new assets created.
@@ -647,26 +4023,460 @@ impl<T: Config> Pallet<T> {
 		T::PalletId::get().into_account_truncating()
 	}
 
+	/// The account holding the amounts of the open limit orders and of the swap intents of the block, apart
+	/// from the deposits of the pallet account and the LP tokens locked in the pool accounts.
+	fn escrow_account() -> T::AccountId {
+		T::PalletId::get().into_sub_account_truncating(b"escrow")
+	}
+
+	/// Makes sure an account of the pallet exists, so it can hold any amount of the native currency and the
+	/// assets. If it doesn't, `payer` funds it with the existential deposit, which stays in the account.
+	fn ensure_account_funded(account: &T::AccountId, payer: &T::AccountId) -> DispatchResult {
+		if !frame_system::Pallet::<T>::account_exists(account) {
+			T::NativeBalance::transfer(payer, account, T::NativeBalance::minimum_balance(), Preservation::Expendable)?;
+		}
+		Ok(())
+	}
+
+	/// Lends the amounts of the pool to `who`, runs `T::FlashSwapHandler` and checks that the constant
+	/// product of the pool is restored with the balances of the pool account, like `swap` in Uniswap V2.
+	pub fn do_flash_swap(
+		who: &T::AccountId,
+		asset_a: BalanceType<T>,
+		asset_b: BalanceType<T>,
+		fee: Permill,
+		amount_a_out: AssetBalanceOf<T>,
+		amount_b_out: AssetBalanceOf<T>,
+	) -> DispatchResult {
+		// Order the asset ids, orders the amounts as well
+		let (asset_a, asset_b, amount_a_out, amount_b_out) = Self::order_asset_ids(asset_a, asset_b, amount_a_out, amount_b_out)?;
+		ensure!(!amount_a_out.is_zero() || !amount_b_out.is_zero(), Error::<T>::FlashSwapAmountZero);
+
+		// Get the liquidity pool, this fails if it doesn't exist
+		let pool = LiquidityPoolMap::<T>::get(&(asset_a.clone(), asset_b.clone(), fee)).ok_or(Error::<T>::PoolDoesNotExist)?;
+		Self::ensure_not_in_batch_mode(&asset_a, &asset_b, fee)?;
+		Self::ensure_swaps_not_paused(&asset_a, &asset_b, fee)?;
+		let (reserve_a, reserve_b) = (pool.reserve_a, pool.reserve_b);
+		ensure!(amount_a_out < reserve_a && amount_b_out < reserve_b, Error::<T>::RequestedExceedsPoolBalance);
+
+		// Lend the amounts and let the handler use them
+		Self::transfer_assets_a_and_b(&pool.pool_account, who, &asset_a, &asset_b, &amount_a_out, &amount_b_out)?;
+		T::FlashSwapHandler::on_flash_swap(who, &pool.pool_account, &asset_a, &asset_b, amount_a_out, amount_b_out)?;
+
+		// The handler may not trade with the pool while its assets are borrowed
+		let pool_after = LiquidityPoolMap::<T>::get(&(asset_a.clone(), asset_b.clone(), fee)).ok_or(Error::<T>::PoolDoesNotExist)?;
+		ensure!(pool_after.reserve_a == reserve_a && pool_after.reserve_b == reserve_b, Error::<T>::PoolChangedDuringFlashSwap);
+
+		// Whatever the pool account holds on top of the reserves left after lending was paid in
+		let balance_a = Self::pool_reserve(&asset_a, &pool.pool_account);
+		let balance_b = Self::pool_reserve(&asset_b, &pool.pool_account);
+		let amount_a_in = balance_a.saturating_sub(reserve_a.saturating_sub(amount_a_out));
+		let amount_b_in = balance_b.saturating_sub(reserve_b.saturating_sub(amount_b_out));
+		ensure!(!amount_a_in.is_zero() || !amount_b_in.is_zero(), Error::<T>::FlashSwapNotRepaid);
+
+		// The fee is taken from the amounts paid in, the rest has to keep the invariant of the curve
+		let adjusted_a = balance_a.checked_sub(&(fee * amount_a_in)).ok_or(ArithmeticError::Underflow)?;
+		let adjusted_b = balance_b.checked_sub(&(fee * amount_b_in)).ok_or(ArithmeticError::Underflow)?;
+		ensure!(
+			Self::curve_k(&pool.curve, adjusted_a, adjusted_b)? >= Self::curve_k(&pool.curve, reserve_a, reserve_b)?,
+			Error::<T>::FlashSwapNotRepaid
+		);
+
+		// Update the tracked reserves
+		Self::set_reserves(asset_a.clone(), asset_b.clone(), fee, balance_a, balance_b)?;
+
+		Self::deposit_event(Event::<T>::FlashSwapped {
+			who: who.clone(),
+			pool_id: pool.liquidity_id,
+			asset_a,
+			asset_b,
+			fee,
+			amount_a_out,
+			amount_b_out,
+			amount_a_in,
+			amount_b_in,
+		});
+
+		Ok(())
+	}
+
+	/// Fails if the current block is past the deadline, so a transaction that waited too long
+	/// in the transaction pool doesn't execute at a stale price.
+	fn ensure_deadline(deadline: Option<BlockNumberFor<T>>) -> DispatchResult {
+		if let Some(deadline) = deadline {
+			ensure!(frame_system::Pallet::<T>::block_number() <= deadline, Error::<T>::DeadlineExpired);
+		}
+		Ok(())
+	}
+
+	/// Whether pools can be created with the fee tier, the default fee tier is always allowed.
+	fn is_fee_tier_allowed(fee: Permill) -> bool {
+		fee == T::DefaultFeeTier::get() || FeeTiers::<T>::get().contains(&fee)
+	}
+
+	/// Returns the pool of the pair with the fee tier and its tracked reserves of `asset_in` and `asset_out`.
+	/// Fails if the pool doesn't exist.
+	fn get_reserves(
+		asset_in: BalanceType<T>,
+		asset_out: BalanceType<T>,
+		fee: Permill,
+	) -> Result<(LiquidityPool<T>, AssetBalanceOf<T>, AssetBalanceOf<T>), DispatchError> {
+		//  Order the asset ids
+		let (asset_a, asset_b, _, _) = Self::order_asset_ids(asset_in.clone(), asset_out, AssetBalanceOf::<T>::zero(), AssetBalanceOf::<T>::zero())?;
+
+		// Get the liquidity pool, this fails if it doesn't exist
+		let pool = LiquidityPoolMap::<T>::get(&(asset_a.clone(), asset_b, fee)).ok_or(Error::<T>::PoolDoesNotExist)?;
+
+		// order the reserves the same way as the assets
+		let (reserve_in, reserve_out) = if asset_in == asset_a {
+			(pool.reserve_a, pool.reserve_b)
+		} else {
+			(pool.reserve_b, pool.reserve_a)
+		};
+
+		Ok((pool, reserve_in, reserve_out))
+	}
+
+	/// Sets the tracked reserves of the pool of the pair with the fee tier, the assets and reserves can be
+	/// passed in any order.
+	fn set_reserves(
+		asset_x: BalanceType<T>,
+		asset_y: BalanceType<T>,
+		fee: Permill,
+		reserve_x: AssetBalanceOf<T>,
+		reserve_y: AssetBalanceOf<T>,
+	) -> DispatchResult {
+		let (asset_a, asset_b, reserve_a, reserve_b) = Self::order_asset_ids(asset_x, asset_y, reserve_x, reserve_y)?;
+		let pool_key = (asset_a, asset_b, fee);
+		LiquidityPoolMap::<T>::try_mutate(&pool_key, |maybe_pool| {
+			let pool = maybe_pool.as_mut().ok_or(Error::<T>::PoolDoesNotExist)?;
+			// the price oracle has to see the reserves before they change
+			Self::update_price_accumulators(&pool_key, pool);
+			pool.reserve_a = reserve_a;
+			pool.reserve_b = reserve_b;
+			Ok(())
+		})
+	}
+
+	/// Returns the account receiving the protocol fee, if the protocol fee is switched on.
+	fn active_protocol_fee_recipient() -> Option<T::AccountId> {
+		if ProtocolFeeShare::<T>::get().is_zero() {
+			return None;
+		}
+		ProtocolFeeRecipient::<T>::get()
+	}
+
+	/// Mints the protocol's share of the fees accrued by the pool since the last liquidity event as LP tokens
+	/// to the protocol fee recipient, like `_mintFee` in Uniswap V2. The fees show up as the growth of
+	/// sqrt(k) of the curve of the pool. Returns whether the protocol fee is switched on.
+	fn mint_protocol_fee(pool_key: &PoolKeyOf<T>, pool: &LiquidityPool<T>) -> Result<bool, DispatchError> {
+		let Some(recipient) = Self::active_protocol_fee_recipient() else {
+			return Ok(false);
+		};
+		if pool.k_last.is_zero() {
+			return Ok(true);
+		}
+
+		let root_k = Self::get_sqrt_of_asset_balance(Self::curve_k(&pool.curve, pool.reserve_a, pool.reserve_b)?);
+		let root_k_last = Self::get_sqrt_of_asset_balance(pool.k_last);
+		if root_k <= root_k_last {
+			return Ok(true);
+		}
+
+		// liquidity = total_supply * share * (root_k - root_k_last) / (root_k * (100 - share) + root_k_last * share)
+		let share = AssetBalanceOf::<T>::from(ProtocolFeeShare::<T>::get().deconstruct() as u32);
+		let remaining = AssetBalanceOf::<T>::from(100u32).saturating_sub(share);
+		let total_supply = T::Fungibles::total_issuance(pool.liquidity_id.clone());
+		let numerator = total_supply
+			.checked_mul(&share)
+			.and_then(|n| n.checked_mul(&(root_k - root_k_last)))
+			.ok_or(ArithmeticError::Overflow)?;
+		let denominator = root_k
+			.checked_mul(&remaining)
+			.and_then(|d| d.checked_add(&root_k_last.checked_mul(&share)?))
+			.ok_or(ArithmeticError::Overflow)?;
+		let liquidity = numerator.checked_div(&denominator).ok_or(ArithmeticError::DivisionByZero)?;
+
+		if !liquidity.is_zero() {
+			T::Fungibles::mint_into(pool.liquidity_id.clone(), &recipient, liquidity)?;
+			Self::deposit_event(Event::<T>::ProtocolFeeMinted {
+				pool_id: pool.liquidity_id.clone(),
+				asset_a: pool_key.0.clone(),
+				asset_b: pool_key.1.clone(),
+				fee: pool_key.2,
+				recipient,
+				amount: liquidity,
+			});
+		}
+
+		Ok(true)
+	}
+
+	/// Stores `k` of the pool after a liquidity event, or zero if the protocol fee is off.
+	fn update_k_last(pool_key: &PoolKeyOf<T>, fee_on: bool) -> DispatchResult {
+		LiquidityPoolMap::<T>::try_mutate(pool_key, |maybe_pool| {
+			let pool = maybe_pool.as_mut().ok_or(Error::<T>::PoolDoesNotExist)?;
+			pool.k_last = if fee_on {
+				Self::curve_k(&pool.curve, pool.reserve_a, pool.reserve_b)?
+			} else {
+				Zero::zero()
+			};
+			Ok(())
+		})
+	}
+
+	/// Returns `k` of `curve` with the reserves, see `PoolCurve::k`.
+	fn curve_k(
+		curve: &PoolCurve,
+		reserve_a: AssetBalanceOf<T>,
+		reserve_b: AssetBalanceOf<T>,
+	) -> Result<AssetBalanceOf<T>, DispatchError> {
+		let k = curve
+			.k(reserve_a.unique_saturated_into(), reserve_b.unique_saturated_into())
+			.ok_or(ArithmeticError::Overflow)?;
+		Ok(AssetBalanceOf::<T>::try_from(k).map_err(|_| ArithmeticError::Overflow)?)
+	}
+
+	/// Returns the spot prices of asset_a in asset_b and of asset_b in asset_a as the inner values of `FixedU128`.
+	/// A pool without reserves has no price.
+	fn spot_prices(pool: &LiquidityPool<T>) -> (u128, u128) {
+		let (price_a, price_b) =
+			pool.curve.spot_prices(pool.reserve_a.unique_saturated_into(), pool.reserve_b.unique_saturated_into());
+		(price_a.into_inner(), price_b.into_inner())
+	}
+
+	/// Returns the cumulative prices of the pool extrapolated to `block`, assuming the reserves don't change.
+	fn cumulative_prices_at(pool: &LiquidityPool<T>, block: BlockNumberFor<T>) -> (u128, u128) {
+		let elapsed: u128 = block.saturating_sub(pool.last_oracle_update).unique_saturated_into();
+		let (price_a, price_b) = Self::spot_prices(pool);
+		(
+			pool.price_a_cumulative.into_inner().wrapping_add(price_a.wrapping_mul(elapsed)),
+			pool.price_b_cumulative.into_inner().wrapping_add(price_b.wrapping_mul(elapsed)),
+		)
+	}
+
+	/// Accumulates the prices of the pool up to the current block. This happens on the first interaction
+	/// with the pool in a block, before the reserves change, and records a new price observation.
+	fn update_price_accumulators(pool_key: &PoolKeyOf<T>, pool: &mut LiquidityPool<T>) {
+		let now = frame_system::Pallet::<T>::block_number();
+		if now <= pool.last_oracle_update {
+			return
+		}
+
+		let (price_a_cumulative, price_b_cumulative) = Self::cumulative_prices_at(pool, now);
+		pool.price_a_cumulative = FixedU128::from_inner(price_a_cumulative);
+		pool.price_b_cumulative = FixedU128::from_inner(price_b_cumulative);
+		pool.last_oracle_update = now;
+
+		Self::record_price_observation(pool_key, PriceObservation {
+			block: now,
+			price_a_cumulative: pool.price_a_cumulative,
+			price_b_cumulative: pool.price_b_cumulative,
+		});
+	}
+
+	/// Appends a price observation to the history of the pool, dropping the oldest one if the history is full.
+	fn record_price_observation(
+		pool_key: &PoolKeyOf<T>,
+		observation: PriceObservation<BlockNumberFor<T>>,
+	) {
+		PriceObservations::<T>::mutate(pool_key, |observations| {
+			if observations.len() >= T::MaxPriceObservations::get() as usize && !observations.is_empty() {
+				observations.remove(0);
+			}
+			let _ = observations.try_push(observation);
+		});
+	}
+
+	/// Calculates the amount of the output asset the pool pays out for `amount_in` on its curve.
+	/// The fee is taken from `amount_in` and left in the pool.
+	pub fn get_amount_out(
+		amount_in: AssetBalanceOf<T>,
+		reserve_in: AssetBalanceOf<T>,
+		reserve_out: AssetBalanceOf<T>,
+		fee: Permill,
+		curve: &PoolCurve,
+	) -> Result<AssetBalanceOf<T>, DispatchError> {
+		// take the fee of the pool for processing the transaction
+		let reduced_for_fee_in = fee.left_from_one() * amount_in;
+
+		// the curve keeps its invariant with the reduced amount
+		let amount_out = curve
+			.amount_out(
+				reduced_for_fee_in.unique_saturated_into(),
+				reserve_in.unique_saturated_into(),
+				reserve_out.unique_saturated_into(),
+			)
+			.ok_or(ArithmeticError::Overflow)?;
+
+		Ok(AssetBalanceOf::<T>::try_from(amount_out).map_err(|_| ArithmeticError::Overflow)?)
+	}
+
+	/// Calculates the amount of the input asset the pool requires to pay out exactly `amount_out` on its curve.
+	/// The fee is added on top of the returned amount and left in the pool.
+	pub fn get_amount_in(
+		amount_out: AssetBalanceOf<T>,
+		reserve_in: AssetBalanceOf<T>,
+		reserve_out: AssetBalanceOf<T>,
+		fee: Permill,
+		curve: &PoolCurve,
+	) -> Result<AssetBalanceOf<T>, DispatchError> {
+		// Check if the amount out exceeds the pool balance
+		ensure!(reserve_out > amount_out, Error::<T>::RequestedExceedsPoolBalance);
+
+		// the amount that keeps the invariant of the curve
+		let amount_in_before_fee = curve
+			.amount_in(amount_out.unique_saturated_into(), reserve_in.unique_saturated_into(), reserve_out.unique_saturated_into())
+			.ok_or(ArithmeticError::Overflow)?;
+		let amount_in_before_fee = AssetBalanceOf::<T>::try_from(amount_in_before_fee).map_err(|_| ArithmeticError::Overflow)?;
+
+		// Take the fee of the pool for processing the transaction
+		let fee_amount = fee * amount_in_before_fee;
+		let amount_in = amount_in_before_fee.checked_add(&fee_amount).ok_or(ArithmeticError::Overflow)?;
+
+		Ok(amount_in)
+	}
+
+	/// Returns the part of `amount_in` a pool keeps as its fee in a swap for `amount_out` from the reserves before
+	/// the swap. An exact in swap takes the fee from `amount_in`, an exact out swap adds it on top of the amount
+	/// the curve of the pool requires.
+	fn swap_fee_amount(
+		pool: &LiquidityPool<T>,
+		amount_in: AssetBalanceOf<T>,
+		amount_out: AssetBalanceOf<T>,
+		reserve_in: AssetBalanceOf<T>,
+		reserve_out: AssetBalanceOf<T>,
+		exact_in: bool,
+	) -> AssetBalanceOf<T> {
+		if exact_in {
+			return amount_in.saturating_sub(pool.fee.left_from_one() * amount_in)
+		}
+		pool.curve
+			.amount_in(amount_out.unique_saturated_into(), reserve_in.unique_saturated_into(), reserve_out.unique_saturated_into())
+			.and_then(|amount_in_before_fee| AssetBalanceOf::<T>::try_from(amount_in_before_fee).ok())
+			.map(|amount_in_before_fee| amount_in.saturating_sub(amount_in_before_fee))
+			.unwrap_or_else(Zero::zero)
+	}
+
+	/// Makes sure the path has at least two assets, a fee tier for every hop and that no asset is visited
+	/// twice, so every hop goes through a different pool.
+	fn validate_path(path: &[BalanceType<T>], fees: &[Permill]) -> DispatchResult {
+		ensure!(path.len() >= 2, Error::<T>::InvalidPath);
+		ensure!(fees.len() == path.len() - 1, Error::<T>::InvalidPath);
+		for (i, asset) in path.iter().enumerate() {
+			ensure!(!path[i + 1..].contains(asset), Error::<T>::InvalidPath);
+		}
+		Ok(())
+	}
+
+	/// Calculates the amounts of every hop for swapping exactly `amount_in` of the first asset in the path.
+	/// The first element is `amount_in`, the last one is the amount of the last asset the user receives.
+	pub fn get_amounts_out(
+		path: &[BalanceType<T>],
+		fees: &[Permill],
+		amount_in: AssetBalanceOf<T>,
+	) -> Result<Vec<AssetBalanceOf<T>>, DispatchError> {
+		Self::validate_path(path, fees)?;
+
+		let mut amounts = Vec::with_capacity(path.len());
+		amounts.push(amount_in);
+		for (hop, fee) in path.windows(2).zip(fees) {
+			let (pool, reserve_in, reserve_out) = Self::get_reserves(hop[0].clone(), hop[1].clone(), *fee)?;
+			let amount_out = Self::get_amount_out(amounts[amounts.len() - 1], reserve_in, reserve_out, pool.fee, &pool.curve)?;
+			amounts.push(amount_out);
+		}
+
+		Ok(amounts)
+	}
+
+	/// Calculates the amounts of every hop for receiving exactly `amount_out` of the last asset in the path.
+	/// The first element is the amount the user pays, the last one is `amount_out`.
+	pub fn get_amounts_in(
+		path: &[BalanceType<T>],
+		fees: &[Permill],
+		amount_out: AssetBalanceOf<T>,
+	) -> Result<Vec<AssetBalanceOf<T>>, DispatchError> {
+		Self::validate_path(path, fees)?;
+
+		let mut amounts = Vec::with_capacity(path.len());
+		amounts.push(amount_out);
+		for (hop, fee) in path.windows(2).zip(fees).rev() {
+			let (pool, reserve_in, reserve_out) = Self::get_reserves(hop[0].clone(), hop[1].clone(), *fee)?;
+			let amount_in = Self::get_amount_in(amounts[amounts.len() - 1], reserve_in, reserve_out, pool.fee, &pool.curve)?;
+			amounts.push(amount_in);
+		}
+		amounts.reverse();
+
+		Ok(amounts)
+	}
+
+	/// Executes the hops of a swap path with the amounts calculated by `get_amounts_out` or `get_amounts_in`,
+	/// `exact_in` tells which one. The output of every hop is transferred straight into the pool of the next hop.
+	fn swap_along_path(
+		who: &T::AccountId,
+		path: &[BalanceType<T>],
+		fees: &[Permill],
+		amounts: &[AssetBalanceOf<T>],
+		exact_in: bool,
+	) -> DispatchResult {
+		let pools = path
+			.windows(2)
+			.zip(fees)
+			.map(|(hop, fee)| {
+				Self::ensure_not_in_batch_mode(&hop[0], &hop[1], *fee)?;
+				Self::ensure_swaps_not_paused(&hop[0], &hop[1], *fee)?;
+				Self::get_reserves(hop[0].clone(), hop[1].clone(), *fee)
+			})
+			.collect::<Result<Vec<_>, DispatchError>>()?;
+
+		// transfer from balance into the first pool
+		Self::transfer_asset(&path[0], who, &pools[0].0.pool_account, amounts[0])?;
+
+		for (i, (pool, reserve_in, reserve_out)) in pools.iter().enumerate() {
+			// the last pool pays out to the user, all the others to the next pool in the path
+			let dest = pools.get(i + 1).map(|(next_pool, _, _)| &next_pool.pool_account).unwrap_or(who);
+			Self::transfer_asset(&path[i + 1], &pool.pool_account, dest, amounts[i + 1])?;
+
+			// update the tracked reserves of the hop
+			let new_reserve_in = reserve_in.checked_add(&amounts[i]).ok_or(ArithmeticError::Overflow)?;
+			let new_reserve_out = reserve_out.checked_sub(&amounts[i + 1]).ok_or(ArithmeticError::Underflow)?;
+			Self::set_reserves(path[i].clone(), path[i + 1].clone(), fees[i], new_reserve_in, new_reserve_out)?;
+
+			// every hop is a swap of its own for indexers
+			Self::deposit_event(Event::<T>::Swapped {
+				who: who.clone(),
+				pool_id: pool.liquidity_id.clone(),
+				asset_in: path[i].clone(),
+				asset_out: path[i + 1].clone(),
+				amount_in: amounts[i],
+				amount_out: amounts[i + 1],
+				fee: Self::swap_fee_amount(pool, amounts[i], amounts[i + 1], *reserve_in, *reserve_out, exact_in),
+				reserve_in_after: new_reserve_in,
+				reserve_out_after: new_reserve_out,
+			});
+		}
+
+		Ok(())
+	}
+
 	/// Functionality that can be used as a price oracle, enables users to convert the price of the
 	/// from one asset to another.
 	pub fn get_price (
-		asset_in: AssetIdOf<T>,
-		asset_out: AssetIdOf<T>,
+		asset_in: BalanceType<T>,
+		asset_out: BalanceType<T>,
+		fee: Permill,
 		amount_in: AssetBalanceOf<T>,
 	) -> Result<AssetBalanceOf<T>, DispatchError> {
 
-		//  Order the asset ids, orders the amounts as well
-		let (asset_a, asset_b, _, _) = Self::order_asset_ids(asset_in.clone(), asset_out.clone(), AssetBalanceOf::<T>::zero(), AssetBalanceOf::<T>::zero())?;
+		// get the tracked reserves, this fails if the pool doesn't exist
+		let (pool, pool_balance_in, pool_balance_out) = Self::get_reserves(asset_in, asset_out, fee)?;
 
-		// check if the pool exists
-		ensure!(LiquidityPoolMap::<T>::contains_key(&(asset_a.clone(), asset_b.clone())), Error::<T>::PoolDoesNotExist);
-		
-		// Get the pool account
-		let pool_account = Self::generate_account_from_asset_id_pair(asset_a, asset_b);
-		
-		// get pool balances
-		let pool_balance_in = T::Fungibles::balance(asset_in.clone(), &pool_account);
-		let pool_balance_out = T::Fungibles::balance(asset_out.clone(), &pool_account);
+		// a stable pool trades close to the peg even when its reserves aren't balanced, so its marginal price is used
+		if let PoolCurve::StableSwap { .. } = pool.curve {
+			let (price, _) = pool.curve.spot_prices(pool_balance_in.unique_saturated_into(), pool_balance_out.unique_saturated_into());
+			let amount_out = price.saturating_mul_int(amount_in.unique_saturated_into());
+			return Ok(AssetBalanceOf::<T>::try_from(amount_out).map_err(|_| ArithmeticError::Overflow)?)
+		}
 
 		let part1 = amount_in.checked_mul(&pool_balance_out).ok_or(ArithmeticError::Overflow)?;
 		let amount_out = part1.checked_div(&pool_balance_in).ok_or(ArithmeticError::Overflow)?;
@@ -674,6 +4484,136 @@ impl<T: Config> Pallet<T> {
 		Ok(amount_out)
 	}
 
+	/// Quotes the amount of `asset_out` received for swapping exactly `amount_in` of `asset_in`,
+	/// with the fee of the pool taken into account. Returns `None` if the swap isn't possible.
+	pub fn quote_exact_in(
+		asset_in: BalanceType<T>,
+		asset_out: BalanceType<T>,
+		fee: Permill,
+		amount_in: AssetBalanceOf<T>,
+	) -> Option<AssetBalanceOf<T>> {
+		let (pool, reserve_in, reserve_out) = Self::get_reserves(asset_in, asset_out, fee).ok()?;
+		Self::get_amount_out(amount_in, reserve_in, reserve_out, pool.fee, &pool.curve).ok()
+	}
+
+	/// Quotes the amount of `asset_in` needed to receive exactly `amount_out` of `asset_out`,
+	/// with the fee of the pool taken into account. Returns `None` if the swap isn't possible.
+	pub fn quote_exact_out(
+		asset_in: BalanceType<T>,
+		asset_out: BalanceType<T>,
+		fee: Permill,
+		amount_out: AssetBalanceOf<T>,
+	) -> Option<AssetBalanceOf<T>> {
+		let (pool, reserve_in, reserve_out) = Self::get_reserves(asset_in, asset_out, fee).ok()?;
+		Self::get_amount_in(amount_out, reserve_in, reserve_out, pool.fee, &pool.curve).ok()
+	}
+
+	/// Returns the tracked reserves of the pool of the pair with the fee tier, in the order the assets are given.
+	pub fn pool_reserves(
+		asset_a: BalanceType<T>,
+		asset_b: BalanceType<T>,
+		fee: Permill,
+	) -> Option<(AssetBalanceOf<T>, AssetBalanceOf<T>)> {
+		let (_, reserve_a, reserve_b) = Self::get_reserves(asset_a, asset_b, fee).ok()?;
+		Some((reserve_a, reserve_b))
+	}
+
+	/// Returns the id of the LP token of the pool of the pair with the fee tier, if the pool exists.
+	pub fn lp_token_id(asset_a: BalanceType<T>, asset_b: BalanceType<T>, fee: Permill) -> Option<AssetIdOf<T>> {
+		let (pool, _, _) = Self::get_reserves(asset_a, asset_b, fee).ok()?;
+		Some(pool.liquidity_id)
+	}
+
+	/// Quotes the amount of `asset_out` received for swapping exactly `amount_in` of `asset_in` in the weighted
+	/// pool, with the fee of the pool taken into account. Returns `None` if the swap isn't possible.
+	pub fn quote_weighted_exact_in(
+		pool_id: WeightedPoolId,
+		asset_in: BalanceType<T>,
+		asset_out: BalanceType<T>,
+		amount_in: AssetBalanceOf<T>,
+	) -> Option<AssetBalanceOf<T>> {
+		let pool = WeightedPools::<T>::get(pool_id)?;
+		let index_in = Self::weighted_asset_index(&pool, &asset_in).ok()?;
+		let index_out = Self::weighted_asset_index(&pool, &asset_out).ok()?;
+		if index_in == index_out {
+			return None
+		}
+		Self::weighted_amount_out(&pool, index_in, index_out, amount_in).ok()
+	}
+
+	/// Returns the ordered assets, the fee tier and the LP token id of every pool.
+	pub fn list_pools() -> Vec<(BalanceType<T>, BalanceType<T>, Permill, AssetIdOf<T>)> {
+		LiquidityPoolMap::<T>::iter()
+			.map(|((asset_a, asset_b, fee), pool)| (asset_a, asset_b, fee, pool.liquidity_id))
+			.collect()
+	}
+
+	/// Time-weighted average price oracle: returns the average price of `asset_in` in `asset_out` over
+	/// the last `window` blocks. Unlike `get_price`, it can't be moved by trading inside a single block.
+	pub fn get_twap(
+		asset_in: BalanceType<T>,
+		asset_out: BalanceType<T>,
+		fee: Permill,
+		window: BlockNumberFor<T>,
+	) -> Result<FixedU128, DispatchError> {
+		ensure!(!window.is_zero(), Error::<T>::InvalidTwapWindow);
+
+		//  Order the asset ids
+		let (asset_a, asset_b, _, _) = Self::order_asset_ids(asset_in.clone(), asset_out, AssetBalanceOf::<T>::zero(), AssetBalanceOf::<T>::zero())?;
+		let pool_key = (asset_a.clone(), asset_b, fee);
+
+		// check if the pool exists
+		let pool = LiquidityPoolMap::<T>::get(&pool_key).ok_or(Error::<T>::PoolDoesNotExist)?;
+
+		let now = frame_system::Pallet::<T>::block_number();
+		let start = now.checked_sub(&window).ok_or(Error::<T>::InsufficientPriceHistory)?;
+
+		// find the newest observation at or before the start of the window
+		let observations = PriceObservations::<T>::get(&pool_key);
+		let index = observations
+			.iter()
+			.rposition(|observation| observation.block <= start)
+			.ok_or(Error::<T>::InsufficientPriceHistory)?;
+		let before = observations[index];
+
+		// The price is constant between two observations, so the cumulative prices at the start of the
+		// window can be interpolated. After the last observation the current reserves apply.
+		let (start_a_cumulative, start_b_cumulative) = match observations.get(index + 1) {
+			Some(after) => {
+				let elapsed: u128 = (start - before.block).unique_saturated_into();
+				let between: u128 = (after.block - before.block).unique_saturated_into();
+				let interpolate = |before: FixedU128, after: FixedU128| {
+					let delta = after.into_inner().wrapping_sub(before.into_inner());
+					let delta_to_start = multiply_by_rational_with_rounding(delta, elapsed, between, Rounding::Down)
+						.unwrap_or_default();
+					before.into_inner().wrapping_add(delta_to_start)
+				};
+				(
+					interpolate(before.price_a_cumulative, after.price_a_cumulative),
+					interpolate(before.price_b_cumulative, after.price_b_cumulative),
+				)
+			},
+			None => {
+				let elapsed: u128 = (start - before.block).unique_saturated_into();
+				let (price_a, price_b) = Self::spot_prices(&pool);
+				(
+					before.price_a_cumulative.into_inner().wrapping_add(price_a.wrapping_mul(elapsed)),
+					before.price_b_cumulative.into_inner().wrapping_add(price_b.wrapping_mul(elapsed)),
+				)
+			},
+		};
+
+		let (now_a_cumulative, now_b_cumulative) = Self::cumulative_prices_at(&pool, now);
+		let (now_cumulative, start_cumulative) = if asset_in == asset_a {
+			(now_a_cumulative, start_a_cumulative)
+		} else {
+			(now_b_cumulative, start_b_cumulative)
+		};
+
+		let blocks: u128 = window.unique_saturated_into();
+		Ok(FixedU128::from_inner(now_cumulative.wrapping_sub(start_cumulative) / blocks))
+	}
+
 	/// this function allows you to set up an account where you can provide the native balance amount and
 	/// a vector of assets and their balances. This function will create the assets if they dont exist.
 	pub fn setup_account(
@@ -685,22 +4625,96 @@ impl<T: Config> Pallet<T> {
 
 		// iterate over the assets and mint them into the account
 		for (asset_id, asset_balance) in assets {
-			// create the asset if it doesnt exist
-			if !T::Fungibles::asset_exists(asset_id.clone()) {
-				let pallet_account = Self::account_id();
-				// is_sufficient is set to true because we don't want users to have an existential deposit to hold this asset
-				T::Fungibles::create(asset_id.clone(), pallet_account, true, One::one())?;
-			}
+			Self::do_mint_asset(&who, asset_id, asset_balance)?;
+		}
+
+		Ok(())
+	}
 
-			T::Fungibles::mint_into(asset_id.clone(), &who, asset_balance)?;
+	/// Mints `amount` of the asset `asset_id` into the account of `who`, the asset is created if it doesn't exist.
+	pub fn do_mint_asset(who: &T::AccountId, asset_id: AssetIdOf<T>, amount: AssetBalanceOf<T>) -> DispatchResult {
+		// create the asset if it doesnt exist
+		if !T::Fungibles::asset_exists(asset_id.clone()) {
+			let pallet_account = Self::account_id();
+			// is_sufficient is set to true because we don't want users to have an existential deposit to hold this asset
+			T::Fungibles::create(asset_id.clone(), pallet_account, true, One::one())?;
 		}
 
+		T::Fungibles::mint_into(asset_id, who, amount)?;
+
 		Ok(())
 	}
 
 	pub fn get_sqrt_of_asset_balance(balance: AssetBalanceOf<T>) -> AssetBalanceOf<T> {
 		IntegerSquareRoot::integer_sqrt(&balance)
 	}
+
+	/// Checks the invariants of the pools:
+	/// * the reserves of every pool are above zero and held by the pool account, a pool is removed with its
+	///   last LP token
+	/// * the LP token of every pool has been issued and belongs to this pool only
+	/// * the LP issuance of a constant product pool is at most `sqrt(k)`, which only grows with fees
+	/// * the escrow account holds the amounts of the open limit orders, swap intents and unclaimed refunds
+	#[cfg(any(feature = "try-runtime", test))]
+	pub fn do_try_state() -> Result<(), sp_runtime::TryRuntimeError> {
+		let mut lp_tokens = Vec::new();
+
+		for ((asset_a, asset_b, _), pool) in LiquidityPoolMap::<T>::iter() {
+			ensure!(!pool.reserve_a.is_zero() && !pool.reserve_b.is_zero(), "a pool has an empty reserve");
+			ensure!(
+				Self::asset_balance(&asset_a, &pool.pool_account) >= pool.reserve_a &&
+					Self::asset_balance(&asset_b, &pool.pool_account) >= pool.reserve_b,
+				"a pool account holds less than the reserves of its pool"
+			);
+
+			let supply = T::Fungibles::total_issuance(pool.liquidity_id.clone());
+			ensure!(!supply.is_zero(), "the LP token of a pool has no issuance");
+			if pool.curve == PoolCurve::ConstantProduct {
+				let root_k = Self::get_sqrt_of_asset_balance(Self::curve_k(&pool.curve, pool.reserve_a, pool.reserve_b)?);
+				ensure!(supply <= root_k, "the LP issuance of a pool is above the square root of its k");
+			}
+
+			ensure!(!lp_tokens.contains(&pool.liquidity_id), "two pools share an LP token");
+			lp_tokens.push(pool.liquidity_id);
+		}
+
+		for (_, pool) in WeightedPools::<T>::iter() {
+			for WeightedAsset { asset, reserve, .. } in pool.assets.iter() {
+				ensure!(!reserve.is_zero(), "a weighted pool has an empty reserve");
+				ensure!(
+					Self::asset_balance(asset, &pool.pool_account) >= *reserve,
+					"a weighted pool account holds less than the reserves of its pool"
+				);
+			}
+
+			ensure!(
+				!T::Fungibles::total_issuance(pool.liquidity_id.clone()).is_zero(),
+				"the LP token of a weighted pool has no issuance"
+			);
+			ensure!(!lp_tokens.contains(&pool.liquidity_id), "two pools share an LP token");
+			lp_tokens.push(pool.liquidity_id);
+		}
+
+		let mut held: Vec<(BalanceType<T>, AssetBalanceOf<T>)> = Vec::new();
+		let orders = LimitOrders::<T>::iter().map(|(_, order)| (order.asset_in, order.amount_in));
+		let intents = SwapIntents::<T>::iter_values().flatten().map(|intent| (intent.asset_in, intent.amount_in));
+		let unclaimed = UnclaimedRefunds::<T>::iter().map(|(_, asset_in, amount_in)| (asset_in, amount_in));
+		for (asset_in, amount_in) in orders.chain(intents).chain(unclaimed) {
+			match held.iter_mut().find(|(asset, _)| *asset == asset_in) {
+				Some((_, amount)) => *amount = amount.saturating_add(amount_in),
+				None => held.push((asset_in, amount_in)),
+			}
+		}
+		let escrow_account = Self::escrow_account();
+		for (asset, amount) in held {
+			ensure!(
+				Self::asset_balance(&asset, &escrow_account) >= amount,
+				"the escrow account holds less than the open limit orders, swap intents and unclaimed refunds"
+			);
+		}
+
+		Ok(())
+	}
 }
 
 // Look at `../interface/` to better understand this API.
@@ -709,56 +4723,111 @@ impl<T: Config> pba_interface::DexInterface for Pallet<T> {
 	type AssetId = <T::Fungibles as fungibles::Inspect<Self::AccountId>>::AssetId;
 	type AssetBalance = <T::Fungibles as fungibles::Inspect<Self::AccountId>>::Balance;
 
-	fn setup_account(_who: Self::AccountId) -> DispatchResult {
-		unimplemented!()
+	fn setup_account(who: Self::AccountId) -> DispatchResult {
+		// The account gets its existential deposit plus the native currency needed to open a pool, including the
+		// existential deposit of the pallet account if it is the first pool
+		let native_balance = T::NativeBalance::minimum_balance()
+			.saturating_add(T::NativeBalance::minimum_balance())
+			.saturating_add(BalanceOf::<T>::from(POOL_ACCOUNT_DEPOSIT))
+			.saturating_add(T::LpMetadataDeposit::get());
+		Self::setup_account(who, native_balance, Vec::new())
 	}
 
 	fn mint_asset(
-		_who: Self::AccountId,
-		_token_id: Self::AssetId,
-		_amount: Self::AssetBalance,
+		who: Self::AccountId,
+		token_id: Self::AssetId,
+		amount: Self::AssetBalance,
 	) -> DispatchResult {
-		unimplemented!()
+		Self::do_mint_asset(&who, token_id, amount)
 	}
 
-	fn asset_balance(_who: Self::AccountId, _token_id: Self::AssetId) -> Self::AssetBalance {
-		unimplemented!()
+	fn asset_balance(who: Self::AccountId, token_id: Self::AssetId) -> Self::AssetBalance {
+		Self::asset_balance(&BalanceType::AssetBalance(token_id), &who)
 	}
 
 	fn swap_fee() -> u16 {
-		T::FeePercentage::get() as u16
+		// Permill has 100 parts per basis point
+		(T::DefaultFeeTier::get().deconstruct() / 100) as u16
 	}
-	
-	/// Allows users to withdraw their tokens from a liquidity pool. It take as input 
-	/// amount of asset_a and amount of asset_b that the user wants to recieve
-	/// The function looks if the user has enough of liquidity tokens to remove the wanted amount from 
-	/// the balance if yes, this amount will be then removed
+
+	fn lp_id(asset_a: Self::AssetId, asset_b: Self::AssetId) -> Option<Self::AssetId> {
+		// The LP token of the pool in the default fee tier, there is none before the pool is created
+		let (asset_a, asset_b) = (BalanceType::AssetBalance(asset_a), BalanceType::AssetBalance(asset_b));
+		Self::lp_token_id(asset_a, asset_b, T::DefaultFeeTier::get())
+	}
+
+	/// Adds liquidity to the pool of `asset_a` and `asset_b`, the pool is created if it doesn't exist yet.
+	fn add_liquidity(
+		who: Self::AccountId,
+		asset_a: Self::AssetId,
+		asset_b: Self::AssetId,
+		amount_a: Self::AssetBalance,
+		amount_b: Self::AssetBalance,
+	) -> DispatchResult {
+		let asset_a = BalanceType::AssetBalance(asset_a);
+		let asset_b = BalanceType::AssetBalance(asset_b);
+
+		let fee = T::DefaultFeeTier::get();
+
+		if Self::pool_reserves(asset_a.clone(), asset_b.clone(), fee).is_some() {
+			Self::do_add_liquidity(&who, asset_a, asset_b, fee, amount_a, amount_b, Zero::zero(), Zero::zero())?;
+			Ok(())
+		} else {
+			Self::do_create_pool(&who, asset_a, asset_b, fee, PoolCurve::ConstantProduct, amount_a, amount_b)
+		}
+	}
+
+	/// Allows users to withdraw their tokens from a liquidity pool by burning `token_amount` LP tokens.
+	/// No minimum amounts are enforced, the user receives their share of the pool reserves.
 	fn remove_liquidity_(
-		_who: Self::AccountId,
-		_asset_a: Self::AssetId,
-		_asset_b: Self::AssetId,
-		_token_amount: Self::AssetBalance,
+		who: Self::AccountId,
+		asset_a: Self::AssetId,
+		asset_b: Self::AssetId,
+		token_amount: Self::AssetBalance,
 	) -> DispatchResult {
-		unimplemented!()
+		Self::do_remove_liquidity(
+			&who,
+			BalanceType::AssetBalance(asset_a),
+			BalanceType::AssetBalance(asset_b),
+			T::DefaultFeeTier::get(),
+			Zero::zero(),
+			Zero::zero(),
+			token_amount,
+		)
+		.map(|_| ())
 	}
 
 	fn swap_exact_in_for_out(
-		_who: Self::AccountId,
-		_asset_in: Self::AssetId,
-		_asset_out: Self::AssetId,
-		_exact_in: Self::AssetBalance,
-		_min_out: Self::AssetBalance,
+		who: Self::AccountId,
+		asset_in: Self::AssetId,
+		asset_out: Self::AssetId,
+		exact_in: Self::AssetBalance,
+		min_out: Self::AssetBalance,
 	) -> DispatchResult {
-		unimplemented!()
+		Self::do_swap_exact_in_for_out(
+			&who,
+			BalanceType::AssetBalance(asset_in),
+			BalanceType::AssetBalance(asset_out),
+			T::DefaultFeeTier::get(),
+			exact_in,
+			min_out,
+		)
 	}
 
 	fn swap_in_for_exact_out(
-		_origin: Self::AccountId,
-		_asset_in: Self::AssetId,
-		_asset_out: Self::AssetId,
-		_max_in: Self::AssetBalance,
-		_exact_out: Self::AssetBalance,
+		origin: Self::AccountId,
+		asset_in: Self::AssetId,
+		asset_out: Self::AssetId,
+		max_in: Self::AssetBalance,
+		exact_out: Self::AssetBalance,
 	) -> DispatchResult {
-		unimplemented!()
+		Self::do_swap_in_for_exact_out(
+			&origin,
+			BalanceType::AssetBalance(asset_in),
+			BalanceType::AssetBalance(asset_out),
+			T::DefaultFeeTier::get(),
+			max_in,
+			exact_out,
+		)
 	}
 }
\ No newline at end of file
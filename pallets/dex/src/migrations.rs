@@ -0,0 +1,193 @@
+//! Storage migrations of the dex pallet.
+//!
+//! The migrations are added to the `Executive` of the runtime and run in `on_runtime_upgrade` before any
+//! block of the new runtime is executed. Each one migrates from the storage version before it and puts its
+//! own version, it does nothing on a chain at any other version, so they are added in order and can stay
+//! in the runtime for as long as chains may need them.
+
+use crate::*;
+use frame_support::traits::{
+	fungible::{Inspect as FInspect, Mutate},
+	fungibles::{Inspect, Mutate as FSMutate},
+	tokens::{Fortitude, Preservation},
+	OnRuntimeUpgrade,
+};
+use frame_support::dispatch::Vec;
+
+#[cfg(feature = "try-runtime")]
+use sp_runtime::TryRuntimeError;
+
+/// The storage of the pallet before it had a storage version.
+pub(crate) mod v0 {
+	use super::*;
+
+	/// The sorted pair of assets of every pool to the account holding its assets. The reserves were read from
+	/// the balances of the account and the LP token id was decoded from a hash of the pair.
+	#[frame_support::storage_alias]
+	pub(crate) type LiquidityPoolMap<T: Config> =
+		StorageMap<Pallet<T>, Blake2_128Concat, (AssetIdOf<T>, AssetIdOf<T>), <T as frame_system::Config>::AccountId>;
+
+	/// The account of a pool, decoded from a hash of its sorted pair of assets.
+	#[cfg(test)]
+	pub(crate) fn pool_account<T: Config>(asset_a: &AssetIdOf<T>, asset_b: &AssetIdOf<T>) -> Option<T::AccountId> {
+		let bytes = T::Hashing::hash(&(asset_a, asset_b).encode());
+		T::AccountId::decode(&mut TrailingZeroInput::new(&bytes.encode())).ok()
+	}
+
+	/// The id of the LP token of a pool, decoded from a hash of its sorted pair of assets.
+	pub(crate) fn liquidity_id<T: Config>(asset_a: &AssetIdOf<T>, asset_b: &AssetIdOf<T>) -> Option<AssetIdOf<T>> {
+		let bytes = T::Hashing::hash(&(asset_a, asset_b).encode());
+		AssetIdOf::<T>::decode(&mut TrailingZeroInput::new(&bytes.encode())).ok()
+	}
+}
+
+/// Storage version 1: every pool is a [`LiquidityPool`] record.
+pub mod v1 {
+	use super::*;
+
+	/// Moves every pool from the bare pair → account map to a [`LiquidityPool`] record under
+	/// `(AssetBalance(a), AssetBalance(b), DefaultFeeTier)`. The reserves are taken from the balances of the
+	/// pool account.
+	///
+	/// The creator of a legacy pool isn't known, so the pallet account is recorded as its creator. Legacy
+	/// pools didn't lock any `MinimumLiquidity` and the migration doesn't lock any either, so every LP token
+	/// of a legacy pool stays redeemable and its total issuance can go down to zero. `remove_liquidity` removes
+	/// the pool with its last LP token.
+	pub struct MigrateToPoolRecords<T>(PhantomData<T>);
+
+	impl<T: Config> OnRuntimeUpgrade for MigrateToPoolRecords<T> {
+		fn on_runtime_upgrade() -> Weight {
+			if Pallet::<T>::on_chain_storage_version() != 0 {
+				return T::DbWeight::get().reads(1)
+			}
+
+			// The pools are collected first, their new keys live under the same prefix
+			let legacy_pools = v0::LiquidityPoolMap::<T>::drain().collect::<Vec<_>>();
+			let count = legacy_pools.len() as u64;
+			let now = frame_system::Pallet::<T>::block_number();
+			let creator = Pallet::<T>::account_id();
+
+			for ((asset_a, asset_b), pool_account) in legacy_pools {
+				let Some(liquidity_id) = v0::liquidity_id::<T>(&asset_a, &asset_b) else {
+					frame_support::defensive!("the LP token id of a pool couldn't be derived");
+					continue
+				};
+
+				let pool = LiquidityPool {
+					reserve_a: T::Fungibles::balance(asset_a.clone(), &pool_account),
+					reserve_b: T::Fungibles::balance(asset_b.clone(), &pool_account),
+					pool_account,
+					liquidity_id,
+					creator: creator.clone(),
+					created_at: now,
+					fee: T::DefaultFeeTier::get(),
+					curve: PoolCurve::ConstantProduct,
+					price_a_cumulative: FixedU128::zero(),
+					price_b_cumulative: FixedU128::zero(),
+					last_oracle_update: now,
+					k_last: Zero::zero(),
+				};
+
+				// Both assets get the same tag in their encoding, so the pair stays sorted
+				let pool_key = (BalanceType::AssetBalance(asset_a), BalanceType::AssetBalance(asset_b), pool.fee);
+				LiquidityPoolMap::<T>::insert(pool_key, pool);
+			}
+
+			StorageVersion::new(1).put::<Pallet<T>>();
+
+			// Every pool is read, removed and written, and the balances of its account are read
+			T::DbWeight::get().reads_writes(count.saturating_mul(3).saturating_add(1), count.saturating_mul(2).saturating_add(1))
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<Vec<u8>, TryRuntimeError> {
+			Ok((v0::LiquidityPoolMap::<T>::iter().count() as u32).encode())
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(state: Vec<u8>) -> Result<(), TryRuntimeError> {
+			let count = u32::decode(&mut &state[..]).map_err(|_| "the pool count couldn't be decoded")?;
+			ensure!(Pallet::<T>::on_chain_storage_version() >= 1, "the storage version wasn't bumped to 1");
+			ensure!(LiquidityPoolMap::<T>::iter().count() as u32 >= count, "a pool was lost in the migration");
+			Ok(())
+		}
+	}
+}
+
+/// Storage version 2: pool accounts are sub-accounts of the pallet id.
+pub mod v2 {
+	use super::*;
+
+	/// Moves every pool from the account decoded from a raw hash of its key to its sub-account of the pallet
+	/// id, together with the balances of the account and the minimum liquidity locked in it. Only the pools of
+	/// `LiquidityPoolMap` are moved, the weighted and concentrated liquidity pools have been created at their
+	/// sub-accounts from the start.
+	pub struct MigrateToSubAccounts<T>(PhantomData<T>);
+
+	impl<T: Config> OnRuntimeUpgrade for MigrateToSubAccounts<T> {
+		fn on_runtime_upgrade() -> Weight {
+			if Pallet::<T>::on_chain_storage_version() != 1 {
+				return T::DbWeight::get().reads(1)
+			}
+
+			let (mut pools, mut moved) = (0u64, 0u64);
+
+			LiquidityPoolMap::<T>::translate::<LiquidityPool<T>, _>(|(asset_a, asset_b, fee), mut pool| {
+				pools += 1;
+				let account = Pallet::<T>::generate_account_from_asset_id_pair(asset_a.clone(), asset_b.clone(), fee);
+				if pool.pool_account != account {
+					let assets = [asset_a, asset_b, BalanceType::AssetBalance(pool.liquidity_id.clone())];
+					move_pool_account::<T>(&pool.pool_account, &account, &assets);
+					pool.pool_account = account;
+					moved += 1;
+				}
+				Some(pool)
+			});
+
+			StorageVersion::new(2).put::<Pallet<T>>();
+
+			// Every pool is read and written. A moved pool transfers the native currency twice and both of its
+			// assets and its LP token once, every transfer touches the accounts of both sides
+			let transfers = moved.saturating_mul(5);
+			let accesses = pools.saturating_add(transfers * 2).saturating_add(1);
+			T::DbWeight::get().reads_writes(accesses, accesses)
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(_state: Vec<u8>) -> Result<(), TryRuntimeError> {
+			ensure!(Pallet::<T>::on_chain_storage_version() >= 2, "the storage version wasn't bumped to 2");
+			for ((asset_a, asset_b, fee), pool) in LiquidityPoolMap::<T>::iter() {
+				let account = Pallet::<T>::generate_account_from_asset_id_pair(asset_a, asset_b, fee);
+				ensure!(pool.pool_account == account, "a pool is not at its sub-account");
+			}
+			Ok(())
+		}
+	}
+
+	/// Moves the native currency and the `assets` held by the account of a pool to its new account, including
+	/// the LP tokens locked in it.
+	///
+	/// The new account receives the native currency first, so it exists before it receives assets that
+	/// aren't sufficient. The rest of the native currency follows once the old account holds no assets
+	/// anymore. A failed transfer leaves its balance behind instead of failing the upgrade.
+	fn move_pool_account<T: Config>(from: &T::AccountId, to: &T::AccountId, assets: &[BalanceType<T>]) {
+		let native = T::NativeBalance::reducible_balance(from, Preservation::Preserve, Fortitude::Polite);
+		if T::NativeBalance::transfer(from, to, native, Preservation::Preserve).is_err() {
+			frame_support::defensive!("the native currency of a pool couldn't be moved to its sub-account");
+		}
+
+		for asset in assets {
+			if let BalanceType::AssetBalance(asset_id) = asset {
+				let amount = T::Fungibles::balance(asset_id.clone(), from);
+				if !amount.is_zero() && T::Fungibles::transfer(asset_id.clone(), from, to, amount, Preservation::Expendable).is_err() {
+					frame_support::defensive!("an asset of a pool couldn't be moved to its sub-account");
+				}
+			}
+		}
+
+		let rest = T::NativeBalance::reducible_balance(from, Preservation::Expendable, Fortitude::Polite);
+		if T::NativeBalance::transfer(from, to, rest, Preservation::Expendable).is_err() {
+			frame_support::defensive!("the native currency of a pool couldn't be moved to its sub-account");
+		}
+	}
+}
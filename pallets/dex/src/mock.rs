@@ -1,18 +1,18 @@
 use crate as pallet_dex;
 use frame_support::{
 	parameter_types,
-	traits::{AsEnsureOriginWithArg, ConstU128, ConstU16, ConstU32, ConstU64},
+	traits::{ConstU128, ConstU16, ConstU32, ConstU64},
 };
 use frame_system::{EnsureRoot, EnsureSigned};
 use sp_core::H256;
 use sp_runtime::{
 	traits::{BlakeTwo256, IdentityLookup},
-	BuildStorage,
+	BuildStorage, DispatchResult, Percent, Permill,
 };
 
 type Block = frame_system::mocking::MockBlock<Test>;
 type Balance = u128;
-type AccountId = u64;
+type AccountId = u128;
 
 use frame_support::PalletId;
 
@@ -75,7 +75,7 @@ impl pallet_assets::Config for Test {
 	type AssetId = u32;
 	type AssetIdParameter = codec::Compact<u32>;
 	type Currency = Balances;
-	type CreateOrigin = AsEnsureOriginWithArg<EnsureSigned<Self::AccountId>>;
+	type CreateOrigin = pallet_dex::EnsureBelowLpAssetIds<Test, EnsureSigned<Self::AccountId>>;
 	//type CreateOrigin = AsEnsureOriginWithArg<EnsureSignedBy<AssetConversionOrigin, AccountId>>;
 
 	type ForceOrigin = EnsureRoot<Self::AccountId>;
@@ -96,16 +96,56 @@ impl pallet_assets::Config for Test {
 
 parameter_types! {
 	pub const DexPalletId: PalletId = PalletId(*b"pba/dex0");
+	pub const DexDefaultFeeTier: Permill = Permill::from_percent(5);
+	pub static FlashSwapPremium: Percent = Percent::from_percent(6);
+	pub static FlashSwapTradesWithPool: bool = false;
+}
+
+// Pays back the borrowed amounts plus `FlashSwapPremium` of them, and swaps with the pool
+// first if `FlashSwapTradesWithPool` is set.
+pub struct RepayFlashSwap;
+
+impl pallet_dex::FlashSwapHandler<AccountId, pallet_dex::BalanceType<Test>, Balance> for RepayFlashSwap {
+	fn on_flash_swap(
+		who: &AccountId,
+		pool_account: &AccountId,
+		asset_a: &pallet_dex::BalanceType<Test>,
+		asset_b: &pallet_dex::BalanceType<Test>,
+		amount_a_out: Balance,
+		amount_b_out: Balance,
+	) -> DispatchResult {
+		if FlashSwapTradesWithPool::get() {
+			Dex::do_swap_exact_in_for_out(who, asset_a.clone(), asset_b.clone(), DexDefaultFeeTier::get(), 100, 0)?;
+		}
+		let premium = FlashSwapPremium::get();
+		Dex::transfer_asset(asset_a, who, pool_account, amount_a_out + premium * amount_a_out)?;
+		Dex::transfer_asset(asset_b, who, pool_account, amount_b_out + premium * amount_b_out)
+	}
 }
 
 impl pallet_dex::Config for Test {
 	type RuntimeEvent = RuntimeEvent;
 	type NativeBalance = Balances;
 	type Fungibles = Assets;
-	type MinPoolDeposit = ConstU32<1_000>;
-	type FeePercentage = ConstU32<5>;
+	type MinPoolDeposit = ConstU32<100>;
+	type MinimumLiquidity = ConstU128<10>;
+	type LpAssetIdStart = ConstU32<1_000>;
+	type LpMetadataDeposit = ConstU128<100>;
+	type DefaultFeeTier = DexDefaultFeeTier;
+	type MaxFeeTiers = ConstU32<8>;
+	type MaxAmplification = ConstU32<10_000>;
+	type MaxWeightedPoolAssets = ConstU32<8>;
+	type MaxConcentratedTicks = ConstU32<100>;
+	type MaxSwapPathLength = ConstU32<4>;
+	type MaxPriceObservations = ConstU32<100>;
+	type MaxLimitOrders = ConstU32<16>;
+	type MaxSwapIntents = ConstU32<16>;
 	type CreatePoolOrigin = frame_system::EnsureSigned<AccountId>;
+	type GovernanceOrigin = EnsureRoot<AccountId>;
+	type PauseOrigin = EnsureRoot<AccountId>;
+	type FlashSwapHandler = RepayFlashSwap;
 	type PalletId = DexPalletId;
+	type WeightInfo = ();
 }
 
 // Build genesis storage according to the mock runtime.
@@ -1,5 +1,9 @@
 use crate::{mock::*, Error, Event, *};
-use frame_support::{assert_noop, assert_ok, traits::fungible::Inspect};
+use frame_support::{
+	assert_noop, assert_ok,
+	traits::{fungible::Inspect, Hooks},
+	weights::Weight,
+};
 
 use frame_support::traits::fungibles::Inspect as FSInspect;
 //use sp_runtime::traits::BadOrigin;
@@ -7,10 +11,19 @@ use frame_support::traits::fungibles::Inspect as FSInspect;
 type Balance = <Test as crate::Config>::NativeBalance;
 type Assets = <Test as crate::Config>::Fungibles;
 
-fn create_multiple_accounts() -> Vec<u64> {
+const NATIVE: BalanceType<Test> = BalanceType::NativeBalance;
+
+// The default fee tier of the mock runtime
+const FEE: Permill = Permill::from_percent(5);
+
+fn asset(id: u32) -> BalanceType<Test> {
+	BalanceType::AssetBalance(id)
+}
+
+fn create_multiple_accounts() -> Vec<u128> {
 	let mut accounts = Vec::new();
 	for i in 0..10 {
-		let account_id = i as u64 + 1;
+		let account_id = i as u128 + 1;
 		assert_ok!(Dex::setup_account(account_id, 1_000_000, vec![(1, 100), (2, 200), (3, 300)]));
 		accounts.push(account_id);
 	}
@@ -49,12 +62,12 @@ fn create_pool() {
 		assert_ok!(Dex::setup_account(1, 1_000_000, vec![(1, 1_000), (2, 1_000), (3, 1_000)]));
 
 		// checks if it can create a pool
-		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), 1, 2, 100, 200));
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 100, 200));
 		assert_eq!(Assets::total_balance(1, &1), 900);
 		assert_eq!(Assets::total_balance(2, &1), 800);
 
 		// pool already exists you should not be able to create another one
-		assert_noop!(Dex::create_pool(RuntimeOrigin::signed(1), 1, 2, 100, 100), Error::<Test>::PoolAlreadyExists);
+		assert_noop!(Dex::create_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 100, 100), Error::<Test>::PoolAlreadyExists);
 	});
 }
 
@@ -64,7 +77,7 @@ fn create_pool_and_add_more_liquidity() {
 		assert_ok!(Dex::setup_account(1, 1_000_000, vec![(1, 1_000), (2, 1_000), (3, 1_000)]));
 
 		// checks if it can create a pool
-		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), 1, 2, 100, 200));
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 100, 200));
 		assert_eq!(Assets::total_balance(1, &1), 900);
 		assert_eq!(Assets::total_balance(2, &1), 800);
 
@@ -72,7 +85,7 @@ fn create_pool_and_add_more_liquidity() {
 		assert_ok!(Dex::setup_account(2, 1_000_000, vec![(1, 1_000), (2, 1_000), (3, 1_000)]));
 
 		// add more liquidity
-		assert_ok!(Dex::add_liquidity(RuntimeOrigin::signed(2), 1, 2, 100, 200));
+		assert_ok!(Dex::add_liquidity(RuntimeOrigin::signed(2), asset(1), asset(2), FEE, 100, 200, 0, 0, None));
 		assert_eq!(Assets::total_balance(1, &2), 900);
 		assert_eq!(Assets::total_balance(2, &2), 800);
 
@@ -85,26 +98,27 @@ fn create_pool_and_remove_some_liquidity() {
 		assert_ok!(Dex::setup_account(1, 1_000_000, vec![(1, 1_000), (2, 1_000), (3, 1_000)]));
 
 		// checks if it can create a pool
-		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), 1, 2, 300, 500));
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 300, 500));
 		assert_eq!(Assets::total_balance(1, &1), 700);
 		assert_eq!(Assets::total_balance(2, &1), 500);
 
 		// Check the balance of liquidity tokens 
-		assert_eq!(Assets::total_balance(Dex::create_liquidity_id(2, 1), &1), 387);
+		let lp_id = Dex::lp_token_id(asset(1), asset(2), FEE).unwrap();
+		assert_eq!(Assets::total_balance(lp_id, &1), 377);
 
 		// setup second account
 		assert_ok!(Dex::setup_account(2, 1_000_000, vec![(1, 1_000), (2, 1_000), (3, 1_000)]));
 
 		// should not work, because the user doesn't have liquidity tokens
-		assert_noop!(Dex::remove_liquidity(RuntimeOrigin::signed(2), 1, 2, 10, 30, 100), Error::<Test>::NotEnoughLiquidityTokens);
+		assert_noop!(Dex::remove_liquidity(RuntimeOrigin::signed(2), asset(1), asset(2), FEE, 10, 30, 100, None), Error::<Test>::NotEnoughLiquidityTokens);
 
 		// remove liquidity
-		assert_ok!(Dex::remove_liquidity(RuntimeOrigin::signed(1), 1, 2, 10, 30, 100));
+		assert_ok!(Dex::remove_liquidity(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 10, 30, 100, None));
 		assert_eq!(Assets::total_balance(1, &1), 777);
 		assert_eq!(Assets::total_balance(2, &1), 629);
 
 		// Check the balance of liquidity tokens 
-		assert_eq!(Assets::total_balance(Dex::create_liquidity_id(2, 1), &1), 287);
+		assert_eq!(Assets::total_balance(lp_id, &1), 277);
 
 	});
 }
@@ -117,32 +131,36 @@ fn create_pool_and_remove_it() {
 		assert_ok!(Dex::setup_account(1, 1_000_000, vec![(1, 1_000), (2, 1_000), (3, 1_000)]));
 
 		// checks if it can create a pool
-		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), 1, 2, 300, 500));
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 300, 500));
 		assert_eq!(Assets::total_balance(1, &1), 700);
 		assert_eq!(Assets::total_balance(2, &1), 500);
 
 		// Check the balance of liquidity tokens 
-		assert_eq!(Assets::total_balance(Dex::create_liquidity_id(2, 1), &1), 387);
+		let lp_id = Dex::lp_token_id(asset(1), asset(2), FEE).unwrap();
+		assert_eq!(Assets::total_balance(lp_id, &1), 377);
 
-		// The native asset is decreased as a result of calling the pool
-		assert_eq!(Balance::balance(&1), 999_000);
+		// The native asset is decreased by the deposits of the pool account and of the LP token metadata, and by
+		// the existential deposit of the pallet account
+		assert_eq!(Balance::balance(&1), 1_000_000 - 1_000 - 100 - 1);
 
 		// setup second account
 		assert_ok!(Dex::setup_account(2, 1_000_000, vec![(1, 1_000), (2, 1_000), (3, 1_000)]));
 
 		// should not work, because the user doesn't have liquidity tokens
-		assert_noop!(Dex::remove_pool(RuntimeOrigin::signed(2), 1, 2), Error::<Test>::NotEnoughLiquidityTokens);
+		assert_noop!(Dex::remove_pool(RuntimeOrigin::signed(2), asset(1), asset(2), FEE), Error::<Test>::NotEnoughLiquidityTokens);
 
 		// remove liquidity
-		assert_ok!(Dex::remove_pool(RuntimeOrigin::signed(1), 1, 2));
+		assert_ok!(Dex::remove_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE));
+		assert!(Dex::liquidity_pool((asset(2), asset(1), FEE)).is_none());
 		assert_eq!(Assets::total_balance(1, &1), 1_000);
 		assert_eq!(Assets::total_balance(2, &1), 1_000);
 
-		// Check the balance of liquidity tokens 
-		assert_eq!(Assets::total_balance(Dex::create_liquidity_id(2, 1), &1), 0);
+		// The LP token has been destroyed
+		assert!(!Assets::asset_exists(lp_id));
+		assert_eq!(Assets::total_balance(lp_id, &1), 0);
 
-		// The native asset is decreased as a result of calling the pool
-		assert_eq!(Balance::balance(&1), 1_000_000);
+		// The deposits are paid back, the existential deposit stays in the pallet account
+		assert_eq!(Balance::balance(&1), 1_000_000 - 1);
 
 	});
 }
@@ -154,12 +172,12 @@ fn create_pool_and_swap() {
 		assert_ok!(Dex::setup_account(1, 1_000_000, vec![(1, 100_000), (2, 100_000), (3, 1_000)]));
 
 		// checks if it can create a pool
-		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), 1, 2, 30_000, 50_000));
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 30_000, 50_000));
 
 		// setup second account
 		assert_ok!(Dex::setup_account(2, 1_000_000, vec![(1, 1_000), (2, 1_000), (3, 1_000)]));
 
-		assert_ok!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(2), 1, 2, 300, 450));
+		assert_ok!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(2), asset(1), asset(2), FEE, 300, 450, None));
 
 		assert_eq!(Assets::total_balance(1, &2), 1000 - 300);
 		assert_eq!(Assets::total_balance(2, &2), 1471);
@@ -172,12 +190,12 @@ fn create_pool_and_swap_exact_out() {
 		assert_ok!(Dex::setup_account(1, 1_000_000, vec![(1, 1_000_000), (2, 1_000_000), (3, 1_000)]));
 
 		// checks if it can create a pool
-		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), 1, 2, 300_000, 500_000));
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 300_000, 500_000));
 
 		// setup second account
 		assert_ok!(Dex::setup_account(2, 1_000_000, vec![(1, 100_000), (2, 100_000), (3, 1_000)]));
 
-		assert_ok!(Dex::swap_in_for_exact_out(RuntimeOrigin::signed(2), 1, 2, 4_300, 5_000));
+		assert_ok!(Dex::swap_in_for_exact_out(RuntimeOrigin::signed(2), asset(1), asset(2), FEE, 4_300, 5_000, None));
 
 		//assert_eq!(Assets::total_balance(1, &2), 100_000 - 3_060);
 		//assert_eq!(Assets::total_balance(2, &2), 100_000 + 5_000);
@@ -190,18 +208,18 @@ fn create_pool_and_swap_uniswap_example() {
 		assert_ok!(Dex::setup_account(1, 1_000_000, vec![(1, 10_000_000), (2, 10000_000), (3, 1_000)]));
 
 		// checks if it can create a pool
-		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), 1, 2, 120_0000, 40_0000));
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 120_0000, 40_0000));
 
 		// setup second account
 		assert_ok!(Dex::setup_account(2, 1_000_000, vec![(1, 10_000_000), (2, 10_000_000), (3, 1_000)]));
 
-		assert_ok!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(2), 1, 2, 3009, 900));
+		assert_ok!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(2), asset(1), asset(2), FEE, 3009, 900, None));
 
 		assert_eq!(Assets::total_balance(1, &2), 10_000_000 - 3009);
 		assert_eq!(Assets::total_balance(2, &2), 10000951);
 
 		// transaction should fail
-		assert_noop!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(2), 1, 2, 3000, 11_000), Error::<Test>::InsufficientBalance);
+		assert_noop!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(2), asset(1), asset(2), FEE, 3000, 11_000, None), Error::<Test>::InsufficientBalance);
 
 	});
 }
@@ -213,21 +231,21 @@ fn check_if_pool_creator_makes_a_profit() {
 		assert_ok!(Dex::setup_account(1, 1_000_000, vec![(1, 100_000_000), (2, 100_000_000), (3, 1_000)]));
 
 		// creates a pool
-		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), 1, 2, 1_000_000, 1_000_000));
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 1_000_000, 1_000_000));
 
 		// setup second account
 		assert_ok!(Dex::setup_account(2, 1_000_000, vec![(1, 10_000_000), (2, 10_000_000), (3, 1_000)]));
 
 		for _i in 0..=100 {
-			assert_ok!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(2), 1, 2, 100, 95));
-			assert_ok!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(2), 2, 1, 100, 95));
+			assert_ok!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(2), asset(1), asset(2), FEE, 100, 95, None));
+			assert_ok!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(2), asset(2), asset(1), FEE, 100, 95, None));
 		}
 		// the transacting person makes a loss
 		assert_eq!(Assets::total_balance(1, &2), 9999596 );
 		assert_eq!(Assets::total_balance(2, &2), 9999500);
 
 		// remove pool
-		assert_ok!(Dex::remove_pool(RuntimeOrigin::signed(1), 1, 2));
+		assert_ok!(Dex::remove_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE));
 		// the person who creates a pool makes a profit
 		assert_eq!(Assets::total_balance(1, &1), 100000404);
 		assert_eq!(Assets::total_balance(2, &1), 100_000_000 + 500);
@@ -242,21 +260,21 @@ fn check_if_pool_creator_makes_a_profit_exact_out() {
 		assert_ok!(Dex::setup_account(1, 10_000_000, vec![(1, 10_000_000), (2, 10_000_000), (3, 1_000)]));
 
 		// creates a pool
-		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), 1, 2, 10_000_000, 10_000_000));
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 10_000_000, 10_000_000));
 
 		// setup second account
 		assert_ok!(Dex::setup_account(2, 1_000_000, vec![(1, 10_000_000), (2, 10_000_000), (3, 1_000)]));
 
 		for _i in 0..=100 {
-			assert_ok!(Dex::swap_in_for_exact_out(RuntimeOrigin::signed(2), 1, 2, 200, 100));
-			assert_ok!(Dex::swap_in_for_exact_out(RuntimeOrigin::signed(2), 2, 1, 200, 100));
+			assert_ok!(Dex::swap_in_for_exact_out(RuntimeOrigin::signed(2), asset(1), asset(2), FEE, 200, 100, None));
+			assert_ok!(Dex::swap_in_for_exact_out(RuntimeOrigin::signed(2), asset(2), asset(1), FEE, 200, 100, None));
 		}
 		// the transacting person makes a loss
 		assert_eq!(Assets::total_balance(1, &2), 9999495);
 		assert_eq!(Assets::total_balance(2, &2), 9999596);
 
 		// remove pool
-		assert_ok!(Dex::remove_pool(RuntimeOrigin::signed(1), 1, 2));
+		assert_ok!(Dex::remove_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE));
 		// the person who creates a pool makes a profit
 		assert_eq!(Assets::total_balance(1, &1), 10_000_000 + 505);
 		assert_eq!(Assets::total_balance(2, &1), 10_000_000 + 404);
@@ -270,16 +288,16 @@ fn test_get_price() {
 		assert_ok!(Dex::setup_account(1, 10_000_000, vec![(1, 10_000_000), (2, 10_000_000), (3, 1_000)]));
 
 		// creates a pool
-		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), 1, 2, 100_000, 200_000));
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 100_000, 200_000));
 
-		assert_eq!(Dex::get_price(1, 2, 200), Ok(400));
+		assert_eq!(Dex::get_price(asset(1), asset(2), FEE, 200), Ok(400));
 	});
 }
 
 #[test]
 fn test_get_price_fail() {
 	new_test_ext().execute_with(|| {
-		assert_noop!(Dex::get_price(1, 2, 200), Error::<Test>::PoolDoesNotExist);
+		assert_noop!(Dex::get_price(asset(1), asset(2), FEE, 200), Error::<Test>::PoolDoesNotExist);
 	});
 }
 
@@ -288,9 +306,1624 @@ fn try_to_create_pool_in_different_orders() {
 	new_test_ext().execute_with(|| {
 		assert_ok!(Dex::setup_account(1, 10_000_000, vec![(1, 10_000_000), (2, 10_000_000), (3, 1_000)]));
 		// creates a pool
-		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), 1, 2, 100_000, 200_000));
-		assert_noop!(Dex::create_pool(RuntimeOrigin::signed(1), 2, 1, 100_000, 200_000), Error::<Test>::PoolAlreadyExists);
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 100_000, 200_000));
+		assert_noop!(Dex::create_pool(RuntimeOrigin::signed(1), asset(2), asset(1), FEE, 100_000, 200_000), Error::<Test>::PoolAlreadyExists);
+
+	});
+}
+
+
+// Creates the pools (1, 3) and (3, 2), so asset 1 can only be swapped for asset 2 through asset 3
+fn create_pools_through_hub_asset() {
+	assert_ok!(Dex::setup_account(1, 1_000_000, vec![(1, 10_000_000), (2, 10_000_000), (3, 10_000_000)]));
+	assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), asset(1), asset(3), FEE, 1_000_000, 1_000_000));
+	assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), asset(3), asset(2), FEE, 1_000_000, 1_000_000));
+
+	assert_ok!(Dex::setup_account(2, 1_000_000, vec![(1, 100_000), (2, 100_000), (3, 100_000)]));
+}
+
+// The fee tiers of `hops` pools using the default fee tier
+fn default_fees(hops: usize) -> BoundedVec<Permill, ConstU32<4>> {
+	vec![FEE; hops].try_into().unwrap()
+}
+
+#[test]
+fn swap_exact_in_for_out_via_path() {
+	new_test_ext().execute_with(|| {
+		create_pools_through_hub_asset();
+
+		// there is no direct pool for the pair
+		assert_noop!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(2), asset(1), asset(2), FEE, 2_000, 1, None), Error::<Test>::PoolDoesNotExist);
+
+		// the final output is below min_out
+		let path: BoundedVec<BalanceType<Test>, ConstU32<4>> = vec![asset(1), asset(3), asset(2)].try_into().unwrap();
+		assert_noop!(Dex::swap_exact_in_for_out_via_path(RuntimeOrigin::signed(2), path.clone(), default_fees(2), 2_000, 1_800, None), Error::<Test>::InsufficientBalance);
+
+		assert_eq!(Dex::get_amounts_out(&path, &default_fees(2), 2_000), Ok(vec![2_000, 1_897, 1_799]));
+		assert_ok!(Dex::swap_exact_in_for_out_via_path(RuntimeOrigin::signed(2), path, default_fees(2), 2_000, 1_799, None));
+
+		assert_eq!(Assets::total_balance(1, &2), 100_000 - 2_000);
+		assert_eq!(Assets::total_balance(2, &2), 100_000 + 1_799);
+		// the hub asset only passes through the pools
+		assert_eq!(Assets::total_balance(3, &2), 100_000);
+	});
+}
+
+#[test]
+fn swap_in_for_exact_out_via_path() {
+	new_test_ext().execute_with(|| {
+		create_pools_through_hub_asset();
+
+		// the required input is above max_in
+		let path: BoundedVec<BalanceType<Test>, ConstU32<4>> = vec![asset(1), asset(3), asset(2)].try_into().unwrap();
+		assert_noop!(Dex::swap_in_for_exact_out_via_path(RuntimeOrigin::signed(2), path.clone(), default_fees(2), 1_104, 1_000, None), Error::<Test>::InsufficientBalance);
+
+		assert_eq!(Dex::get_amounts_in(&path, &default_fees(2), 1_000), Ok(vec![1_105, 1_051, 1_000]));
+		assert_ok!(Dex::swap_in_for_exact_out_via_path(RuntimeOrigin::signed(2), path, default_fees(2), 1_105, 1_000, None));
+
+		assert_eq!(Assets::total_balance(1, &2), 100_000 - 1_105);
+		assert_eq!(Assets::total_balance(2, &2), 100_000 + 1_000);
+		assert_eq!(Assets::total_balance(3, &2), 100_000);
+	});
+}
+
+#[test]
+fn swaps_deposit_an_event_for_every_pool_they_go_through() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		create_pools_through_hub_asset();
+
+		// An exact out swap adds 5% on top of the amount the curve requires, every hop has an event of its own
+		let path: BoundedVec<BalanceType<Test>, ConstU32<4>> = vec![asset(1), asset(3), asset(2)].try_into().unwrap();
+		assert_ok!(Dex::swap_in_for_exact_out_via_path(RuntimeOrigin::signed(2), path, default_fees(2), 1_105, 1_000, None));
+		System::assert_has_event(
+			Event::Swapped {
+				who: 2,
+				pool_id: Dex::lp_token_id(asset(1), asset(3), FEE).unwrap(),
+				asset_in: asset(1),
+				asset_out: asset(3),
+				amount_in: 1_105,
+				amount_out: 1_051,
+				fee: 53,
+				reserve_in_after: 1_000_000 + 1_105,
+				reserve_out_after: 1_000_000 - 1_051,
+			}
+			.into(),
+		);
+		System::assert_last_event(
+			Event::Swapped {
+				who: 2,
+				pool_id: Dex::lp_token_id(asset(3), asset(2), FEE).unwrap(),
+				asset_in: asset(3),
+				asset_out: asset(2),
+				amount_in: 1_051,
+				amount_out: 1_000,
+				fee: 50,
+				reserve_in_after: 1_000_000 + 1_051,
+				reserve_out_after: 1_000_000 - 1_000,
+			}
+			.into(),
+		);
+
+		// An exact in swap takes 5% of the amount in as the fee
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 30_000, 50_000));
+		assert_ok!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(2), asset(1), asset(2), FEE, 300, 450, None));
+		System::assert_last_event(
+			Event::Swapped {
+				who: 2,
+				pool_id: Dex::lp_token_id(asset(1), asset(2), FEE).unwrap(),
+				asset_in: asset(1),
+				asset_out: asset(2),
+				amount_in: 300,
+				amount_out: 471,
+				fee: 15,
+				reserve_in_after: 30_300,
+				reserve_out_after: 50_000 - 471,
+			}
+			.into(),
+		);
+	});
+}
+
+#[test]
+fn swap_via_invalid_path_fails() {
+	new_test_ext().execute_with(|| {
+		create_pools_through_hub_asset();
+
+		let too_short: BoundedVec<BalanceType<Test>, ConstU32<4>> = vec![asset(1)].try_into().unwrap();
+		assert_noop!(Dex::swap_exact_in_for_out_via_path(RuntimeOrigin::signed(2), too_short, default_fees(0), 1_000, 1, None), Error::<Test>::InvalidPath);
+
+		let circular: BoundedVec<BalanceType<Test>, ConstU32<4>> = vec![asset(1), asset(3), asset(1)].try_into().unwrap();
+		assert_noop!(Dex::swap_exact_in_for_out_via_path(RuntimeOrigin::signed(2), circular, default_fees(2), 1_000, 1, None), Error::<Test>::InvalidPath);
+
+		let path: BoundedVec<BalanceType<Test>, ConstU32<4>> = vec![asset(1), asset(3), asset(2)].try_into().unwrap();
+		assert_noop!(Dex::swap_exact_in_for_out_via_path(RuntimeOrigin::signed(2), path, default_fees(1), 1_000, 1, None), Error::<Test>::InvalidPath);
+
+		let missing_pool: BoundedVec<BalanceType<Test>, ConstU32<4>> = vec![asset(1), asset(2), asset(3)].try_into().unwrap();
+		assert_noop!(Dex::swap_in_for_exact_out_via_path(RuntimeOrigin::signed(2), missing_pool, default_fees(2), 1_000, 1, None), Error::<Test>::PoolDoesNotExist);
+
+		// the pool (1, 3) only exists with the default fee tier
+		let path: BoundedVec<BalanceType<Test>, ConstU32<4>> = vec![asset(1), asset(3), asset(2)].try_into().unwrap();
+		let fees: BoundedVec<Permill, ConstU32<4>> = vec![Permill::from_percent(1), FEE].try_into().unwrap();
+		assert_noop!(Dex::swap_exact_in_for_out_via_path(RuntimeOrigin::signed(2), path, fees, 1_000, 1, None), Error::<Test>::PoolDoesNotExist);
+	});
+}
+
+#[test]
+fn create_native_pool_and_swap() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Dex::setup_account(1, 10_000_000, vec![(1, 10_000_000)]));
+
+		// the native currency is moved through the balances pallet
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), NATIVE, asset(1), FEE, 100_000, 200_000));
+		assert_eq!(Balance::balance(&1), 10_000_000 - 100_000 - 1_000 - 100 - 1);
+		assert_eq!(Assets::total_balance(1, &1), 10_000_000 - 200_000);
+
+		// the native deposit of the pool account is not counted as reserves
+		assert_eq!(Dex::get_price(NATIVE, asset(1), FEE, 200), Ok(400));
+
+		// setup second account
+		assert_ok!(Dex::setup_account(2, 1_000_000, vec![(1, 1_000)]));
+
+		assert_ok!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(2), NATIVE, asset(1), FEE, 1_000, 1_883, None));
+		assert_eq!(Balance::balance(&2), 1_000_000 - 1_000);
+		assert_eq!(Assets::total_balance(1, &2), 1_000 + 1_883);
+
+		// removing the pool returns the reserves and the deposit
+		assert_ok!(Dex::remove_pool(RuntimeOrigin::signed(1), asset(1), NATIVE, FEE));
+		assert_eq!(Balance::balance(&1), 10_000_000 + 1_000 - 1);
+		assert_eq!(Assets::total_balance(1, &1), 10_000_000 - 1_883);
+	});
+}
+
+#[test]
+fn donations_do_not_move_the_price_until_synced() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Dex::setup_account(1, 10_000_000, vec![(1, 10_000_000), (2, 10_000_000)]));
+		assert_ok!(Dex::setup_account(2, 1_000_000, vec![]));
+
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 100_000, 200_000));
+
+		// the pool is stored with ordered assets and tracks its reserves
+		let pool = Dex::liquidity_pool((asset(2), asset(1), FEE)).unwrap();
+		assert_eq!((pool.reserve_a, pool.reserve_b), (200_000, 100_000));
+		assert_eq!(pool.creator, 1);
+		assert_eq!(pool.created_at, 1);
+
+		// donate to the pool account, the price stays the same
+		assert_ok!(Assets::transfer(RuntimeOrigin::signed(1), codec::Compact(1), pool.pool_account, 100_000));
+		assert_eq!(Dex::get_price(asset(1), asset(2), FEE, 200), Ok(400));
+
+		// anyone can skim the donation
+		assert_ok!(Dex::skim(RuntimeOrigin::signed(2), asset(1), asset(2), FEE));
+		assert_eq!(Assets::total_balance(1, &2), 100_000);
+		System::assert_last_event(
+			Event::PoolSkimmed {
+				who: 2,
+				pool_id: pool.liquidity_id,
+				asset_a: asset(2),
+				asset_b: asset(1),
+				fee: FEE,
+				amount_a: 0,
+				amount_b: 100_000,
+			}
+			.into(),
+		);
+
+		// after a sync the donation becomes part of the reserves
+		assert_ok!(Assets::transfer(RuntimeOrigin::signed(1), codec::Compact(1), pool.pool_account, 100_000));
+		assert_ok!(Dex::sync(RuntimeOrigin::signed(2), asset(1), asset(2), FEE));
+		assert_eq!(Dex::get_price(asset(1), asset(2), FEE, 200), Ok(200));
+		System::assert_last_event(
+			Event::PoolSynced {
+				who: 2,
+				pool_id: pool.liquidity_id,
+				asset_a: asset(2),
+				asset_b: asset(1),
+				fee: FEE,
+				reserve_a: 200_000,
+				reserve_b: 200_000,
+			}
+			.into(),
+		);
+	});
+}
+
+#[test]
+fn twap_averages_the_price_over_the_window() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Dex::setup_account(1, 10_000_000, vec![(1, 10_000_000), (2, 10_000_000)]));
+		assert_ok!(Dex::setup_account(2, 1_000_000, vec![(1, 100_000)]));
+
+		// the price of asset 1 is 2 of asset 2
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 100_000, 200_000));
+
+		assert_noop!(Dex::get_twap(asset(1), asset(2), FEE, 0), Error::<Test>::InvalidTwapWindow);
+		assert_noop!(Dex::get_twap(asset(1), asset(2), FEE, 1), Error::<Test>::InsufficientPriceHistory);
+
+		// the price moves in block 11
+		System::set_block_number(11);
+		assert_ok!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(2), asset(1), asset(2), FEE, 10_000, 17_352, None));
+		let two = FixedU128::saturating_from_integer(2u32).into_inner();
+		let new_price = FixedU128::checked_from_rational(182_648u128, 110_000u128).unwrap().into_inner();
+
+		// trading in the current block doesn't change the TWAP
+		System::set_block_number(21);
+		assert_ok!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(2), asset(1), asset(2), FEE, 10_000, 1, None));
+
+		assert_eq!(Dex::get_twap(asset(1), asset(2), FEE, 10), Ok(FixedU128::from_inner(new_price)));
+		assert_eq!(Dex::get_twap(asset(1), asset(2), FEE, 20), Ok(FixedU128::from_inner((two + new_price) / 2)));
+		assert_eq!(Dex::get_twap(asset(1), asset(2), FEE, 15), Ok(FixedU128::from_inner((two * 5 + new_price * 10) / 15)));
+		assert_noop!(Dex::get_twap(asset(1), asset(2), FEE, 21), Error::<Test>::InsufficientPriceHistory);
+	});
+}
+
+#[test]
+fn quotes_match_the_swaps() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Dex::setup_account(1, 10_000_000, vec![(1, 10_000_000), (2, 10_000_000)]));
+		assert_ok!(Dex::setup_account(2, 1_000_000, vec![(1, 100_000)]));
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 100_000, 200_000));
+
+		assert_eq!(Dex::pool_reserves(asset(1), asset(2), FEE), Some((100_000, 200_000)));
+		// LP tokens take the ids from `LpAssetIdStart` on
+		assert_eq!(Dex::lp_token_id(asset(1), asset(2), FEE), Some(1_000));
+		assert_eq!(Dex::list_pools(), vec![(asset(2), asset(1), FEE, 1_000)]);
+
+		// the quotes use the same math as the swaps
+		assert_eq!(Dex::quote_exact_in(asset(1), asset(2), FEE, 10_000), Some(17_352));
+		assert_ok!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(2), asset(1), asset(2), FEE, 10_000, 17_352, None));
+
+		let amount_in = Dex::quote_exact_out(asset(1), asset(2), FEE, 1_000).unwrap();
+		assert_ok!(Dex::swap_in_for_exact_out(RuntimeOrigin::signed(2), asset(1), asset(2), FEE, amount_in, 1_000, None));
+		assert_eq!(Assets::total_balance(1, &2), 100_000 - 10_000 - amount_in);
+
+		// there is no pool for these assets
+		assert_eq!(Dex::quote_exact_in(asset(1), asset(3), FEE, 10_000), None);
+		assert_eq!(Dex::pool_reserves(asset(1), asset(3), FEE), None);
+	});
+}
+
+#[test]
+fn dex_interface_creates_pools_and_swaps() {
+	use pba_interface::DexInterface;
+
+	new_test_ext().execute_with(|| {
+		assert_ok!(<Dex as DexInterface>::setup_account(1));
+		assert_eq!(Balance::total_balance(&1), 1_101);
+		assert_ok!(<Dex as DexInterface>::mint_asset(1, 1, 1_000_000));
+		assert_ok!(<Dex as DexInterface>::mint_asset(1, 2, 1_000_000));
+		assert_eq!(<Dex as DexInterface>::asset_balance(1, 1), 1_000_000);
+
+		// the first add_liquidity creates the pool
+		assert_ok!(<Dex as DexInterface>::add_liquidity(1, 1, 2, 100_000, 100_000));
+		let lp_id = <Dex as DexInterface>::lp_id(1, 2).unwrap();
+		assert_eq!(<Dex as DexInterface>::lp_id(2, 1), Some(lp_id));
+		assert_eq!(Dex::lp_token_id(asset(1), asset(2), FEE), Some(lp_id));
+		assert_eq!(<Dex as DexInterface>::lp_id(1, 3), None);
+		// the minimum liquidity is locked in the pool account
+		assert_eq!(Assets::total_balance(lp_id, &1), 100_000 - 10);
+
+		assert_ok!(<Dex as DexInterface>::add_liquidity(1, 1, 2, 100_000, 100_000));
+		assert_eq!(Assets::total_balance(lp_id, &1), 200_000 - 10);
+		assert_eq!(Dex::pool_reserves(asset(1), asset(2), FEE), Some((200_000, 200_000)));
+
+		let amount_out = Dex::quote_exact_in(asset(1), asset(2), FEE, 10_000).unwrap();
+		assert_ok!(<Dex as DexInterface>::swap_exact_in_for_out(1, 1, 2, 10_000, amount_out));
+		let amount_in = Dex::quote_exact_out(asset(1), asset(2), FEE, 1_000).unwrap();
+		assert_ok!(<Dex as DexInterface>::swap_in_for_exact_out(1, 1, 2, amount_in, 1_000));
+		assert_eq!(<Dex as DexInterface>::asset_balance(1, 2), 800_000 + amount_out + 1_000);
+
+		assert_ok!(<Dex as DexInterface>::remove_liquidity_(1, 1, 2, 100_000));
+		assert_eq!(Assets::total_balance(lp_id, &1), 100_000 - 10);
+	});
+}
+
+#[test]
+fn only_governance_sets_the_protocol_fee() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		assert_noop!(
+			Dex::set_protocol_fee(RuntimeOrigin::signed(1), Some(9), Percent::from_percent(50)),
+			sp_runtime::DispatchError::BadOrigin
+		);
+
+		assert_ok!(Dex::set_protocol_fee(RuntimeOrigin::root(), Some(9), Percent::from_percent(50)));
+		assert_eq!(Dex::protocol_fee_recipient(), Some(9));
+		assert_eq!(Dex::protocol_fee_share(), Percent::from_percent(50));
+		System::assert_last_event(Event::ProtocolFeeSet { recipient: Some(9), share: Percent::from_percent(50) }.into());
+	});
+}
+
+#[test]
+fn protocol_fee_is_minted_on_liquidity_events() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Dex::setup_account(1, 1_000_000, vec![(1, 10_000_000), (2, 10_000_000)]));
+		assert_ok!(Dex::setup_account(2, 1_000_000, vec![(1, 1_000_000)]));
+		assert_ok!(Dex::setup_account(9, 1_000, vec![]));
+		assert_ok!(Dex::set_protocol_fee(RuntimeOrigin::root(), Some(9), Percent::from_percent(50)));
+
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 1_000_000, 1_000_000));
+		let lp_id = Dex::lp_token_id(asset(1), asset(2), FEE).unwrap();
+		assert_eq!(Dex::liquidity_pool((asset(2), asset(1), FEE)).unwrap().k_last, 1_000_000 * 1_000_000);
+
+		// the swap fees grow sqrt(k) from 1_000_000 to 1_002_280
+		assert_ok!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(2), asset(1), asset(2), FEE, 100_000, 0, None));
+		assert_eq!(Assets::balance(lp_id, &9), 0);
+
+		// half of the growth is minted to the recipient: 1_000_000 * 2_280 / (1_002_280 + 1_000_000)
+		assert_ok!(Dex::add_liquidity(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 110_000, 91_324, 0, 0, None));
+		assert_eq!(Assets::balance(lp_id, &9), 1_138);
+
+		// nothing accrued since the last liquidity event
+		assert_ok!(Dex::remove_liquidity(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 0, 0, 100_000, None));
+		assert_eq!(Assets::balance(lp_id, &9), 1_138);
+
+		// switching the fee off stops the accrual
+		assert_ok!(Dex::set_protocol_fee(RuntimeOrigin::root(), None, Percent::zero()));
+		assert_ok!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(2), asset(1), asset(2), FEE, 100_000, 0, None));
+		assert_ok!(Dex::add_liquidity(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 100_000, 80_000, 0, 0, None));
+		assert_eq!(Assets::balance(lp_id, &9), 1_138);
+		assert_eq!(Dex::liquidity_pool((asset(2), asset(1), FEE)).unwrap().k_last, 0);
+	});
+}
+
+#[test]
+fn pools_of_the_same_pair_can_have_different_fee_tiers() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Dex::setup_account(1, 1_000_000, vec![(1, 10_000_000), (2, 10_000_000)]));
+		assert_ok!(Dex::setup_account(2, 1_000_000, vec![(1, 100_000)]));
+		let stable_fee = Permill::from_rational(5u32, 10_000u32);
+
+		// only the default fee tier is allowed until governance adds more
+		assert_noop!(
+			Dex::create_pool(RuntimeOrigin::signed(1), asset(1), asset(2), stable_fee, 100_000, 100_000),
+			Error::<Test>::FeeTierNotAllowed
+		);
+
+		let fee_tiers: BoundedVec<Permill, ConstU32<8>> = vec![stable_fee].try_into().unwrap();
+		assert_noop!(Dex::set_fee_tiers(RuntimeOrigin::signed(1), fee_tiers.clone()), sp_runtime::DispatchError::BadOrigin);
+		assert_ok!(Dex::set_fee_tiers(RuntimeOrigin::root(), fee_tiers.clone()));
+		assert_eq!(Dex::fee_tiers(), fee_tiers);
+		System::assert_last_event(Event::FeeTiersSet { fee_tiers }.into());
+
+		// both pools of the pair exist next to each other
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 100_000, 100_000));
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), asset(1), asset(2), stable_fee, 100_000, 100_000));
+		assert_ne!(Dex::lp_token_id(asset(1), asset(2), FEE), Dex::lp_token_id(asset(1), asset(2), stable_fee));
+		assert_eq!(Dex::list_pools().len(), 2);
+
+		// the lower fee tier gives a better quote
+		assert_eq!(Dex::quote_exact_in(asset(1), asset(2), FEE, 1_000), Some(942));
+		assert_eq!(Dex::quote_exact_in(asset(1), asset(2), stable_fee, 1_000), Some(990));
+
+		// and the swap only moves the reserves of its own pool
+		assert_ok!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(2), asset(1), asset(2), stable_fee, 1_000, 990, None));
+		assert_eq!(Dex::pool_reserves(asset(1), asset(2), stable_fee), Some((101_000, 99_010)));
+		assert_eq!(Dex::pool_reserves(asset(1), asset(2), FEE), Some((100_000, 100_000)));
+	});
+}
+
+#[test]
+fn transactions_after_their_deadline_fail() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(10);
+		assert_ok!(Dex::setup_account(1, 1_000_000, vec![(1, 10_000_000), (2, 10_000_000), (3, 10_000_000)]));
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 100_000, 100_000));
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), asset(2), asset(3), FEE, 100_000, 100_000));
+		let path: BoundedVec<BalanceType<Test>, ConstU32<4>> = vec![asset(1), asset(2), asset(3)].try_into().unwrap();
+
+		// a deadline in the past fails every liquidity and swap call
+		assert_noop!(Dex::add_liquidity(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 1_000, 1_000, 0, 0, Some(9)), Error::<Test>::DeadlineExpired);
+		assert_noop!(Dex::remove_liquidity(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 0, 0, 1_000, Some(9)), Error::<Test>::DeadlineExpired);
+		assert_noop!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 1_000, 1, Some(9)), Error::<Test>::DeadlineExpired);
+		assert_noop!(Dex::swap_in_for_exact_out(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 2_000, 1_000, Some(9)), Error::<Test>::DeadlineExpired);
+		assert_noop!(Dex::swap_exact_in_for_out_via_path(RuntimeOrigin::signed(1), path.clone(), default_fees(2), 1_000, 1, Some(9)), Error::<Test>::DeadlineExpired);
+		assert_noop!(Dex::swap_in_for_exact_out_via_path(RuntimeOrigin::signed(1), path.clone(), default_fees(2), 2_000, 1_000, Some(9)), Error::<Test>::DeadlineExpired);
+
+		// the deadline block itself is still fine
+		assert_ok!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 1_000, 1, Some(10)));
+		assert_ok!(Dex::swap_exact_in_for_out_via_path(RuntimeOrigin::signed(1), path, default_fees(2), 1_000, 1, Some(10)));
+	});
+}
+
+#[test]
+fn add_liquidity_only_deposits_the_optimal_amounts() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Dex::setup_account(1, 1_000_000, vec![(1, 10_000_000), (2, 10_000_000)]));
+		assert_ok!(Dex::setup_account(2, 1_000_000, vec![(1, 100_000), (2, 100_000)]));
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 100_000, 200_000));
+		let lp_id = Dex::lp_token_id(asset(1), asset(2), FEE).unwrap();
+
+		// too much of asset 2 is offered, only 2_000 of it matches 1_000 of asset 1
+		assert_ok!(Dex::add_liquidity(RuntimeOrigin::signed(2), asset(1), asset(2), FEE, 1_000, 5_000, 0, 0, None));
+		assert_eq!(Assets::total_balance(1, &2), 100_000 - 1_000);
+		assert_eq!(Assets::total_balance(2, &2), 100_000 - 2_000);
+		assert_eq!(Assets::total_balance(lp_id, &2), 1_414);
+		assert_eq!(Dex::pool_reserves(asset(1), asset(2), FEE), Some((101_000, 202_000)));
+		System::assert_last_event(
+			Event::LiquidityAdded {
+				who: 2,
+				pool_id: lp_id,
+				asset_a: asset(2),
+				asset_b: asset(1),
+				fee: FEE,
+				amount_a: 2_000,
+				amount_b: 1_000,
+				amount_liq: 1_414,
+			}
+			.into(),
+		);
+
+		// the optimal amounts have to respect the minimums
+		assert_noop!(Dex::add_liquidity(RuntimeOrigin::signed(2), asset(1), asset(2), FEE, 1_000, 5_000, 0, 2_100, None), Error::<Test>::NotEnoughAssetsB);
+		assert_noop!(Dex::add_liquidity(RuntimeOrigin::signed(2), asset(1), asset(2), FEE, 5_000, 1_000, 600, 0, None), Error::<Test>::NotEnoughAssetsA);
+
+		// too much of asset 1 is offered, only 500 of it matches 1_000 of asset 2
+		assert_ok!(Dex::add_liquidity(RuntimeOrigin::signed(2), asset(1), asset(2), FEE, 5_000, 1_000, 500, 0, None));
+		assert_eq!(Dex::pool_reserves(asset(1), asset(2), FEE), Some((101_500, 203_000)));
+	});
+}
+
+#[test]
+fn create_pool_checks_the_balances_of_both_assets() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Dex::setup_account(1, 1_000_000, vec![(1, 40_000), (2, 100_000)]));
+
+		// asset 2 sorts first, so asset 1 is asset_b and has to cover its own amount
+		assert_noop!(
+			Dex::create_pool(RuntimeOrigin::signed(1), asset(2), asset(1), FEE, 30_000, 50_000),
+			sp_runtime::DispatchError::Other("Not enough balance for asset_b")
+		);
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), asset(2), asset(1), FEE, 30_000, 35_000));
+	});
+}
+
+#[test]
+fn minimum_liquidity_is_locked_forever() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Dex::setup_account(1, 1_000_000, vec![(1, 10_000_000), (2, 10_000_000)]));
+
+		// both initial amounts have to be at least MinPoolDeposit
+		assert_noop!(Dex::create_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 99, 1_000), Error::<Test>::InitialDepositTooLow);
+
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 1_000, 1_000));
+		let lp_id = Dex::lp_token_id(asset(1), asset(2), FEE).unwrap();
+		assert_eq!(Assets::total_issuance(lp_id), 1_000);
+		assert_eq!(Assets::total_balance(lp_id, &1), 990);
+		let pool_account = Dex::liquidity_pool((asset(2), asset(1), FEE)).unwrap().pool_account;
+		assert_eq!(Assets::total_balance(lp_id, &pool_account), 10);
+
+		// redeeming every LP token of the creator leaves the locked share in the pool
+		assert_ok!(Dex::remove_liquidity(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 0, 0, 990, None));
+		assert_eq!(Dex::pool_reserves(asset(1), asset(2), FEE), Some((10, 10)));
+
+		// and nobody can remove the pool holding only the locked share
+		assert_noop!(Dex::remove_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE), Error::<Test>::NotEnoughLiquidityTokens);
+
+		// a deposit worth less than one LP token is rejected instead of being donated to the pool
+		let pool = Dex::liquidity_pool((asset(2), asset(1), FEE)).unwrap();
+		assert_ok!(Assets::transfer(RuntimeOrigin::signed(1), codec::Compact(1), pool.pool_account, 1_000_000));
+		assert_ok!(Assets::transfer(RuntimeOrigin::signed(1), codec::Compact(2), pool.pool_account, 1_000_000));
+		assert_ok!(Dex::sync(RuntimeOrigin::signed(1), asset(1), asset(2), FEE));
+		assert_noop!(
+			Dex::add_liquidity(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 50_000, 50_000, 0, 0, None),
+			Error::<Test>::InsufficientLiquidityMinted
+		);
+	});
+}
+
+#[test]
+fn flash_swaps_have_to_restore_the_constant_product() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Dex::setup_account(1, 1_000_000, vec![(1, 10_000_000), (2, 10_000_000)]));
+		assert_ok!(Dex::setup_account(2, 1_000_000, vec![(1, 10_000), (2, 10_000)]));
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 100_000, 100_000));
+
+		assert_noop!(Dex::flash_swap(RuntimeOrigin::signed(2), asset(1), asset(2), FEE, 0, 0), Error::<Test>::FlashSwapAmountZero);
+		assert_noop!(Dex::flash_swap(RuntimeOrigin::signed(2), asset(1), asset(2), FEE, 0, 100_000), Error::<Test>::RequestedExceedsPoolBalance);
+
+		// paying back 5% on top doesn't cover the fee on the amount paid back
+		FlashSwapPremium::set(Percent::from_percent(5));
+		assert_noop!(Dex::flash_swap(RuntimeOrigin::signed(2), asset(1), asset(2), FEE, 0, 1_000), Error::<Test>::FlashSwapNotRepaid);
+
+		// the handler may not trade with the pool it borrowed from
+		FlashSwapPremium::set(Percent::from_percent(6));
+		FlashSwapTradesWithPool::set(true);
+		assert_noop!(Dex::flash_swap(RuntimeOrigin::signed(2), asset(1), asset(2), FEE, 0, 1_000), Error::<Test>::PoolChangedDuringFlashSwap);
+
+		// paying back 6% on top does
+		FlashSwapTradesWithPool::set(false);
+		assert_ok!(Dex::flash_swap(RuntimeOrigin::signed(2), asset(1), asset(2), FEE, 0, 1_000));
+		assert_eq!(Assets::total_balance(2, &2), 10_000 - 60);
+		assert_eq!(Dex::pool_reserves(asset(1), asset(2), FEE), Some((100_000, 100_060)));
+		System::assert_last_event(
+			Event::FlashSwapped {
+				who: 2,
+				pool_id: Dex::lp_token_id(asset(1), asset(2), FEE).unwrap(),
+				asset_a: asset(2),
+				asset_b: asset(1),
+				fee: FEE,
+				amount_a_out: 1_000,
+				amount_b_out: 0,
+				amount_a_in: 1_060,
+				amount_b_in: 0,
+			}
+			.into(),
+		);
+	});
+}
+
+#[test]
+fn stable_pools_swap_pegged_assets_with_low_slippage() {
+	new_test_ext().execute_with(|| {
+		let balances = vec![(1, 10_000_000), (2, 10_000_000), (3, 10_000_000), (4, 10_000_000)];
+		assert_ok!(Dex::setup_account(1, 1_000_000, balances));
+
+		assert_noop!(
+			Dex::create_stable_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 0, 100_000, 100_000),
+			Error::<Test>::InvalidAmplification
+		);
+		assert_noop!(
+			Dex::create_stable_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 10_001, 100_000, 100_000),
+			Error::<Test>::InvalidAmplification
+		);
+		assert_ok!(Dex::create_stable_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 100, 100_000, 100_000));
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), asset(3), asset(4), FEE, 100_000, 100_000));
+
+		// A pair can only have one pool per fee tier, whatever its curve
+		assert_noop!(
+			Dex::create_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 100_000, 100_000),
+			Error::<Test>::PoolAlreadyExists
+		);
+
+		// A balanced stable pool mints D / 2 LP tokens, like sqrt(x * y) of a balanced constant product pool
+		let lp_id = Dex::lp_token_id(asset(1), asset(2), FEE).unwrap();
+		assert_eq!(Assets::total_balance(lp_id, &1), 100_000 - 10);
+		assert_eq!(Dex::get_price(asset(1), asset(2), FEE, 1_000), Ok(1_000));
+
+		// Both pools take the fee of 50, the stable pool pays out almost all of the rest
+		assert_eq!(Dex::quote_exact_in(asset(1), asset(2), FEE, 1_000), Some(949));
+		assert_eq!(Dex::quote_exact_in(asset(3), asset(4), FEE, 1_000), Some(942));
+		assert_eq!(Dex::quote_exact_out(asset(1), asset(2), FEE, 1_000), Some(1_051));
+
+		assert_ok!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 1_000, 949, None));
+		assert_eq!(Assets::total_balance(2, &1), 10_000_000 - 100_000 + 949);
+		assert_eq!(Dex::pool_reserves(asset(1), asset(2), FEE), Some((101_000, 100_000 - 949)));
+
+		// Liquidity is removed at the ratio of the reserves like in any other pool
+		assert_ok!(Dex::remove_liquidity(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 1, 1, 50_000, None));
+		assert_eq!(Assets::total_balance(lp_id, &1), 50_000 - 10);
+	});
+}
+
+fn weighted_assets(
+	assets: Vec<(u32, u32, u128)>,
+) -> BoundedVec<(BalanceType<Test>, Perbill, u128), <Test as crate::Config>::MaxWeightedPoolAssets> {
+	assets
+		.into_iter()
+		.map(|(id, weight, amount)| (asset(id), Perbill::from_percent(weight), amount))
+		.collect::<Vec<_>>()
+		.try_into()
+		.unwrap()
+}
+
+#[test]
+fn weighted_pools_need_normalized_weights_and_distinct_assets() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Dex::setup_account(1, 1_000_000, vec![(1, 10_000_000), (2, 10_000_000)]));
+
+		assert_noop!(
+			Dex::create_weighted_pool(RuntimeOrigin::signed(1), weighted_assets(vec![(1, 100, 100_000)]), FEE),
+			Error::<Test>::InvalidWeightedPoolAssets
+		);
+		assert_noop!(
+			Dex::create_weighted_pool(RuntimeOrigin::signed(1), weighted_assets(vec![(1, 50, 100_000), (1, 50, 100_000)]), FEE),
+			Error::<Test>::InvalidWeightedPoolAssets
+		);
+		assert_noop!(
+			Dex::create_weighted_pool(RuntimeOrigin::signed(1), weighted_assets(vec![(1, 80, 100_000), (2, 30, 100_000)]), FEE),
+			Error::<Test>::InvalidWeights
+		);
+		let below_minimum_weight: BoundedVec<_, _> = vec![
+			(asset(1), Perbill::from_parts(995_000_000), 100_000),
+			(asset(2), Perbill::from_parts(5_000_000), 100_000),
+		]
+		.try_into()
+		.unwrap();
+		assert_noop!(
+			Dex::create_weighted_pool(RuntimeOrigin::signed(1), below_minimum_weight, FEE),
+			Error::<Test>::InvalidWeights
+		);
+	});
+}
 
+#[test]
+fn weighted_pools_swap_join_and_exit_with_the_weighted_product() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Dex::setup_account(1, 1_000_000, vec![(1, 10_000_000), (2, 10_000_000), (3, 10_000_000)]));
+
+		// An 80/20 pool whose reserves are worth the same per unit of weight, so the price is 1
+		assert_ok!(Dex::create_weighted_pool(
+			RuntimeOrigin::signed(1),
+			weighted_assets(vec![(1, 80, 400_000), (2, 20, 100_000)]),
+			FEE
+		));
+		let pool = Dex::weighted_pool(0).unwrap();
+		System::assert_last_event(
+			Event::WeightedPoolCreated {
+				who: 1,
+				pool_id: 0,
+				liquidity_id: pool.liquidity_id,
+				fee: FEE,
+				amounts: BoundedVec::truncate_from(vec![(asset(1), 400_000), (asset(2), 100_000)]),
+				amount_liq: 303_143 - 10,
+			}
+			.into(),
+		);
+
+		// The initial LP tokens are 400_000^0.8 * 100_000^0.2, the minimum liquidity is locked
+		assert_eq!(Assets::total_issuance(pool.liquidity_id), 303_143);
+		assert_eq!(Assets::total_balance(pool.liquidity_id, &1), 303_143 - 10);
+
+		// Both directions pay out the same at a price of 1, after the fee of 50
+		assert_eq!(Dex::quote_weighted_exact_in(0, asset(1), asset(2), 1_000), Some(944));
+		assert_eq!(Dex::quote_weighted_exact_in(0, asset(2), asset(1), 1_000), Some(944));
+		assert_eq!(Dex::quote_weighted_exact_in(0, asset(1), asset(3), 1_000), None);
+
+		assert_noop!(
+			Dex::swap_weighted(RuntimeOrigin::signed(1), 0, asset(1), asset(3), 1_000, 0, None),
+			Error::<Test>::AssetNotInPool
+		);
+		assert_noop!(
+			Dex::swap_weighted(RuntimeOrigin::signed(1), 0, asset(2), asset(1), 50_001, 0, None),
+			Error::<Test>::MaxRatioExceeded
+		);
+		assert_noop!(
+			Dex::swap_weighted(RuntimeOrigin::signed(1), 0, asset(1), asset(2), 1_000, 945, None),
+			Error::<Test>::InsufficientBalance
+		);
+		assert_ok!(Dex::swap_weighted(RuntimeOrigin::signed(1), 0, asset(1), asset(2), 1_000, 944, None));
+		System::assert_last_event(
+			Event::WeightedSwapped {
+				who: 1,
+				pool_id: 0,
+				asset_in: asset(1),
+				asset_out: asset(2),
+				amount_in: 1_000,
+				amount_out: 944,
+				fee: 50,
+				reserve_in_after: 401_000,
+				reserve_out_after: 100_000 - 944,
+			}
+			.into(),
+		);
+		let pool = Dex::weighted_pool(0).unwrap();
+		assert_eq!((pool.assets[0].reserve, pool.assets[1].reserve), (401_000, 100_000 - 944));
+
+		// Depositing only asset 2 pays the fee on the 80% of the deposit that isn't matched by asset 1
+		assert_ok!(Dex::join_weighted_pool(RuntimeOrigin::signed(1), 0, asset(2), 10_000, 5_659, None));
+		System::assert_last_event(
+			Event::WeightedPoolJoined { who: 1, pool_id: 0, asset: asset(2), amount_in: 10_000, amount_liq: 5_659 }.into(),
+		);
+
+		// Withdrawing only asset 2 again pays the fee a second time
+		let balance_before = Assets::total_balance(2, &1);
+		assert_ok!(Dex::exit_weighted_pool(RuntimeOrigin::signed(1), 0, asset(2), 5_659, 1, None));
+		assert_eq!(Assets::total_balance(2, &1), balance_before + 9_247);
+		assert_eq!(Dex::weighted_pool(0).unwrap().assets[1].reserve, 100_000 - 944 + 10_000 - 9_247);
+
+		// A basket of three assets with equal weights
+		let basket: BoundedVec<_, _> = vec![
+			(asset(1), Perbill::from_parts(333_333_334), 100_000),
+			(asset(2), Perbill::from_parts(333_333_333), 100_000),
+			(asset(3), Perbill::from_parts(333_333_333), 100_000),
+		]
+		.try_into()
+		.unwrap();
+		assert_ok!(Dex::create_weighted_pool(RuntimeOrigin::signed(1), basket, FEE));
+		assert_eq!(Dex::quote_weighted_exact_in(1, asset(1), asset(3), 1_000), Some(941));
 	});
 }
 
+#[test]
+fn weighted_math_pays_out_less_than_the_exact_amounts_with_large_reserves() {
+	// Millions of tokens with 18 decimals, the powers are off by far more than one unit
+	let unit = 1_000_000_000_000_000_000u128;
+	let (balance_in, balance_out, supply) = (1_000_000 * unit, 3_000_000 * unit, 2_000_000 * unit);
+	let below_exact = |amount: u128, exact: u128| amount <= exact && amount > exact - exact / 10_000_000_000_000;
+
+	// A weight ratio of 2 pays out balance_out * (1 - (4 / 5)^2)
+	let amount_out = weighted::amount_out(
+		balance_in,
+		Perbill::from_percent(80),
+		balance_out,
+		Perbill::from_percent(40),
+		balance_in / 4,
+		Permill::zero(),
+	);
+	assert!(below_exact(amount_out.unwrap(), balance_out * 9 / 25));
+
+	// With all the weight on one asset, joining and exiting are proportional to the amounts
+	let amount_liq =
+		weighted::liquidity_out_for_join(balance_in, Perbill::one(), supply, balance_in / 4, Permill::zero());
+	assert!(below_exact(amount_liq.unwrap(), supply / 4));
+	let amount_out =
+		weighted::amount_out_for_exit(balance_out, Perbill::one(), supply, supply / 8, Permill::zero());
+	assert!(below_exact(amount_out.unwrap(), balance_out / 8));
+}
+
+#[test]
+fn concentrated_positions_need_a_valid_tick_range() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Dex::setup_account(1, 1_000_000, vec![(1, 1_000_000), (2, 1_000_000)]));
+		assert_ok!(Dex::setup_account(2, 1_000_000, vec![(1, 1_000_000), (2, 1_000_000)]));
+		let price = FixedU128::saturating_from_integer(4u32);
+
+		assert_noop!(
+			Dex::create_concentrated_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 0, price),
+			Error::<Test>::InvalidTickSpacing
+		);
+		assert_noop!(
+			Dex::create_concentrated_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 10, FixedU128::zero()),
+			Error::<Test>::InvalidPrice
+		);
+
+		// Asset 2 is asset_0 of the pool, so the price of asset 1 is inverted to a price of 0.25 of asset 2
+		assert_ok!(Dex::create_concentrated_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 10, price));
+		let pool = Dex::concentrated_pool(0).unwrap();
+		assert_eq!((pool.sqrt_price, pool.tick), (1 << 63, -13_864));
+
+		for (tick_lower, tick_upper) in [(-14_005, -13_000), (-13_000, -13_000), (-13_000, -14_000), (-443_640, 0)] {
+			assert_noop!(
+				Dex::mint_position(RuntimeOrigin::signed(1), 0, tick_lower, tick_upper, 1_000, 1_000, 0, 0, None),
+				Error::<Test>::InvalidTickRange
+			);
+		}
+		assert_noop!(
+			Dex::mint_position(RuntimeOrigin::signed(1), 0, -14_000, -13_000, 1_000, 1_000, 0, 42, None),
+			Error::<Test>::NotEnoughAssetsB
+		);
+		assert_ok!(Dex::mint_position(RuntimeOrigin::signed(1), 0, -14_000, -13_000, 1_000, 1_000, 0, 41, None));
+		assert_eq!(Dex::position(1, 0).unwrap().liquidity, 11_831);
+
+		// Positions can only be changed by their owner
+		assert_noop!(
+			Dex::decrease_liquidity(RuntimeOrigin::signed(2), 0, 1, 0, 0, None),
+			Error::<Test>::PositionDoesNotExist
+		);
+		assert_noop!(Dex::collect(RuntimeOrigin::signed(2), 0), Error::<Test>::PositionDoesNotExist);
+		assert_noop!(
+			Dex::decrease_liquidity(RuntimeOrigin::signed(1), 0, 11_832, 0, 0, None),
+			Error::<Test>::InsufficientPositionLiquidity
+		);
+	});
+}
+
+#[test]
+fn concentrated_positions_earn_the_fees_of_swaps_in_their_range() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Dex::setup_account(1, 1_000_000, vec![(1, 10_000_000), (2, 10_000_000)]));
+		assert_ok!(Dex::setup_account(2, 1_000_000, vec![(1, 10_000_000), (2, 10_000_000)]));
+
+		// Asset 2 is asset_0 of the pool, which starts at a price of 1
+		let price = FixedU128::saturating_from_integer(1u32);
+		assert_ok!(Dex::create_concentrated_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 10, price));
+		let pool = Dex::concentrated_pool(0).unwrap();
+		assert_eq!((pool.asset_0, pool.asset_1, pool.sqrt_price, pool.tick), (asset(2), asset(1), 1 << 64, 0));
+
+		// Account 1 provides liquidity around the price, account 2 above it where it is made of asset_0 only
+		assert_ok!(Dex::mint_position(RuntimeOrigin::signed(1), 0, -600, 600, 100_000, 100_000, 0, 0, None));
+		System::assert_last_event(
+			Event::PositionMinted {
+				who: 1,
+				pool_id: 0,
+				position_id: 0,
+				tick_lower: -600,
+				tick_upper: 600,
+				liquidity: 3_383_749,
+				amount_0: 100_000,
+				amount_1: 100_000,
+			}
+			.into(),
+		);
+		assert_ok!(Dex::mint_position(RuntimeOrigin::signed(2), 0, 600, 1_200, 100_000, 100_000, 0, 0, None));
+		System::assert_last_event(
+			Event::PositionMinted {
+				who: 2,
+				pool_id: 0,
+				position_id: 1,
+				tick_lower: 600,
+				tick_upper: 1_200,
+				liquidity: 3_486_795,
+				amount_0: 100_000,
+				amount_1: 0,
+			}
+			.into(),
+		);
+		assert_eq!(Dex::initialized_ticks(0).into_inner(), vec![-600, 600, 1_200]);
+		assert_eq!(Dex::concentrated_pool(0).unwrap().liquidity, 3_383_749);
+
+		// There isn't enough asset_0 in both ranges to swap this much
+		assert_noop!(
+			Dex::swap_concentrated(RuntimeOrigin::signed(1), 0, asset(1), 500_000, 0, None),
+			Error::<Test>::NotEnoughLiquidityInRange
+		);
+
+		// The swap buys all the asset_0 of the first range and crosses into the range of account 2
+		let balance_before = Assets::total_balance(2, &1);
+		assert_ok!(Dex::swap_concentrated(RuntimeOrigin::signed(1), 0, asset(1), 150_000, 136_750, None));
+		assert_eq!(Assets::total_balance(2, &1), balance_before + 136_750);
+		System::assert_last_event(
+			Event::ConcentratedSwapped {
+				who: 1,
+				pool_id: 0,
+				asset_in: asset(1),
+				asset_out: asset(2),
+				amount_in: 150_000,
+				amount_out: 136_750,
+				fee: 5_424 + 2_077,
+				sqrt_price_after: 19_217_227_013_816_276_556,
+				liquidity_after: 3_486_795,
+				tick_after: 818,
+			}
+			.into(),
+		);
+		let pool = Dex::concentrated_pool(0).unwrap();
+		assert_eq!((pool.tick, pool.liquidity), (818, 3_486_795));
+
+		// Account 1 is owed the asset_1 its liquidity is made of now and its share of the fee
+		assert_ok!(Dex::decrease_liquidity(RuntimeOrigin::signed(1), 0, 3_383_749, 0, 203_045, None));
+		assert_eq!(Dex::position(1, 0).unwrap().tokens_owed_1, 203_045 + 5_423);
+		let balance_before = Assets::total_balance(1, &1);
+		assert_ok!(Dex::collect(RuntimeOrigin::signed(1), 0));
+		assert_eq!(Assets::total_balance(1, &1), balance_before + 208_468);
+		assert!(Dex::position(1, 0).is_none());
+		assert_eq!(Dex::initialized_ticks(0).into_inner(), vec![600, 1_200]);
+
+		// Account 2 earned the fee of the rest of the swap and of a swap back within its range
+		assert_ok!(Dex::swap_concentrated(RuntimeOrigin::signed(1), 0, asset(2), 10_000, 10_280, None));
+		assert_eq!(Dex::concentrated_pool(0).unwrap().tick, 761);
+		assert_ok!(Dex::collect(RuntimeOrigin::signed(2), 1));
+		System::assert_last_event(Event::PositionCollected { who: 2, pool_id: 0, position_id: 1, amount_0: 499, amount_1: 2_076 }.into());
+		assert_eq!(Dex::position(2, 1).unwrap().liquidity, 3_486_795);
+	});
+}
+
+#[test]
+fn limit_orders_are_filled_once_the_pool_pays_the_limit_price() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Dex::setup_account(1, 1_000_000, vec![(1, 1_000_000), (2, 1_000_000)]));
+		assert_ok!(Dex::setup_account(2, 1_000_000, vec![(1, 1_000_000), (2, 1_000_000)]));
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 100_000, 100_000));
+
+		// Account 2 wants at least 1.1 of asset 2 per unit of asset 1, the amount is held until the order is closed
+		let limit_price = FixedU128::saturating_from_rational(11u32, 10u32);
+		assert_ok!(Dex::place_limit_order(RuntimeOrigin::signed(2), asset(1), asset(2), FEE, 1_000, limit_price, 10));
+		System::assert_last_event(
+			Event::LimitOrderPlaced {
+				order_id: 0,
+				owner: 2,
+				pool_id: Dex::lp_token_id(asset(1), asset(2), FEE).unwrap(),
+				asset_in: asset(1),
+				asset_out: asset(2),
+				fee: FEE,
+				amount_in: 1_000,
+				limit_price,
+				expires_at: 10,
+			}
+			.into(),
+		);
+		assert_eq!(Assets::total_balance(1, &2), 1_000_000 - 1_000);
+
+		// At a price of 1 the order can't be filled, neither by a keeper nor when the block is idle
+		assert_noop!(Dex::fill_order(RuntimeOrigin::signed(3), 0), Error::<Test>::OrderNotFillable);
+		Dex::on_idle(1, Weight::MAX);
+		assert!(Dex::limit_order(0).is_some());
+
+		// The reserves become 84_033 of asset 1 and 120_000 of asset 2
+		assert_ok!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(1), asset(2), asset(1), FEE, 20_000, 0, None));
+
+		// The spot price of 1.428 crosses 1.4, but after the fee and the slippage the pool only pays 1.342
+		let limit_price = FixedU128::saturating_from_rational(14u32, 10u32);
+		assert_ok!(Dex::place_limit_order(RuntimeOrigin::signed(2), asset(1), asset(2), FEE, 1_000, limit_price, 10));
+		assert_noop!(Dex::fill_order(RuntimeOrigin::signed(3), 1), Error::<Test>::OrderNotFillable);
+
+		// Without any weight left no order is checked
+		assert_eq!(Dex::on_idle(1, Weight::zero()), Weight::zero());
+		assert!(Dex::limit_order(0).is_some());
+
+		Dex::on_idle(1, Weight::MAX);
+		System::assert_has_event(
+			Event::LimitOrderFilled {
+				order_id: 0,
+				owner: 2,
+				pool_id: Dex::lp_token_id(asset(1), asset(2), FEE).unwrap(),
+				amount_in: 1_000,
+				amount_out: 1_342,
+			}
+			.into(),
+		);
+		// The swap of the fill is made on behalf of the owner
+		assert!(System::events().iter().any(|record| matches!(
+			record.event,
+			RuntimeEvent::Dex(Event::Swapped { who: 2, amount_in: 1_000, amount_out: 1_342, .. })
+		)));
+		assert_eq!(Assets::total_balance(2, &2), 1_000_000 + 1_342);
+		assert!(Dex::limit_order(0).is_none());
+		assert!(Dex::limit_order(1).is_some());
+		assert_eq!(OpenOrdersOf::<Test>::get(2), 1);
+	});
+}
+
+#[test]
+fn limit_orders_can_be_cancelled_and_expire() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Dex::setup_account(1, 1_000_000, vec![(1, 1_000_000), (2, 1_000_000)]));
+		assert_ok!(Dex::setup_account(2, 1_000_000, vec![(1, 1_000_000), (2, 1_000_000)]));
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 100_000, 100_000));
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), NATIVE, asset(2), FEE, 100_000, 100_000));
+		let limit_price = FixedU128::saturating_from_integer(2u32);
+
+		assert_noop!(
+			Dex::place_limit_order(RuntimeOrigin::signed(2), asset(1), asset(3), FEE, 1_000, limit_price, 5),
+			Error::<Test>::PoolDoesNotExist
+		);
+		assert_ok!(Dex::place_limit_order(RuntimeOrigin::signed(2), asset(1), asset(2), FEE, 1_000, limit_price, 5));
+		assert_ok!(Dex::place_limit_order(RuntimeOrigin::signed(2), NATIVE, asset(2), FEE, 1_000, limit_price, 5));
+
+		// Only the owner can cancel an order before it expires
+		assert_noop!(Dex::cancel_limit_order(RuntimeOrigin::signed(1), 0), Error::<Test>::NotOrderOwner);
+		assert_ok!(Dex::cancel_limit_order(RuntimeOrigin::signed(2), 0));
+		System::assert_last_event(
+			Event::LimitOrderCancelled {
+				order_id: 0,
+				owner: 2,
+				pool_id: Dex::lp_token_id(asset(1), asset(2), FEE),
+				amount_in: 1_000,
+			}
+			.into(),
+		);
+		assert_eq!(Assets::total_balance(1, &2), 1_000_000);
+
+		// After its expiry the order is paid back when the block is idle
+		System::set_block_number(6);
+		assert_noop!(Dex::fill_order(RuntimeOrigin::signed(1), 1), Error::<Test>::DeadlineExpired);
+		Dex::on_idle(6, Weight::MAX);
+		System::assert_last_event(
+			Event::LimitOrderExpired {
+				order_id: 1,
+				owner: 2,
+				pool_id: Dex::lp_token_id(NATIVE, asset(2), FEE),
+				amount_in: 1_000,
+			}
+			.into(),
+		);
+		// The first order paid the existential deposit of the escrow account
+		assert_eq!(Balance::balance(&2), 1_000_000 - 1);
+		assert_eq!(OpenOrdersOf::<Test>::get(2), 0);
+
+		assert_noop!(
+			Dex::place_limit_order(RuntimeOrigin::signed(2), asset(1), asset(2), FEE, 1_000, limit_price, 5),
+			Error::<Test>::DeadlineExpired
+		);
+
+		// An account can only have `MaxLimitOrders` open orders
+		for _ in 0..16 {
+			assert_ok!(Dex::place_limit_order(RuntimeOrigin::signed(2), asset(1), asset(2), FEE, 10, limit_price, 10));
+		}
+		assert_noop!(
+			Dex::place_limit_order(RuntimeOrigin::signed(2), asset(1), asset(2), FEE, 10, limit_price, 10),
+			Error::<Test>::TooManyLimitOrders
+		);
+	});
+}
+
+#[test]
+fn limit_orders_are_not_placed_in_pools_that_dont_swap_immediately() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Dex::setup_account(1, 1_000_000, vec![(1, 1_000_000), (2, 1_000_000)]));
+		assert_ok!(Dex::setup_account(2, 1_000_000, vec![(1, 1_000_000)]));
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 100_000, 100_000));
+		let limit_price = FixedU128::saturating_from_integer(2u32);
+
+		// A pool in batch mode only clears swap intents
+		assert_ok!(Dex::set_batch_mode(RuntimeOrigin::root(), asset(1), asset(2), FEE, true));
+		assert_noop!(
+			Dex::place_limit_order(RuntimeOrigin::signed(2), asset(1), asset(2), FEE, 1_000, limit_price, 10),
+			Error::<Test>::PoolInBatchMode
+		);
+		assert_ok!(Dex::set_batch_mode(RuntimeOrigin::root(), asset(1), asset(2), FEE, false));
+
+		// A pool with paused swaps couldn't fill the order
+		assert_ok!(Dex::pause_pool(RuntimeOrigin::root(), asset(1), asset(2), FEE, PauseFlags { swaps: true, adds: false }));
+		assert_noop!(
+			Dex::place_limit_order(RuntimeOrigin::signed(2), asset(1), asset(2), FEE, 1_000, limit_price, 10),
+			Error::<Test>::PoolPaused
+		);
+		assert_ok!(Dex::unpause_pool(RuntimeOrigin::root(), asset(1), asset(2), FEE));
+		assert_ok!(Dex::place_limit_order(RuntimeOrigin::signed(2), asset(1), asset(2), FEE, 1_000, limit_price, 10));
+	});
+}
+
+#[test]
+fn lp_tokens_held_for_limit_orders_are_not_burned_with_their_pool() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Dex::setup_account(1, 1_000_000, vec![(1, 1_000_000), (2, 1_000_000), (3, 1_000_000)]));
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 100_000, 100_000));
+		let lp_id = Dex::lp_token_id(asset(1), asset(2), FEE).unwrap();
+		let lp_token = BalanceType::AssetBalance(lp_id);
+
+		// The LP token is traded in a pool of its own, an order sells some of it there
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), lp_token.clone(), asset(3), FEE, 10_000, 10_000));
+		let limit_price = FixedU128::saturating_from_integer(2u32);
+		assert_ok!(Dex::place_limit_order(RuntimeOrigin::signed(1), lp_token.clone(), asset(3), FEE, 1_000, limit_price, 10));
+		assert_eq!(Assets::balance(lp_id, &Dex::escrow_account()), 1_000);
+		assert_ok!(Dex::remove_pool(RuntimeOrigin::signed(1), lp_token.clone(), asset(3), FEE));
+
+		// Only the minimum liquidity in the pool account is locked, so the pool can't be removed while an order
+		// holds some of its LP tokens
+		assert_eq!(Assets::balance(lp_id, &1), 100_000 - 10 - 1_000);
+		assert_noop!(Dex::remove_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE), Error::<Test>::NotEnoughLiquidityTokens);
+
+		// The order is paid back in full, then the pool can be removed
+		assert_ok!(Dex::cancel_limit_order(RuntimeOrigin::signed(1), 0));
+		assert_eq!(Assets::balance(lp_id, &1), 100_000 - 10);
+		assert_ok!(Dex::remove_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE));
+		assert!(!Assets::asset_exists(lp_id));
+		assert_ok!(Dex::do_try_state());
+	});
+}
+
+#[test]
+fn batch_mode_clears_the_swap_intents_of_a_block_at_a_uniform_price() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		for who in 1..=4 {
+			assert_ok!(Dex::setup_account(who, 1_000_000, vec![(1, 1_000_000), (2, 1_000_000)]));
+		}
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 100_000, 100_000));
+
+		// Only the governance origin can switch a pool to batch mode, and only pools in batch mode take intents
+		assert_noop!(
+			Dex::submit_swap_intent(RuntimeOrigin::signed(2), asset(1), asset(2), FEE, 10_000, 1, None),
+			Error::<Test>::PoolNotInBatchMode
+		);
+		assert_noop!(
+			Dex::set_batch_mode(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, true),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		let pool_id = Dex::lp_token_id(asset(1), asset(2), FEE).unwrap();
+		assert_ok!(Dex::set_batch_mode(RuntimeOrigin::root(), asset(1), asset(2), FEE, true));
+		System::assert_last_event(
+			Event::BatchModeSet { pool_id, asset_a: asset(2), asset_b: asset(1), fee: FEE, enabled: true }.into(),
+		);
+		assert_noop!(
+			Dex::swap_exact_in_for_out(RuntimeOrigin::signed(2), asset(1), asset(2), FEE, 10_000, 0, None),
+			Error::<Test>::PoolInBatchMode
+		);
+
+		// Swapped alone, 10_000 of asset 1 would get 8_676 of asset 2 and 4_000 of asset 2 would get 3_661 of asset 1
+		assert_ok!(Dex::submit_swap_intent(RuntimeOrigin::signed(2), asset(1), asset(2), FEE, 10_000, 1, None));
+		assert_ok!(Dex::submit_swap_intent(RuntimeOrigin::signed(3), asset(2), asset(1), FEE, 4_000, 1, None));
+		assert_ok!(Dex::submit_swap_intent(RuntimeOrigin::signed(4), asset(1), asset(2), FEE, 2_000, 2_000, None));
+		assert_eq!(Assets::total_balance(1, &2), 1_000_000 - 10_000);
+		assert_eq!(Assets::balance(1, &Dex::escrow_account()), 12_000);
+		assert_eq!(Assets::balance(2, &Dex::escrow_account()), 4_000);
+
+		// In the batch the intents selling asset 1 would get 1_773 for 2_000, below the limit of account 4, so it is
+		// paid back. The rest clears at 1.10825 of asset 1 per asset 2, the pool only swaps 5_567 of asset 1 for 5_024.
+		Dex::on_finalize(1);
+		System::assert_has_event(
+			Event::SwapIntentRefunded { who: 4, pool_id: Some(pool_id), asset_in: asset(1), fee: FEE, amount_in: 2_000 }.into(),
+		);
+		System::assert_has_event(
+			Event::SwapIntentFilled {
+				who: 2,
+				pool_id,
+				asset_in: asset(1),
+				asset_out: asset(2),
+				fee: FEE,
+				amount_in: 10_000,
+				amount_out: 9_024,
+			}
+			.into(),
+		);
+		System::assert_last_event(
+			Event::BatchCleared {
+				pool_id,
+				asset_a: asset(2),
+				asset_b: asset(1),
+				fee: FEE,
+				amount_a_in: 4_000,
+				amount_b_in: 10_000,
+				price: FixedU128::saturating_from_rational(4_433u32, 4_000u32),
+			}
+			.into(),
+		);
+		assert_eq!(Assets::total_balance(2, &2), 1_000_000 + 9_024);
+		assert_eq!(Assets::total_balance(1, &3), 1_000_000 + 4_433);
+		assert_eq!(Assets::total_balance(1, &4), 1_000_000);
+		assert_eq!(Assets::balance(1, &Dex::escrow_account()), 0);
+		assert_eq!(Assets::balance(2, &Dex::escrow_account()), 0);
+
+		let pool = Dex::liquidity_pool((asset(2), asset(1), FEE)).unwrap();
+		assert_eq!((pool.reserve_a, pool.reserve_b), (94_976, 105_567));
+		assert!(SwapIntents::<Test>::get((asset(2), asset(1), FEE)).is_empty());
+
+		// The intents are cleared again once without the ones below their limit. Account 3 would get 4_976 with
+		// account 4 and 4_895 without it, so its limit of 4_950 is missed then and the whole batch is paid back.
+		System::set_block_number(2);
+		System::reset_events();
+		assert_ok!(Dex::submit_swap_intent(RuntimeOrigin::signed(2), asset(1), asset(2), FEE, 10_000, 1, None));
+		assert_ok!(Dex::submit_swap_intent(RuntimeOrigin::signed(3), asset(2), asset(1), FEE, 4_000, 4_950, None));
+		assert_ok!(Dex::submit_swap_intent(RuntimeOrigin::signed(4), asset(1), asset(2), FEE, 2_000, 2_000, None));
+		Dex::on_finalize(2);
+		System::assert_has_event(
+			Event::SwapIntentRefunded { who: 3, pool_id: Some(pool_id), asset_in: asset(2), fee: FEE, amount_in: 4_000 }.into(),
+		);
+		System::assert_last_event(
+			Event::SwapIntentRefunded { who: 4, pool_id: Some(pool_id), asset_in: asset(1), fee: FEE, amount_in: 2_000 }.into(),
+		);
+		assert_eq!(Assets::total_balance(1, &2), 1_000_000 - 10_000);
+		assert_eq!(Assets::total_balance(2, &3), 1_000_000 - 4_000);
+		assert_eq!(Assets::total_balance(1, &4), 1_000_000);
+		let pool = Dex::liquidity_pool((asset(2), asset(1), FEE)).unwrap();
+		assert_eq!((pool.reserve_a, pool.reserve_b), (94_976, 105_567));
+
+		// A pool in batch mode takes at most `MaxSwapIntents` intents per block
+		for _ in 0..16 {
+			assert_ok!(Dex::submit_swap_intent(RuntimeOrigin::signed(2), asset(1), asset(2), FEE, 10, 1, None));
+		}
+		assert_noop!(
+			Dex::submit_swap_intent(RuntimeOrigin::signed(2), asset(1), asset(2), FEE, 10, 1, None),
+			Error::<Test>::TooManySwapIntents
+		);
+	});
+}
+
+#[test]
+fn swap_intents_that_cannot_be_paid_back_stay_claimable() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Dex::setup_account(1, 1_000_000, vec![(1, 1_000_000), (2, 1_000_000)]));
+		assert_ok!(Dex::setup_account(2, 1_000_000, vec![(1, 1_000_000)]));
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 100_000, 100_000));
+		let pool_id = Dex::lp_token_id(asset(1), asset(2), FEE).unwrap();
+		assert_ok!(Dex::set_batch_mode(RuntimeOrigin::root(), asset(1), asset(2), FEE, true));
+		assert_ok!(Dex::submit_swap_intent(RuntimeOrigin::signed(2), asset(1), asset(2), FEE, 10_000, 1, None));
+
+		// The asset sold is frozen, so the batch can't be cleared and the intent can't be paid back either
+		assert_ok!(Assets::freeze_asset(RuntimeOrigin::signed(Dex::account_id()), codec::Compact(1)));
+		Dex::on_finalize(1);
+		System::assert_last_event(
+			Event::SwapIntentRefundUnclaimed { who: 2, pool_id: Some(pool_id), asset_in: asset(1), fee: FEE, amount_in: 10_000 }
+				.into(),
+		);
+		assert!(SwapIntents::<Test>::get((asset(2), asset(1), FEE)).is_empty());
+		assert_eq!(UnclaimedRefunds::<Test>::get(2, asset(1)), 10_000);
+		assert_eq!(Assets::balance(1, &Dex::escrow_account()), 10_000);
+		assert_ok!(Dex::do_try_state());
+
+		// The amount stays in the escrow account until it can be paid back
+		assert!(Dex::claim_swap_intent_refund(RuntimeOrigin::signed(2), asset(1)).is_err());
+		assert_eq!(UnclaimedRefunds::<Test>::get(2, asset(1)), 10_000);
+		assert_ok!(Assets::thaw_asset(RuntimeOrigin::signed(Dex::account_id()), codec::Compact(1)));
+		assert_ok!(Dex::claim_swap_intent_refund(RuntimeOrigin::signed(2), asset(1)));
+		System::assert_last_event(Event::SwapIntentRefundClaimed { who: 2, asset: asset(1), amount: 10_000 }.into());
+		assert_eq!(Assets::total_balance(1, &2), 1_000_000);
+		assert_noop!(Dex::claim_swap_intent_refund(RuntimeOrigin::signed(2), asset(1)), Error::<Test>::NothingToClaim);
+		assert_ok!(Dex::do_try_state());
+	});
+}
+
+#[test]
+fn genesis_config_creates_the_fee_tiers_and_pools() {
+	use sp_runtime::BuildStorage;
+
+	let mut storage = frame_system::GenesisConfig::<Test>::default().build_storage().unwrap();
+	pallet_balances::GenesisConfig::<Test> { balances: vec![(1, 1_000_000)] }.assimilate_storage(&mut storage).unwrap();
+	pallet_assets::GenesisConfig::<Test> {
+		assets: vec![(1, 1, true, 1), (2, 1, true, 1)],
+		metadata: vec![],
+		accounts: vec![(1, 1, 1_000_000), (2, 1, 1_000_000)],
+	}
+	.assimilate_storage(&mut storage)
+	.unwrap();
+	crate::GenesisConfig::<Test> {
+		fee_tiers: vec![Permill::from_parts(3_000)],
+		pools: vec![
+			(asset(1), asset(2), FEE, 100_000, 100_000, 1),
+			(NATIVE, asset(1), Permill::from_parts(3_000), 50_000, 200_000, 1),
+		],
+	}
+	.assimilate_storage(&mut storage)
+	.unwrap();
+
+	sp_io::TestExternalities::from(storage).execute_with(|| {
+		assert_eq!(Dex::fee_tiers().into_inner(), vec![Permill::from_parts(3_000)]);
+
+		let pool = Dex::liquidity_pool((asset(2), asset(1), FEE)).unwrap();
+		assert_eq!((pool.reserve_a, pool.reserve_b), (100_000, 100_000));
+		assert_eq!(Assets::balance(pool.liquidity_id, &1), 100_000 - 10);
+
+		let pool = Dex::liquidity_pool((asset(1), NATIVE, Permill::from_parts(3_000))).unwrap();
+		assert_eq!((pool.reserve_a, pool.reserve_b), (200_000, 50_000));
+		assert_eq!(Assets::balance(pool.liquidity_id, &1), 100_000 - 10);
+
+		// The LP holder provided the initial amounts, the deposits of both pool accounts and the existential
+		// deposit of the pallet account
+		assert_eq!(Assets::balance(1, &1), 1_000_000 - 100_000 - 200_000);
+		assert_eq!(Balance::balance(&1), 1_000_000 - 50_000 - 2 * (1_000 + 100) - 1);
+	});
+}
+
+#[test]
+fn paused_pools_fail_swaps_and_adds_but_not_exits() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Dex::setup_account(1, 1_000_000, vec![(1, 1_000_000), (2, 1_000_000), (3, 1_000_000)]));
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 100_000, 100_000));
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), asset(1), asset(3), FEE, 100_000, 100_000));
+
+		// Only the pause origin can pause, and a pause has to block something
+		let swaps_only = PauseFlags { swaps: true, adds: false };
+		assert_noop!(
+			Dex::pause_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, swaps_only),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		assert_noop!(
+			Dex::pause_pool(RuntimeOrigin::root(), asset(1), asset(2), FEE, PauseFlags::default()),
+			Error::<Test>::NothingToPause
+		);
+		assert_noop!(
+			Dex::pause_pool(RuntimeOrigin::root(), asset(2), asset(3), FEE, swaps_only),
+			Error::<Test>::PoolDoesNotExist
+		);
+
+		// A pool with paused swaps still takes liquidity, the other pool keeps swapping
+		let path: BoundedVec<BalanceType<Test>, ConstU32<4>> = vec![asset(3), asset(1), asset(2)].try_into().unwrap();
+		let pool_id = Dex::lp_token_id(asset(1), asset(2), FEE).unwrap();
+		assert_ok!(Dex::pause_pool(RuntimeOrigin::root(), asset(1), asset(2), FEE, swaps_only));
+		System::assert_last_event(
+			Event::PoolPaused { pool_id, asset_a: asset(2), asset_b: asset(1), fee: FEE, flags: swaps_only }.into(),
+		);
+		assert_noop!(
+			Dex::swap_exact_in_for_out(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 1_000, 0, None),
+			Error::<Test>::PoolPaused
+		);
+		assert_noop!(
+			Dex::swap_exact_in_for_out_via_path(RuntimeOrigin::signed(1), path, default_fees(2), 1_000, 0, None),
+			Error::<Test>::PoolPaused
+		);
+		assert_noop!(Dex::flash_swap(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 1_000, 0), Error::<Test>::PoolPaused);
+		assert_ok!(Dex::add_liquidity(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 1_000, 1_000, 0, 0, None));
+		assert_ok!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(1), asset(1), asset(3), FEE, 1_000, 0, None));
+
+		// A pool with paused adds still swaps
+		let adds_only = PauseFlags { swaps: false, adds: true };
+		assert_ok!(Dex::pause_pool(RuntimeOrigin::root(), asset(1), asset(2), FEE, adds_only));
+		assert_noop!(
+			Dex::add_liquidity(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 1_000, 1_000, 0, 0, None),
+			Error::<Test>::PoolPaused
+		);
+		assert_ok!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 1_000, 0, None));
+
+		// LPs can always exit
+		assert_ok!(Dex::remove_liquidity(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 0, 0, 1_000, None));
+
+		assert_ok!(Dex::unpause_pool(RuntimeOrigin::root(), asset(1), asset(2), FEE));
+		System::assert_last_event(Event::PoolUnpaused { pool_id, asset_a: asset(2), asset_b: asset(1), fee: FEE }.into());
+		assert_ok!(Dex::add_liquidity(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 1_000, 1_000, 0, 0, None));
+
+		// Pausing all the pools stops every pool, lifting it leaves the pauses of single pools in place
+		let everything = PauseFlags { swaps: true, adds: true };
+		assert_ok!(Dex::pause_pool(RuntimeOrigin::root(), asset(1), asset(2), FEE, swaps_only));
+		assert_ok!(Dex::pause_all(RuntimeOrigin::root(), everything));
+		System::assert_last_event(Event::AllPaused { flags: everything }.into());
+		assert_noop!(
+			Dex::swap_exact_in_for_out(RuntimeOrigin::signed(1), asset(1), asset(3), FEE, 1_000, 0, None),
+			Error::<Test>::PoolPaused
+		);
+		assert_noop!(
+			Dex::add_liquidity(RuntimeOrigin::signed(1), asset(1), asset(3), FEE, 1_000, 1_000, 0, 0, None),
+			Error::<Test>::PoolPaused
+		);
+		assert_ok!(Dex::remove_liquidity(RuntimeOrigin::signed(1), asset(1), asset(3), FEE, 0, 0, 1_000, None));
+
+		assert_ok!(Dex::unpause_all(RuntimeOrigin::root()));
+		System::assert_last_event(Event::AllUnpaused.into());
+		assert_ok!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(1), asset(1), asset(3), FEE, 1_000, 0, None));
+		assert_noop!(
+			Dex::swap_exact_in_for_out(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 1_000, 0, None),
+			Error::<Test>::PoolPaused
+		);
+	});
+}
+
+#[test]
+fn weighted_and_concentrated_pools_are_paused_by_their_id() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Dex::setup_account(1, 1_000_000, vec![(1, 10_000_000), (2, 10_000_000)]));
+		assert_ok!(Dex::create_weighted_pool(
+			RuntimeOrigin::signed(1),
+			weighted_assets(vec![(1, 50, 100_000), (2, 50, 100_000)]),
+			FEE
+		));
+		let price = FixedU128::saturating_from_integer(1u32);
+		assert_ok!(Dex::create_concentrated_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 10, price));
+		assert_ok!(Dex::mint_position(RuntimeOrigin::signed(1), 0, -600, 600, 100_000, 100_000, 0, 0, None));
+
+		let swaps_only = PauseFlags { swaps: true, adds: false };
+		let adds_only = PauseFlags { swaps: false, adds: true };
+		assert_noop!(Dex::pause_weighted_pool(RuntimeOrigin::signed(1), 0, swaps_only), sp_runtime::DispatchError::BadOrigin);
+		assert_noop!(Dex::pause_weighted_pool(RuntimeOrigin::root(), 1, swaps_only), Error::<Test>::PoolDoesNotExist);
+		assert_noop!(
+			Dex::pause_concentrated_pool(RuntimeOrigin::root(), 0, PauseFlags::default()),
+			Error::<Test>::NothingToPause
+		);
+
+		// The weighted pool stops swapping but still takes liquidity, the concentrated pool keeps swapping
+		assert_ok!(Dex::pause_weighted_pool(RuntimeOrigin::root(), 0, swaps_only));
+		System::assert_last_event(Event::WeightedPoolPaused { pool_id: 0, flags: swaps_only }.into());
+		assert_noop!(
+			Dex::swap_weighted(RuntimeOrigin::signed(1), 0, asset(1), asset(2), 1_000, 0, None),
+			Error::<Test>::PoolPaused
+		);
+		assert_ok!(Dex::join_weighted_pool(RuntimeOrigin::signed(1), 0, asset(1), 1_000, 0, None));
+		assert_ok!(Dex::swap_concentrated(RuntimeOrigin::signed(1), 0, asset(1), 1_000, 0, None));
+
+		// The concentrated pool stops taking liquidity but still swaps and pays out its positions
+		assert_ok!(Dex::pause_concentrated_pool(RuntimeOrigin::root(), 0, adds_only));
+		System::assert_last_event(Event::ConcentratedPoolPaused { pool_id: 0, flags: adds_only }.into());
+		assert_noop!(
+			Dex::mint_position(RuntimeOrigin::signed(1), 0, -600, 600, 1_000, 1_000, 0, 0, None),
+			Error::<Test>::PoolPaused
+		);
+		assert_ok!(Dex::swap_concentrated(RuntimeOrigin::signed(1), 0, asset(2), 1_000, 0, None));
+		assert_ok!(Dex::collect(RuntimeOrigin::signed(1), 0));
+
+		assert_ok!(Dex::unpause_weighted_pool(RuntimeOrigin::root(), 0));
+		System::assert_last_event(Event::WeightedPoolUnpaused { pool_id: 0 }.into());
+		assert_ok!(Dex::swap_weighted(RuntimeOrigin::signed(1), 0, asset(1), asset(2), 1_000, 0, None));
+		assert_ok!(Dex::unpause_concentrated_pool(RuntimeOrigin::root(), 0));
+		System::assert_last_event(Event::ConcentratedPoolUnpaused { pool_id: 0 }.into());
+		assert_ok!(Dex::mint_position(RuntimeOrigin::signed(1), 0, -600, 600, 1_000, 1_000, 0, 0, None));
+	});
+}
+
+#[test]
+fn lp_tokens_take_free_ids_get_metadata_and_are_destroyed_with_their_pool() {
+	use frame_support::traits::fungibles::metadata::Inspect as MetadataInspect;
+
+	new_test_ext().execute_with(|| {
+		// An asset already uses the first LP token id
+		assert_ok!(Dex::setup_account(1, 1_000_000, vec![(1, 1_000_000), (2, 1_000_000), (1_000, 1)]));
+		assert_eq!(<Dex as pba_interface::DexInterface>::lp_id(1, 2), None);
+
+		// The LP token skips the id in use and is named after the assets of the pool and its fee tier
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 100_000, 100_000));
+		assert_eq!(Dex::lp_token_id(asset(1), asset(2), FEE), Some(1_001));
+		assert_eq!(<Dex as pba_interface::DexInterface>::lp_id(1, 2), Some(1_001));
+		assert_eq!(NextLpAssetId::<Test>::get(), Some(1_002));
+		assert_eq!(Assets::name(1_001), b"LP-2-1".to_vec());
+		assert_eq!(Assets::symbol(1_001), b"LP-2-1-500".to_vec());
+		assert_eq!(Assets::decimals(1_001), 12);
+		assert_eq!(Balance::balance(&1), 1_000_000 - 1_000 - 100 - 1);
+
+		// The LP tokens of weighted pools take the next id
+		assert_ok!(Dex::create_weighted_pool(
+			RuntimeOrigin::signed(1),
+			weighted_assets(vec![(1, 50, 100_000), (2, 50, 100_000)]),
+			FEE
+		));
+		assert_eq!(Dex::weighted_pool(0).unwrap().liquidity_id, 1_002);
+		assert_eq!(Assets::name(1_002), b"LP-WEIGHTED-0".to_vec());
+		assert_eq!(Assets::symbol(1_002), b"LPW-0".to_vec());
+
+		// Removing the pool destroys its LP token and pays the metadata deposit back
+		assert_ok!(Dex::remove_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE));
+		assert!(!Assets::asset_exists(1_001));
+		assert_eq!(Balance::balance(&1), 1_000_000 - 1_000 - 100 - 1);
+
+		// A new pool of the pair gets a new LP token
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 100_000, 100_000));
+		assert_eq!(Dex::lp_token_id(asset(1), asset(2), FEE), Some(1_003));
+
+		// Only a bounded number of ids is looked at for a free one
+		assert_ok!(Dex::setup_account(1, 1_000_000, (1_004..1_020).map(|id| (id, 1)).collect()));
+		assert_noop!(
+			Dex::create_pool(RuntimeOrigin::signed(1), NATIVE, asset(1), FEE, 100_000, 100_000),
+			Error::<Test>::LpAssetIdsExhausted
+		);
+	});
+}
+
+#[test]
+fn signed_accounts_cannot_take_the_ids_of_lp_tokens() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Dex::setup_account(1, 1_000_000, vec![(1, 1_000_000), (2, 1_000_000)]));
+		assert_ok!(Dex::setup_account(2, 1_000_000, vec![]));
+
+		// A squatter can't create the assets LP tokens would take, only the ones below `LpAssetIdStart`
+		for id in [1_000, 1_001, 1_015, u32::MAX] {
+			assert_noop!(
+				Assets::create(RuntimeOrigin::signed(2), codec::Compact(id), 2, 1),
+				sp_runtime::DispatchError::BadOrigin
+			);
+		}
+		assert_ok!(Assets::create(RuntimeOrigin::signed(2), codec::Compact(999), 2, 1));
+
+		// So the LP token of a new pool takes the first id
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 100_000, 100_000));
+		assert_eq!(Dex::lp_token_id(asset(1), asset(2), FEE), Some(1_000));
+	});
+}
+
+#[test]
+fn pool_accounts_are_sub_accounts_and_legacy_pools_are_migrated() {
+	use crate::migrations::v2::MigrateToSubAccounts;
+	use frame_support::traits::{
+		fungible::Mutate,
+		fungibles::Mutate as FSMutate,
+		tokens::Preservation,
+		OnRuntimeUpgrade,
+	};
+	use sp_runtime::traits::Hash;
+
+	new_test_ext().execute_with(|| {
+		assert_ok!(Dex::setup_account(1, 1_000_000, vec![(1, 1_000_000)]));
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), NATIVE, asset(1), FEE, 100_000, 100_000));
+		let (key, pool) = LiquidityPoolMap::<Test>::iter().next().unwrap();
+		let sub_account = pool.pool_account;
+
+		// The pool account is derived from the pallet id but differs from the pallet account
+		assert_ne!(sub_account, Dex::account_id());
+		assert_eq!(Balance::balance(&sub_account), 100_000 + 1_000);
+
+		// Emulates a pool created before the migration, at the account decoded from the hash of its key
+		let legacy: u128 = Decode::decode(&mut TrailingZeroInput::new(
+			&<Test as frame_system::Config>::Hashing::hash(&key.encode()).encode(),
+		))
+		.unwrap();
+		assert_ok!(<Balance as Mutate<_>>::transfer(&sub_account, &legacy, 1_000, Preservation::Expendable));
+		assert_ok!(<Assets as FSMutate<_>>::transfer(1, &sub_account, &legacy, 100_000, Preservation::Expendable));
+		assert_ok!(<Assets as FSMutate<_>>::transfer(pool.liquidity_id, &sub_account, &legacy, 10, Preservation::Expendable));
+		assert_ok!(<Balance as Mutate<_>>::transfer(&sub_account, &legacy, 100_000, Preservation::Expendable));
+		LiquidityPoolMap::<Test>::mutate(&key, |pool| pool.as_mut().unwrap().pool_account = legacy);
+		StorageVersion::new(1).put::<Dex>();
+
+		// The migration moves the pool and its balances to the sub-account
+		MigrateToSubAccounts::<Test>::on_runtime_upgrade();
+		assert_eq!(Dex::on_chain_storage_version(), 2);
+		assert_eq!(LiquidityPoolMap::<Test>::get(&key).unwrap().pool_account, sub_account);
+		assert_eq!(Balance::balance(&sub_account), 100_000 + 1_000);
+		assert_eq!(Assets::balance(1, &sub_account), 100_000);
+		assert_eq!(Assets::balance(pool.liquidity_id, &sub_account), 10);
+		assert_eq!(Balance::balance(&legacy), 0);
+		assert_eq!(Assets::balance(1, &legacy), 0);
+
+		// Running it again at version 2 does nothing, and the pool still swaps
+		MigrateToSubAccounts::<Test>::on_runtime_upgrade();
+		assert_eq!(LiquidityPoolMap::<Test>::get(&key).unwrap().pool_account, sub_account);
+		assert_ok!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(1), asset(1), NATIVE, FEE, 1_000, 900, None));
+	});
+}
+
+#[test]
+fn unversioned_pools_are_migrated_to_pool_records_and_sub_accounts() {
+	use crate::migrations::{v0, v1::MigrateToPoolRecords, v2::MigrateToSubAccounts};
+	use frame_support::traits::{
+		fungible::Mutate,
+		fungibles::{Create, Mutate as FSMutate},
+		tokens::Preservation,
+		OnRuntimeUpgrade,
+	};
+
+	new_test_ext().execute_with(|| {
+		assert_ok!(Dex::setup_account(1, 1_000_000, vec![(1, 1_000_000), (2, 1_000_000)]));
+
+		// Emulates a pool of the pair (2, 1) created before the pallet had a storage version
+		let legacy = v0::pool_account::<Test>(&2, &1).unwrap();
+		let lp_token = v0::liquidity_id::<Test>(&2, &1).unwrap();
+		assert_ok!(<Balance as Mutate<_>>::transfer(&1, &legacy, 1_000, Preservation::Expendable));
+		assert_ok!(<Assets as FSMutate<_>>::transfer(1, &1, &legacy, 10_000, Preservation::Expendable));
+		assert_ok!(<Assets as FSMutate<_>>::transfer(2, &1, &legacy, 40_000, Preservation::Expendable));
+		assert_ok!(<Assets as Create<_>>::create(lp_token, Dex::account_id(), false, 1));
+		assert_ok!(<Assets as FSMutate<_>>::mint_into(lp_token, &1, 20_000));
+		v0::LiquidityPoolMap::<Test>::insert((2, 1), legacy);
+		StorageVersion::new(0).put::<Dex>();
+
+		<(MigrateToPoolRecords<Test>, MigrateToSubAccounts<Test>) as OnRuntimeUpgrade>::on_runtime_upgrade();
+		assert_eq!(Dex::on_chain_storage_version(), 2);
+
+		// The pool is a record under the default fee tier, at its sub-account, with the balances as reserves
+		let pool = Dex::liquidity_pool((asset(2), asset(1), FEE)).unwrap();
+		assert_eq!(pool.pool_account, Dex::generate_account_from_asset_id_pair(asset(2), asset(1), FEE));
+		assert_eq!((pool.reserve_a, pool.reserve_b), (40_000, 10_000));
+		assert_eq!(pool.liquidity_id, lp_token);
+		assert_eq!(Assets::balance(2, &pool.pool_account), 40_000);
+		assert_eq!(Assets::balance(1, &legacy), 0);
+		assert_ok!(Dex::do_try_state());
+
+		// The migrated pool swaps like any other
+		assert_ok!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 1_000, 3_000, None));
+		assert_ok!(Dex::do_try_state());
+
+		// try_state catches a pool with an empty reserve
+		LiquidityPoolMap::<Test>::mutate((asset(2), asset(1), FEE), |pool| pool.as_mut().unwrap().reserve_b = 0);
+		assert!(Dex::do_try_state().is_err());
+	});
+}
+
+#[test]
+fn a_migrated_pool_is_removed_with_its_last_lp_token() {
+	use crate::migrations::{v0, v1::MigrateToPoolRecords, v2::MigrateToSubAccounts};
+	use frame_support::traits::{
+		fungible::Mutate,
+		fungibles::{Create, Mutate as FSMutate},
+		tokens::Preservation,
+		OnRuntimeUpgrade,
+	};
+
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Dex::setup_account(1, 1_000_000, vec![(1, 1_000_000), (2, 1_000_000)]));
+
+		// Emulates a pool of the pair (2, 1) created before the pallet had a storage version, without any
+		// locked liquidity
+		let legacy = v0::pool_account::<Test>(&2, &1).unwrap();
+		let lp_token = v0::liquidity_id::<Test>(&2, &1).unwrap();
+		assert_ok!(<Balance as Mutate<_>>::transfer(&1, &legacy, 1_000, Preservation::Expendable));
+		assert_ok!(<Assets as FSMutate<_>>::transfer(1, &1, &legacy, 10_000, Preservation::Expendable));
+		assert_ok!(<Assets as FSMutate<_>>::transfer(2, &1, &legacy, 40_000, Preservation::Expendable));
+		assert_ok!(<Assets as Create<_>>::create(lp_token, Dex::account_id(), false, 1));
+		assert_ok!(<Assets as FSMutate<_>>::mint_into(lp_token, &1, 20_000));
+		v0::LiquidityPoolMap::<Test>::insert((2, 1), legacy);
+		StorageVersion::new(0).put::<Dex>();
+		<(MigrateToPoolRecords<Test>, MigrateToSubAccounts<Test>) as OnRuntimeUpgrade>::on_runtime_upgrade();
+
+		// Part of the LP tokens leaves the pool in place
+		assert_ok!(Dex::remove_liquidity(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 0, 0, 5_000, None));
+		assert!(Dex::liquidity_pool((asset(2), asset(1), FEE)).is_some());
+		assert_ok!(Dex::do_try_state());
+
+		// The last LP tokens take the whole reserves and the pool is removed with them
+		assert_ok!(Dex::remove_liquidity(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 0, 0, 15_000, None));
+		System::assert_last_event(
+			Event::PoolRemoved {
+				who: 1,
+				pool_id: lp_token,
+				asset_a: asset(2),
+				asset_b: asset(1),
+				fee: FEE,
+				amount_liq: 0,
+				amount_a: 0,
+				amount_b: 0,
+			}
+			.into(),
+		);
+		assert!(Dex::liquidity_pool((asset(2), asset(1), FEE)).is_none());
+		assert!(!Assets::asset_exists(lp_token));
+		assert_eq!(Assets::balance(1, &1), 1_000_000);
+		assert_eq!(Assets::balance(2, &1), 1_000_000);
+		assert_eq!(Balance::balance(&1), 1_000_000);
+		assert_ok!(Dex::do_try_state());
+
+		// The pair can be pooled again
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 100_000, 100_000));
+		assert_ok!(Dex::do_try_state());
+	});
+}
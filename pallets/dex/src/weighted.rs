@@ -0,0 +1,163 @@
+//! The weighted-product math of Balancer for pools of more than two assets with arbitrary normalized
+//! weights. A weighted pool keeps `prod(balance_i ^ weight_i)` constant during swaps.
+//!
+//! The powers have fractional exponents, so they are computed as `exp(y * ln(x))` in fixed point with
+//! 18 decimals. `ln` and `mul_exp` round down at every step, which can work against the pool, so what
+//! the pool pays out or mints is lowered by a margin that grows with the amounts it is computed from.
+
+use sp_arithmetic::{
+	helpers_128bit::multiply_by_rational_with_rounding, per_things::Rounding, PerThing, Perbill, Permill,
+};
+use sp_core::U256;
+
+/// 1 in the fixed point representation of this module.
+const ONE: u128 = 1_000_000_000_000_000_000;
+
+/// ln(2) in the fixed point representation of this module.
+const LN_2: i128 = 693_147_180_559_945_309;
+
+/// The powers come out at most about 5e-16 of `balance_out` or `supply` above the exact result, for
+/// weight ratios up to the 99 allowed by the 1% minimum weight of the pallet. The results are lowered by
+/// `1 / ROUNDING_MARGIN` of that amount plus one unit.
+const ROUNDING_MARGIN: u128 = 100_000_000_000_000;
+
+/// Returns `prod(balance_i ^ weight_i)` of the balances of a pool, it is used as the initial supply of
+/// the LP token of the pool.
+pub fn invariant(balances: &[(u128, Perbill)]) -> Option<u128> {
+	let mut exponent = 0i128;
+	for (balance, weight) in balances {
+		exponent = exponent.checked_add(mul_weight_ratio(ln(*balance)?, *weight, Perbill::one())?)?;
+	}
+	mul_exp(1, exponent)
+}
+
+/// Calculates the amount of the asset with `balance_out` and `weight_out` the pool pays out for `amount_in`
+/// of the asset with `balance_in` and `weight_in`, the fee is taken from `amount_in`:
+/// `balance_out * (1 - (balance_in / (balance_in + amount_in))^(weight_in / weight_out))`.
+pub fn amount_out(
+	balance_in: u128,
+	weight_in: Perbill,
+	balance_out: u128,
+	weight_out: Perbill,
+	amount_in: u128,
+	fee: Permill,
+) -> Option<u128> {
+	let amount_in = fee.left_from_one() * amount_in;
+	let ln_ratio = ln(balance_in)?.checked_sub(ln(balance_in.checked_add(amount_in)?)?)?;
+	let exponent = mul_weight_ratio(ln_ratio, weight_in, weight_out)?;
+	let amount_out = balance_out.checked_sub(mul_exp(balance_out, exponent)?)?;
+	Some(amount_out.saturating_sub(rounding_margin(balance_out)))
+}
+
+/// Calculates the LP tokens minted for depositing only `amount_in` of the asset with `balance_in` and
+/// `weight_in`: `supply * ((1 + amount_in / balance_in)^weight_in - 1)`. The part of the deposit that
+/// isn't matched by the other assets of the pool is a swap, so the fee is taken from it.
+pub fn liquidity_out_for_join(
+	balance_in: u128,
+	weight_in: Perbill,
+	supply: u128,
+	amount_in: u128,
+	fee: Permill,
+) -> Option<u128> {
+	let amount_in = amount_in.checked_sub(fee_on_unmatched(amount_in, weight_in, fee))?;
+	let ln_ratio = ln(balance_in.checked_add(amount_in)?)?.checked_sub(ln(balance_in)?)?;
+	let exponent = mul_weight_ratio(ln_ratio, weight_in, Perbill::one())?;
+	let amount_liq = mul_exp(supply, exponent)?.checked_sub(supply)?;
+	Some(amount_liq.saturating_sub(rounding_margin(supply)))
+}
+
+/// Calculates the amount of the asset with `balance_out` and `weight_out` paid out for burning `amount_liq`
+/// of the `supply` LP tokens: `balance_out * (1 - (1 - amount_liq / supply)^(1 / weight_out))`. The part
+/// of the withdrawal that isn't matched by the other assets of the pool is a swap, so the fee is taken from it.
+pub fn amount_out_for_exit(
+	balance_out: u128,
+	weight_out: Perbill,
+	supply: u128,
+	amount_liq: u128,
+	fee: Permill,
+) -> Option<u128> {
+	let ln_ratio = ln(supply.checked_sub(amount_liq)?)?.checked_sub(ln(supply)?)?;
+	let exponent = mul_weight_ratio(ln_ratio, Perbill::one(), weight_out)?;
+	let amount_out = balance_out.checked_sub(mul_exp(balance_out, exponent)?)?;
+	let amount_out = amount_out.saturating_sub(rounding_margin(balance_out));
+	amount_out.checked_sub(fee_on_unmatched(amount_out, weight_out, fee))
+}
+
+/// The amount kept from a result computed from `value`, so the rounding of the powers never works
+/// against the pool.
+fn rounding_margin(value: u128) -> u128 {
+	value / ROUNDING_MARGIN + 1
+}
+
+/// The fee on the part of a single asset deposit or withdrawal that isn't matched by the other assets,
+/// `amount * (1 - weight) * fee`.
+fn fee_on_unmatched(amount: u128, weight: Perbill, fee: Permill) -> u128 {
+	weight.left_from_one() * (fee * amount)
+}
+
+/// Multiplies a fixed point number by `numerator / denominator`, rounded towards zero.
+fn mul_weight_ratio(value: i128, numerator: Perbill, denominator: Perbill) -> Option<i128> {
+	let abs = multiply_by_rational_with_rounding(
+		value.unsigned_abs(),
+		numerator.deconstruct().into(),
+		denominator.deconstruct().into(),
+		Rounding::Down,
+	)?;
+	let abs = i128::try_from(abs).ok()?;
+	Some(if value < 0 { -abs } else { abs })
+}
+
+/// Returns the natural logarithm of a positive integer in fixed point, rounded down.
+fn ln(value: u128) -> Option<i128> {
+	if value == 0 {
+		return None
+	}
+
+	// value = m * 2^k with 1 <= m < 2
+	let k = 127 - value.leading_zeros();
+	let m: u128 = ((U256::from(value) * U256::from(ONE)) >> k as usize).try_into().ok()?;
+
+	// ln(m) = 2 * (z + z^3 / 3 + z^5 / 5 + ...) with z = (m - 1) / (m + 1) < 1 / 3
+	let z = multiply_by_rational_with_rounding(m - ONE, ONE, m + ONE, Rounding::Down)?;
+	let z_squared = multiply_by_rational_with_rounding(z, z, ONE, Rounding::Down)?;
+	let (mut term, mut sum, mut i) = (z, 0u128, 1u128);
+	while term > 0 {
+		sum += term / i;
+		term = multiply_by_rational_with_rounding(term, z_squared, ONE, Rounding::Down)?;
+		i += 2;
+	}
+
+	i128::from(k).checked_mul(LN_2)?.checked_add(i128::try_from(2 * sum).ok()?)
+}
+
+/// Returns `value * exp(exponent)` for an exponent in fixed point, rounded down.
+fn mul_exp(value: u128, exponent: i128) -> Option<u128> {
+	// exp(exponent) = 2^k * exp(r) with 0 <= r < ln(2)
+	let k = exponent.div_euclid(LN_2);
+	let r = exponent.rem_euclid(LN_2) as u128;
+
+	// exp(r) = 1 + r + r^2 / 2! + r^3 / 3! + ...
+	let (mut term, mut exp_r, mut i) = (ONE, ONE, 1u128);
+	while term > 0 {
+		term = multiply_by_rational_with_rounding(term, r, ONE.checked_mul(i)?, Rounding::Down)?;
+		exp_r += term;
+		i += 1;
+	}
+
+	let product = U256::from(value) * U256::from(exp_r);
+	let scaled = if k >= 0 {
+		let shift = u32::try_from(k).ok()?;
+		if product.leading_zeros() <= shift {
+			return None
+		}
+		product << shift as usize
+	} else {
+		let shift = k.unsigned_abs();
+		if shift >= 256 {
+			U256::zero()
+		} else {
+			product >> shift as usize
+		}
+	};
+	(scaled / U256::from(ONE)).try_into().ok()
+}
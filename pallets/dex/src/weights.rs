@@ -0,0 +1,634 @@
+
+//! Weights for pallet_dex
+//!
+//! These weights are estimates written by hand, they have not been generated by the benchmark
+//! CLI. The storage accesses follow the benchmarks in `benchmarking.rs`, the execution times and
+//! proof sizes are rough figures. Replace the file with the output of `benchmark pallet` on
+//! reference hardware before relying on it in production.
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use core::marker::PhantomData;
+
+/// Weight functions needed for pallet_dex.
+pub trait WeightInfo {
+	fn create_pool() -> Weight;
+	fn add_liquidity() -> Weight;
+	fn remove_liquidity() -> Weight;
+	fn remove_pool() -> Weight;
+	fn swap_exact_in_for_out() -> Weight;
+	fn swap_in_for_exact_out() -> Weight;
+	fn swap_exact_in_for_out_via_path(n: u32, ) -> Weight;
+	fn swap_in_for_exact_out_via_path(n: u32, ) -> Weight;
+	fn skim() -> Weight;
+	fn sync() -> Weight;
+	fn set_protocol_fee() -> Weight;
+	fn set_fee_tiers() -> Weight;
+	fn flash_swap() -> Weight;
+	fn create_stable_pool() -> Weight;
+	fn create_weighted_pool(n: u32, ) -> Weight;
+	fn join_weighted_pool() -> Weight;
+	fn exit_weighted_pool() -> Weight;
+	fn swap_weighted() -> Weight;
+	fn create_concentrated_pool() -> Weight;
+	fn mint_position() -> Weight;
+	fn decrease_liquidity() -> Weight;
+	fn collect() -> Weight;
+	fn swap_concentrated(n: u32, ) -> Weight;
+	fn place_limit_order() -> Weight;
+	fn cancel_limit_order() -> Weight;
+	fn fill_order() -> Weight;
+	fn set_batch_mode() -> Weight;
+	fn submit_swap_intent() -> Weight;
+	fn clear_swap_batch(n: u32, ) -> Weight;
+	fn pause_pool() -> Weight;
+	fn unpause_pool() -> Weight;
+	fn pause_all() -> Weight;
+	fn unpause_all() -> Weight;
+	fn pause_weighted_pool() -> Weight;
+	fn unpause_weighted_pool() -> Weight;
+	fn pause_concentrated_pool() -> Weight;
+	fn unpause_concentrated_pool() -> Weight;
+	fn claim_swap_intent_refund() -> Weight;
+}
+
+/// Weights for pallet_dex estimated by hand, see the module docs.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	/// Storage: Dex FeeTiers (r:1 w:0)
+	/// Storage: Dex LiquidityPoolMap (r:1 w:1)
+	/// Storage: Dex NextLpAssetId (r:1 w:1)
+	/// Storage: Assets Asset (r:18 w:3)
+	/// Storage: Assets Metadata (r:1 w:1)
+	/// Storage: Assets Account (r:6 w:6)
+	/// Storage: System Account (r:3 w:3)
+	/// Storage: Dex ProtocolFeeShare (r:1 w:0)
+	/// Storage: Dex PriceObservations (r:1 w:1)
+	/// The LP token id is the last of the `LP_ASSET_ID_PROBES` ids looked at.
+	fn create_pool() -> Weight {
+		Weight::from_parts(171_000_000, 64_000)
+			.saturating_add(T::DbWeight::get().reads(33_u64))
+			.saturating_add(T::DbWeight::get().writes(16_u64))
+	}
+	/// Storage: Dex LiquidityPoolMap (r:1 w:1)
+	/// Storage: Assets Asset (r:3 w:3)
+	/// Storage: Assets Account (r:5 w:5)
+	/// Storage: Dex PriceObservations (r:1 w:1)
+	fn add_liquidity() -> Weight {
+		Weight::from_parts(96_000_000, 14_000)
+			.saturating_add(T::DbWeight::get().reads(10_u64))
+			.saturating_add(T::DbWeight::get().writes(10_u64))
+	}
+	/// Storage: Dex LiquidityPoolMap (r:1 w:1)
+	/// Storage: Assets Asset (r:3 w:3)
+	/// Storage: Assets Account (r:5 w:5)
+	/// Storage: Dex PriceObservations (r:1 w:1)
+	fn remove_liquidity() -> Weight {
+		Weight::from_parts(94_000_000, 14_000)
+			.saturating_add(T::DbWeight::get().reads(10_u64))
+			.saturating_add(T::DbWeight::get().writes(10_u64))
+	}
+	/// Storage: Dex LiquidityPoolMap (r:1 w:1)
+	/// Storage: Assets Asset (r:3 w:3)
+	/// Storage: Assets Account (r:7 w:6)
+	/// Storage: Assets Approvals (r:1 w:0)
+	/// Storage: Assets Metadata (r:1 w:1)
+	/// Storage: System Account (r:3 w:3)
+	/// Storage: Dex PriceObservations (r:0 w:1)
+	/// Storage: Dex BatchModePools (r:0 w:1)
+	/// Storage: Dex PausedPools (r:0 w:1)
+	fn remove_pool() -> Weight {
+		Weight::from_parts(146_000_000, 24_000)
+			.saturating_add(T::DbWeight::get().reads(16_u64))
+			.saturating_add(T::DbWeight::get().writes(17_u64))
+	}
+	/// Storage: Dex LiquidityPoolMap (r:1 w:1)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:4 w:4)
+	/// Storage: Dex PriceObservations (r:1 w:1)
+	fn swap_exact_in_for_out() -> Weight {
+		Weight::from_parts(78_000_000, 11_000)
+			.saturating_add(T::DbWeight::get().reads(8_u64))
+			.saturating_add(T::DbWeight::get().writes(8_u64))
+	}
+	/// Storage: Dex LiquidityPoolMap (r:1 w:1)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:4 w:4)
+	/// Storage: Dex PriceObservations (r:1 w:1)
+	fn swap_in_for_exact_out() -> Weight {
+		Weight::from_parts(79_000_000, 11_000)
+			.saturating_add(T::DbWeight::get().reads(8_u64))
+			.saturating_add(T::DbWeight::get().writes(8_u64))
+	}
+	/// Storage: Dex LiquidityPoolMap (r:3 w:3)
+	/// Storage: Assets Asset (r:4 w:4)
+	/// Storage: Assets Account (r:8 w:8)
+	/// Storage: Dex PriceObservations (r:3 w:3)
+	/// The range of component `n` is `[2, 4]`.
+	fn swap_exact_in_for_out_via_path(n: u32, ) -> Weight {
+		Weight::from_parts(22_000_000, 3_000)
+			.saturating_add(Weight::from_parts(61_000_000, 0).saturating_mul(n.into()))
+			.saturating_add(Weight::from_parts(0, 5_500).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads((5_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes((5_u64).saturating_mul(n.into())))
+	}
+	/// Storage: Dex LiquidityPoolMap (r:3 w:3)
+	/// Storage: Assets Asset (r:4 w:4)
+	/// Storage: Assets Account (r:8 w:8)
+	/// Storage: Dex PriceObservations (r:3 w:3)
+	/// The range of component `n` is `[2, 4]`.
+	fn swap_in_for_exact_out_via_path(n: u32, ) -> Weight {
+		Weight::from_parts(23_000_000, 3_000)
+			.saturating_add(Weight::from_parts(62_000_000, 0).saturating_mul(n.into()))
+			.saturating_add(Weight::from_parts(0, 5_500).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads((5_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes((5_u64).saturating_mul(n.into())))
+	}
+	/// Storage: Dex LiquidityPoolMap (r:1 w:0)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:4 w:4)
+	fn skim() -> Weight {
+		Weight::from_parts(61_000_000, 9_000)
+			.saturating_add(T::DbWeight::get().reads(7_u64))
+			.saturating_add(T::DbWeight::get().writes(6_u64))
+	}
+	/// Storage: Dex LiquidityPoolMap (r:1 w:1)
+	/// Storage: Assets Account (r:2 w:0)
+	/// Storage: Dex PriceObservations (r:1 w:1)
+	fn sync() -> Weight {
+		Weight::from_parts(32_000_000, 6_000)
+			.saturating_add(T::DbWeight::get().reads(4_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+	/// Storage: Dex ProtocolFeeRecipient (r:0 w:1)
+	/// Storage: Dex ProtocolFeeShare (r:0 w:1)
+	fn set_protocol_fee() -> Weight {
+		Weight::from_parts(12_000_000, 0)
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+	/// Storage: Dex FeeTiers (r:0 w:1)
+	fn set_fee_tiers() -> Weight {
+		Weight::from_parts(11_000_000, 0)
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Dex LiquidityPoolMap (r:2 w:1)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:4 w:4)
+	/// Storage: Dex PriceObservations (r:1 w:1)
+	fn flash_swap() -> Weight {
+		Weight::from_parts(84_000_000, 11_000)
+			.saturating_add(T::DbWeight::get().reads(9_u64))
+			.saturating_add(T::DbWeight::get().writes(8_u64))
+	}
+	/// Storage: Dex FeeTiers (r:1 w:0)
+	/// Storage: Dex LiquidityPoolMap (r:1 w:1)
+	/// Storage: Dex NextLpAssetId (r:1 w:1)
+	/// Storage: Assets Asset (r:18 w:3)
+	/// Storage: Assets Metadata (r:1 w:1)
+	/// Storage: Assets Account (r:6 w:6)
+	/// Storage: System Account (r:3 w:3)
+	/// Storage: Dex ProtocolFeeShare (r:1 w:0)
+	/// Storage: Dex PriceObservations (r:1 w:1)
+	/// The LP token id is the last of the `LP_ASSET_ID_PROBES` ids looked at.
+	fn create_stable_pool() -> Weight {
+		Weight::from_parts(184_000_000, 64_000)
+			.saturating_add(T::DbWeight::get().reads(33_u64))
+			.saturating_add(T::DbWeight::get().writes(16_u64))
+	}
+	/// Storage: Dex FeeTiers (r:1 w:0)
+	/// Storage: Dex NextWeightedPoolId (r:1 w:1)
+	/// Storage: Dex WeightedPools (r:0 w:1)
+	/// Storage: Dex NextLpAssetId (r:1 w:1)
+	/// Storage: Assets Asset (r:24 w:9)
+	/// Storage: Assets Metadata (r:1 w:1)
+	/// Storage: Assets Account (r:18 w:18)
+	/// Storage: System Account (r:2 w:2)
+	/// The range of component `n` is `[2, 8]`.
+	/// The LP token id is the last of the `LP_ASSET_ID_PROBES` ids looked at.
+	fn create_weighted_pool(n: u32, ) -> Weight {
+		Weight::from_parts(114_000_000, 57_000)
+			.saturating_add(Weight::from_parts(58_000_000, 0).saturating_mul(n.into()))
+			.saturating_add(Weight::from_parts(0, 5_200).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(26_u64))
+			.saturating_add(T::DbWeight::get().reads((3_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes(10_u64))
+			.saturating_add(T::DbWeight::get().writes((3_u64).saturating_mul(n.into())))
+	}
+	/// Storage: Dex WeightedPools (r:1 w:1)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:3 w:3)
+	fn join_weighted_pool() -> Weight {
+		Weight::from_parts(92_000_000, 12_000)
+			.saturating_add(T::DbWeight::get().reads(6_u64))
+			.saturating_add(T::DbWeight::get().writes(6_u64))
+	}
+	/// Storage: Dex WeightedPools (r:1 w:1)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:3 w:3)
+	fn exit_weighted_pool() -> Weight {
+		Weight::from_parts(94_000_000, 12_000)
+			.saturating_add(T::DbWeight::get().reads(6_u64))
+			.saturating_add(T::DbWeight::get().writes(6_u64))
+	}
+	/// Storage: Dex WeightedPools (r:1 w:1)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:4 w:4)
+	fn swap_weighted() -> Weight {
+		Weight::from_parts(88_000_000, 11_000)
+			.saturating_add(T::DbWeight::get().reads(7_u64))
+			.saturating_add(T::DbWeight::get().writes(7_u64))
+	}
+	/// Storage: Dex FeeTiers (r:1 w:0)
+	/// Storage: Dex NextConcentratedPoolId (r:1 w:1)
+	/// Storage: Dex ConcentratedPools (r:0 w:1)
+	/// Storage: System Account (r:2 w:2)
+	fn create_concentrated_pool() -> Weight {
+		Weight::from_parts(412_000_000, 7_000)
+			.saturating_add(T::DbWeight::get().reads(4_u64))
+			.saturating_add(T::DbWeight::get().writes(4_u64))
+	}
+	/// Storage: Dex ConcentratedPools (r:1 w:1)
+	/// Storage: Dex Ticks (r:2 w:2)
+	/// Storage: Dex InitializedTicks (r:1 w:1)
+	/// Storage: Dex NextPositionId (r:1 w:1)
+	/// Storage: Dex Positions (r:0 w:1)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:4 w:4)
+	fn mint_position() -> Weight {
+		Weight::from_parts(104_000_000, 14_000)
+			.saturating_add(T::DbWeight::get().reads(11_u64))
+			.saturating_add(T::DbWeight::get().writes(12_u64))
+	}
+	/// Storage: Dex Positions (r:1 w:1)
+	/// Storage: Dex ConcentratedPools (r:1 w:1)
+	/// Storage: Dex Ticks (r:2 w:2)
+	/// Storage: Dex InitializedTicks (r:1 w:1)
+	fn decrease_liquidity() -> Weight {
+		Weight::from_parts(61_000_000, 8_000)
+			.saturating_add(T::DbWeight::get().reads(5_u64))
+			.saturating_add(T::DbWeight::get().writes(5_u64))
+	}
+	/// Storage: Dex Positions (r:1 w:1)
+	/// Storage: Dex ConcentratedPools (r:1 w:0)
+	/// Storage: Dex Ticks (r:2 w:0)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:4 w:4)
+	fn collect() -> Weight {
+		Weight::from_parts(83_000_000, 12_000)
+			.saturating_add(T::DbWeight::get().reads(10_u64))
+			.saturating_add(T::DbWeight::get().writes(7_u64))
+	}
+	/// Storage: Dex ConcentratedPools (r:1 w:1)
+	/// Storage: Dex InitializedTicks (r:1 w:0)
+	/// Storage: Dex Ticks (r:100 w:100)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:4 w:4)
+	/// The range of component `n` is `[1, 100]`.
+	fn swap_concentrated(n: u32, ) -> Weight {
+		Weight::from_parts(96_000_000, 12_000)
+			.saturating_add(Weight::from_parts(38_000_000, 0).saturating_mul(n.into()))
+			.saturating_add(Weight::from_parts(0, 2_600).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(8_u64))
+			.saturating_add(T::DbWeight::get().reads((1_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes(7_u64))
+			.saturating_add(T::DbWeight::get().writes((1_u64).saturating_mul(n.into())))
+	}
+	/// Storage: Dex LiquidityPoolMap (r:1 w:0)
+	/// Storage: Dex BatchModePools (r:1 w:0)
+	/// Storage: Dex PausedPools (r:1 w:0)
+	/// Storage: Dex GlobalPause (r:1 w:0)
+	/// Storage: Dex OpenOrdersOf (r:1 w:1)
+	/// Storage: Dex NextOrderId (r:1 w:1)
+	/// Storage: Dex LimitOrders (r:0 w:1)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Assets Account (r:2 w:2)
+	/// Storage: System Account (r:1 w:1)
+	fn place_limit_order() -> Weight {
+		Weight::from_parts(64_000_000, 9_500)
+			.saturating_add(T::DbWeight::get().reads(10_u64))
+			.saturating_add(T::DbWeight::get().writes(7_u64))
+	}
+	/// Storage: Dex LimitOrders (r:1 w:1)
+	/// Storage: Dex OpenOrdersOf (r:1 w:1)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Assets Account (r:2 w:2)
+	fn cancel_limit_order() -> Weight {
+		Weight::from_parts(44_000_000, 7_000)
+			.saturating_add(T::DbWeight::get().reads(5_u64))
+			.saturating_add(T::DbWeight::get().writes(5_u64))
+	}
+	/// Storage: Dex LimitOrders (r:1 w:1)
+	/// Storage: Dex LiquidityPoolMap (r:1 w:1)
+	/// Storage: Dex PriceObservations (r:1 w:1)
+	/// Storage: Dex OpenOrdersOf (r:1 w:1)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:5 w:5)
+	fn fill_order() -> Weight {
+		Weight::from_parts(97_000_000, 13_000)
+			.saturating_add(T::DbWeight::get().reads(11_u64))
+			.saturating_add(T::DbWeight::get().writes(11_u64))
+	}
+	/// Storage: Dex LiquidityPoolMap (r:1 w:0)
+	/// Storage: Dex BatchModePools (r:0 w:1)
+	fn set_batch_mode() -> Weight {
+		Weight::from_parts(19_000_000, 4_000)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Dex LiquidityPoolMap (r:1 w:0)
+	/// Storage: Dex BatchModePools (r:1 w:0)
+	/// Storage: Dex SwapIntents (r:1 w:1)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Assets Account (r:2 w:2)
+	/// Storage: System Account (r:1 w:1)
+	fn submit_swap_intent() -> Weight {
+		Weight::from_parts(56_000_000, 9_000)
+			.saturating_add(T::DbWeight::get().reads(7_u64))
+			.saturating_add(T::DbWeight::get().writes(5_u64))
+	}
+	/// Storage: Dex SwapIntents (r:2 w:1)
+	/// Storage: Dex LiquidityPoolMap (r:1 w:1)
+	/// Storage: Dex PriceObservations (r:1 w:1)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:68 w:68)
+	/// The range of component `n` is `[1, 64]`.
+	/// The batch is cleared twice on the StableSwap curve, half of the intents are paid back after the first time.
+	fn clear_swap_batch(n: u32, ) -> Weight {
+		Weight::from_parts(312_000_000, 10_000)
+			.saturating_add(Weight::from_parts(23_000_000, 0).saturating_mul(n.into()))
+			.saturating_add(Weight::from_parts(0, 2_600).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(10_u64))
+			.saturating_add(T::DbWeight::get().reads((1_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes(9_u64))
+			.saturating_add(T::DbWeight::get().writes((1_u64).saturating_mul(n.into())))
+	}
+	/// Storage: Dex LiquidityPoolMap (r:1 w:0)
+	/// Storage: Dex PausedPools (r:0 w:1)
+	fn pause_pool() -> Weight {
+		Weight::from_parts(18_000_000, 4_000)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Dex LiquidityPoolMap (r:1 w:0)
+	/// Storage: Dex PausedPools (r:0 w:1)
+	fn unpause_pool() -> Weight {
+		Weight::from_parts(18_000_000, 4_000)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Dex GlobalPause (r:0 w:1)
+	fn pause_all() -> Weight {
+		Weight::from_parts(9_000_000, 1_000)
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Dex GlobalPause (r:0 w:1)
+	fn unpause_all() -> Weight {
+		Weight::from_parts(9_000_000, 1_000)
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Dex WeightedPools (r:1 w:0)
+	/// Storage: Dex PausedWeightedPools (r:0 w:1)
+	fn pause_weighted_pool() -> Weight {
+		Weight::from_parts(18_000_000, 4_000)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Dex WeightedPools (r:1 w:0)
+	/// Storage: Dex PausedWeightedPools (r:0 w:1)
+	fn unpause_weighted_pool() -> Weight {
+		Weight::from_parts(18_000_000, 4_000)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Dex ConcentratedPools (r:1 w:0)
+	/// Storage: Dex PausedConcentratedPools (r:0 w:1)
+	fn pause_concentrated_pool() -> Weight {
+		Weight::from_parts(18_000_000, 4_000)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Dex ConcentratedPools (r:1 w:0)
+	/// Storage: Dex PausedConcentratedPools (r:0 w:1)
+	fn unpause_concentrated_pool() -> Weight {
+		Weight::from_parts(18_000_000, 4_000)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Dex UnclaimedRefunds (r:1 w:1)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Assets Account (r:2 w:2)
+	fn claim_swap_intent_refund() -> Weight {
+		Weight::from_parts(38_000_000, 6_000)
+			.saturating_add(T::DbWeight::get().reads(4_u64))
+			.saturating_add(T::DbWeight::get().writes(4_u64))
+	}
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+	fn create_pool() -> Weight {
+		Weight::from_parts(171_000_000, 64_000)
+			.saturating_add(RocksDbWeight::get().reads(33_u64))
+			.saturating_add(RocksDbWeight::get().writes(16_u64))
+	}
+	fn add_liquidity() -> Weight {
+		Weight::from_parts(96_000_000, 14_000)
+			.saturating_add(RocksDbWeight::get().reads(10_u64))
+			.saturating_add(RocksDbWeight::get().writes(10_u64))
+	}
+	fn remove_liquidity() -> Weight {
+		Weight::from_parts(94_000_000, 14_000)
+			.saturating_add(RocksDbWeight::get().reads(10_u64))
+			.saturating_add(RocksDbWeight::get().writes(10_u64))
+	}
+	fn remove_pool() -> Weight {
+		Weight::from_parts(146_000_000, 24_000)
+			.saturating_add(RocksDbWeight::get().reads(16_u64))
+			.saturating_add(RocksDbWeight::get().writes(17_u64))
+	}
+	fn swap_exact_in_for_out() -> Weight {
+		Weight::from_parts(78_000_000, 11_000)
+			.saturating_add(RocksDbWeight::get().reads(8_u64))
+			.saturating_add(RocksDbWeight::get().writes(8_u64))
+	}
+	fn swap_in_for_exact_out() -> Weight {
+		Weight::from_parts(79_000_000, 11_000)
+			.saturating_add(RocksDbWeight::get().reads(8_u64))
+			.saturating_add(RocksDbWeight::get().writes(8_u64))
+	}
+	fn swap_exact_in_for_out_via_path(n: u32, ) -> Weight {
+		Weight::from_parts(22_000_000, 3_000)
+			.saturating_add(Weight::from_parts(61_000_000, 0).saturating_mul(n.into()))
+			.saturating_add(Weight::from_parts(0, 5_500).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads((5_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().writes((5_u64).saturating_mul(n.into())))
+	}
+	fn swap_in_for_exact_out_via_path(n: u32, ) -> Weight {
+		Weight::from_parts(23_000_000, 3_000)
+			.saturating_add(Weight::from_parts(62_000_000, 0).saturating_mul(n.into()))
+			.saturating_add(Weight::from_parts(0, 5_500).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads((5_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().writes((5_u64).saturating_mul(n.into())))
+	}
+	fn skim() -> Weight {
+		Weight::from_parts(61_000_000, 9_000)
+			.saturating_add(RocksDbWeight::get().reads(7_u64))
+			.saturating_add(RocksDbWeight::get().writes(6_u64))
+	}
+	fn sync() -> Weight {
+		Weight::from_parts(32_000_000, 6_000)
+			.saturating_add(RocksDbWeight::get().reads(4_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+	fn set_protocol_fee() -> Weight {
+		Weight::from_parts(12_000_000, 0)
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+	fn set_fee_tiers() -> Weight {
+		Weight::from_parts(11_000_000, 0)
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	fn flash_swap() -> Weight {
+		Weight::from_parts(84_000_000, 11_000)
+			.saturating_add(RocksDbWeight::get().reads(9_u64))
+			.saturating_add(RocksDbWeight::get().writes(8_u64))
+	}
+	fn create_stable_pool() -> Weight {
+		Weight::from_parts(184_000_000, 64_000)
+			.saturating_add(RocksDbWeight::get().reads(33_u64))
+			.saturating_add(RocksDbWeight::get().writes(16_u64))
+	}
+	fn create_weighted_pool(n: u32, ) -> Weight {
+		Weight::from_parts(114_000_000, 57_000)
+			.saturating_add(Weight::from_parts(58_000_000, 0).saturating_mul(n.into()))
+			.saturating_add(Weight::from_parts(0, 5_200).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads(26_u64))
+			.saturating_add(RocksDbWeight::get().reads((3_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().writes(10_u64))
+			.saturating_add(RocksDbWeight::get().writes((3_u64).saturating_mul(n.into())))
+	}
+	fn join_weighted_pool() -> Weight {
+		Weight::from_parts(92_000_000, 12_000)
+			.saturating_add(RocksDbWeight::get().reads(6_u64))
+			.saturating_add(RocksDbWeight::get().writes(6_u64))
+	}
+	fn exit_weighted_pool() -> Weight {
+		Weight::from_parts(94_000_000, 12_000)
+			.saturating_add(RocksDbWeight::get().reads(6_u64))
+			.saturating_add(RocksDbWeight::get().writes(6_u64))
+	}
+	fn swap_weighted() -> Weight {
+		Weight::from_parts(88_000_000, 11_000)
+			.saturating_add(RocksDbWeight::get().reads(7_u64))
+			.saturating_add(RocksDbWeight::get().writes(7_u64))
+	}
+	fn create_concentrated_pool() -> Weight {
+		Weight::from_parts(412_000_000, 7_000)
+			.saturating_add(RocksDbWeight::get().reads(4_u64))
+			.saturating_add(RocksDbWeight::get().writes(4_u64))
+	}
+	fn mint_position() -> Weight {
+		Weight::from_parts(104_000_000, 14_000)
+			.saturating_add(RocksDbWeight::get().reads(11_u64))
+			.saturating_add(RocksDbWeight::get().writes(12_u64))
+	}
+	fn decrease_liquidity() -> Weight {
+		Weight::from_parts(61_000_000, 8_000)
+			.saturating_add(RocksDbWeight::get().reads(5_u64))
+			.saturating_add(RocksDbWeight::get().writes(5_u64))
+	}
+	fn collect() -> Weight {
+		Weight::from_parts(83_000_000, 12_000)
+			.saturating_add(RocksDbWeight::get().reads(10_u64))
+			.saturating_add(RocksDbWeight::get().writes(7_u64))
+	}
+	fn swap_concentrated(n: u32, ) -> Weight {
+		Weight::from_parts(96_000_000, 12_000)
+			.saturating_add(Weight::from_parts(38_000_000, 0).saturating_mul(n.into()))
+			.saturating_add(Weight::from_parts(0, 2_600).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads(8_u64))
+			.saturating_add(RocksDbWeight::get().reads((1_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().writes(7_u64))
+			.saturating_add(RocksDbWeight::get().writes((1_u64).saturating_mul(n.into())))
+	}
+	fn place_limit_order() -> Weight {
+		Weight::from_parts(64_000_000, 9_500)
+			.saturating_add(RocksDbWeight::get().reads(10_u64))
+			.saturating_add(RocksDbWeight::get().writes(7_u64))
+	}
+	fn cancel_limit_order() -> Weight {
+		Weight::from_parts(44_000_000, 7_000)
+			.saturating_add(RocksDbWeight::get().reads(5_u64))
+			.saturating_add(RocksDbWeight::get().writes(5_u64))
+	}
+	fn fill_order() -> Weight {
+		Weight::from_parts(97_000_000, 13_000)
+			.saturating_add(RocksDbWeight::get().reads(11_u64))
+			.saturating_add(RocksDbWeight::get().writes(11_u64))
+	}
+	fn set_batch_mode() -> Weight {
+		Weight::from_parts(19_000_000, 4_000)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	fn submit_swap_intent() -> Weight {
+		Weight::from_parts(56_000_000, 9_000)
+			.saturating_add(RocksDbWeight::get().reads(7_u64))
+			.saturating_add(RocksDbWeight::get().writes(5_u64))
+	}
+	fn clear_swap_batch(n: u32, ) -> Weight {
+		Weight::from_parts(312_000_000, 10_000)
+			.saturating_add(Weight::from_parts(23_000_000, 0).saturating_mul(n.into()))
+			.saturating_add(Weight::from_parts(0, 2_600).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads(10_u64))
+			.saturating_add(RocksDbWeight::get().reads((1_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().writes(9_u64))
+			.saturating_add(RocksDbWeight::get().writes((1_u64).saturating_mul(n.into())))
+	}
+	fn pause_pool() -> Weight {
+		Weight::from_parts(18_000_000, 4_000)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	fn unpause_pool() -> Weight {
+		Weight::from_parts(18_000_000, 4_000)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	fn pause_all() -> Weight {
+		Weight::from_parts(9_000_000, 1_000)
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	fn unpause_all() -> Weight {
+		Weight::from_parts(9_000_000, 1_000)
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	fn pause_weighted_pool() -> Weight {
+		Weight::from_parts(18_000_000, 4_000)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	fn unpause_weighted_pool() -> Weight {
+		Weight::from_parts(18_000_000, 4_000)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	fn pause_concentrated_pool() -> Weight {
+		Weight::from_parts(18_000_000, 4_000)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	fn unpause_concentrated_pool() -> Weight {
+		Weight::from_parts(18_000_000, 4_000)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	fn claim_swap_intent_refund() -> Weight {
+		Weight::from_parts(38_000_000, 6_000)
+			.saturating_add(RocksDbWeight::get().reads(4_u64))
+			.saturating_add(RocksDbWeight::get().writes(4_u64))
+	}
+}
@@ -0,0 +1,140 @@
+//! Benchmarking setup for pallet-farming
+#![cfg(feature = "runtime-benchmarks")]
+use super::*;
+
+#[allow(unused)]
+use crate::Pallet as Farming;
+use frame_benchmarking::v2::*;
+use frame_support::{assert_ok, dispatch::Vec};
+use frame_system::RawOrigin;
+use pallet_dex::Pallet as Dex;
+
+fn asset<T: Config>(id: u32) -> BalanceType<T>
+where
+	AssetIdOf<T>: From<u32>,
+{
+	BalanceType::AssetBalance(id.into())
+}
+
+// Creates an account holding the native currency and the assets 1 up to 3, with the pool of the assets 1 and 2
+// in the default fee tier. Returns the account.
+fn funded_pool_creator<T: Config>() -> T::AccountId
+where
+	AssetIdOf<T>: From<u32>,
+{
+	let caller: T::AccountId = whitelisted_caller();
+	let balances = (1..=3).map(|id| (id.into(), 1_000_000_000u32.into())).collect::<Vec<_>>();
+	assert_ok!(Dex::<T>::setup_account(caller.clone(), 1_000_000_000u32.into(), balances));
+	assert_ok!(Dex::<T>::create_pool(
+		RawOrigin::Signed(caller.clone()).into(),
+		asset::<T>(1),
+		asset::<T>(2),
+		<T as pallet_dex::Config>::DefaultFeeTier::get(),
+		1_000_000u32.into(),
+		1_000_000u32.into(),
+	));
+	caller
+}
+
+// Creates the farm 0 for the pool of the assets 1 and 2, paying 100 of asset 3 per block for 100 blocks,
+// funded by `who`
+fn create_farm_of<T: Config>(who: &T::AccountId)
+where
+	AssetIdOf<T>: From<u32>,
+{
+	let now = frame_system::Pallet::<T>::block_number();
+	assert_ok!(Farming::<T>::do_create_farm(
+		asset::<T>(1),
+		asset::<T>(2),
+		<T as pallet_dex::Config>::DefaultFeeTier::get(),
+		asset::<T>(3),
+		100u32.into(),
+		now,
+		now + 100u32.into(),
+	));
+	assert_ok!(Farming::<T>::fund_farm(RawOrigin::Signed(who.clone()).into(), 0, 10_000u32.into()));
+}
+
+#[benchmarks(where AssetIdOf<T>: From<u32>)]
+mod benchmarks {
+	use super::*;
+
+	#[benchmark]
+	fn create_farm() -> Result<(), BenchmarkError> {
+		funded_pool_creator::<T>();
+		let origin =
+			<T as pallet_dex::Config>::GovernanceOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let now = frame_system::Pallet::<T>::block_number();
+		let fee = <T as pallet_dex::Config>::DefaultFeeTier::get();
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, asset::<T>(1), asset::<T>(2), fee, asset::<T>(3), 100u32.into(), now, now + 100u32.into());
+
+		assert!(Farms::<T>::contains_key(0));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn fund_farm() {
+		let caller = funded_pool_creator::<T>();
+		create_farm_of::<T>(&caller);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller), 0, 1_000u32.into());
+	}
+
+	#[benchmark]
+	fn stake() {
+		let caller = funded_pool_creator::<T>();
+		create_farm_of::<T>(&caller);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()), 0, 1_000u32.into());
+
+		assert!(Stakes::<T>::contains_key(0, &caller));
+	}
+
+	#[benchmark]
+	fn unstake() {
+		let caller = funded_pool_creator::<T>();
+		create_farm_of::<T>(&caller);
+		assert_ok!(Farming::<T>::do_stake(&caller, 0, 1_000u32.into()));
+		frame_system::Pallet::<T>::set_block_number(frame_system::Pallet::<T>::block_number() + 10u32.into());
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()), 0, 1_000u32.into());
+
+		assert_eq!(Stakes::<T>::get(0, &caller).unwrap().amount, 0u32.into());
+	}
+
+	#[benchmark]
+	fn claim() {
+		let caller = funded_pool_creator::<T>();
+		create_farm_of::<T>(&caller);
+		assert_ok!(Farming::<T>::do_stake(&caller, 0, 1_000u32.into()));
+		frame_system::Pallet::<T>::set_block_number(frame_system::Pallet::<T>::block_number() + 10u32.into());
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()), 0);
+
+		assert!(Stakes::<T>::get(0, &caller).unwrap().rewards.is_zero());
+	}
+
+	#[benchmark]
+	fn close_farm() -> Result<(), BenchmarkError> {
+		// a farm without stakes is removed as well
+		let caller = funded_pool_creator::<T>();
+		create_farm_of::<T>(&caller);
+		frame_system::Pallet::<T>::set_block_number(frame_system::Pallet::<T>::block_number() + 10u32.into());
+		let origin =
+			<T as pallet_dex::Config>::GovernanceOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, 0, caller);
+
+		assert!(!Farms::<T>::contains_key(0));
+		Ok(())
+	}
+
+	impl_benchmark_test_suite!(Farming, crate::mock::new_test_ext(), crate::mock::Test);
+}
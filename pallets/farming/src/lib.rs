@@ -0,0 +1,489 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Liquidity mining for the LP tokens of `pallet-dex`. The governance origin creates a farm for a pool,
+//! which pays a reward asset at a fixed rate per block between a start and an end block. LPs stake the LP
+//! tokens of the pool in the farm and the rewards of every block are shared by the staked amounts.
+
+use frame_support::{pallet_prelude::*, traits::fungibles};
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+pub mod weights;
+pub use weights::*;
+
+type AssetIdOf<T> = <<T as pallet_dex::Config>::Fungibles as fungibles::Inspect<
+	<T as frame_system::Config>::AccountId,
+>>::AssetId;
+
+type AssetBalanceOf<T> = <<T as pallet_dex::Config>::Fungibles as fungibles::Inspect<
+	<T as frame_system::Config>::AccountId,
+>>::Balance;
+
+use sp_runtime::traits::{AccountIdConversion, CheckedAdd, CheckedSub, Saturating, Zero};
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_system::pallet_prelude::*;
+	use pallet_dex::{BalanceType, PoolKeyOf};
+	use sp_runtime::FixedU128;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	/// Configure the pallet by specifying the parameters and types on which it depends.
+	/// The LP tokens, the reward assets and the governance origin are the ones of the dex.
+	#[pallet::config]
+	pub trait Config: frame_system::Config + pallet_dex::Config {
+		/// Because this pallet emits events, it depends on the runtime's definition of an event.
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// The farming pallet's id, every farm holds its staked LP tokens and its rewards in a sub-account of it.
+		#[pallet::constant]
+		type PalletId: Get<frame_support::PalletId>;
+
+		/// Weight information for extrinsics in this pallet.
+		type WeightInfo: WeightInfo;
+	}
+
+	/// Identifies a farm, they are numbered in the order they are created.
+	pub type FarmId = u32;
+
+	/// A reward schedule for the LP tokens of a pool. `reward_per_block` of the reward asset is shared by the
+	/// staked LP tokens in every block from `start` up to `end`, in total `reward_per_block * (end - start)`.
+	#[derive(CloneNoBound, PartialEqNoBound, EqNoBound, RuntimeDebugNoBound, Encode, Decode, MaxEncodedLen, TypeInfo)]
+	#[scale_info(skip_type_params(T))]
+	pub struct Farm<T: Config> {
+		/// The pool whose LP tokens are staked.
+		pub pool: PoolKeyOf<T>,
+		/// The LP token of the pool.
+		pub lp_token: AssetIdOf<T>,
+		/// The asset the rewards are paid in.
+		pub reward_asset: BalanceType<T>,
+		/// The rewards shared by the stakers in every block.
+		pub reward_per_block: AssetBalanceOf<T>,
+		/// The first block that pays rewards.
+		pub start: BlockNumberFor<T>,
+		/// The block the rewards stop at.
+		pub end: BlockNumberFor<T>,
+		/// The total amount of LP tokens staked in the farm.
+		pub total_staked: AssetBalanceOf<T>,
+		/// The sum of the rewards per staked LP token, up to `last_reward_block`.
+		pub reward_per_share: FixedU128,
+		/// The block up to which the rewards have been added to `reward_per_share`.
+		pub last_reward_block: BlockNumberFor<T>,
+		/// The rewards of past blocks that were too small to add to `reward_per_share`, they are added with
+		/// the rewards of the next blocks.
+		pub undistributed: AssetBalanceOf<T>,
+		/// The rewards added to `reward_per_share` that haven't been claimed, at least what the stakers are owed.
+		pub rewards_owed: AssetBalanceOf<T>,
+	}
+
+	/// The LP tokens an account has staked in a farm and the rewards it is owed.
+	#[derive(CloneNoBound, PartialEqNoBound, EqNoBound, RuntimeDebugNoBound, Encode, Decode, MaxEncodedLen, TypeInfo)]
+	#[scale_info(skip_type_params(T))]
+	pub struct Stake<T: Config> {
+		/// The amount of LP tokens staked.
+		pub amount: AssetBalanceOf<T>,
+		/// The `reward_per_share` of the farm up to which the rewards of the stake have been added to `rewards`.
+		pub reward_per_share_paid: FixedU128,
+		/// The rewards owed to the account, paid out by `claim`.
+		pub rewards: AssetBalanceOf<T>,
+	}
+
+	/// The farms by their id.
+	#[pallet::storage]
+	#[pallet::getter(fn farm)]
+	pub type Farms<T: Config> = StorageMap<_, Blake2_128Concat, FarmId, Farm<T>>;
+
+	/// The id of the next farm.
+	#[pallet::storage]
+	pub type NextFarmId<T: Config> = StorageValue<_, FarmId, ValueQuery>;
+
+	/// The stakes in every farm by account.
+	#[pallet::storage]
+	#[pallet::getter(fn stake_of)]
+	pub type Stakes<T: Config> =
+		StorageDoubleMap<_, Blake2_128Concat, FarmId, Blake2_128Concat, T::AccountId, Stake<T>>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// A farm has been created for the LP tokens of a pool.
+		FarmCreated {
+			farm_id: FarmId,
+			asset_a: BalanceType<T>,
+			asset_b: BalanceType<T>,
+			fee: Permill,
+			reward_asset: BalanceType<T>,
+			reward_per_block: AssetBalanceOf<T>,
+			start: BlockNumberFor<T>,
+			end: BlockNumberFor<T>,
+		},
+		/// Rewards have been transferred to the account of a farm.
+		FarmFunded { farm_id: FarmId, who: T::AccountId, amount: AssetBalanceOf<T> },
+		/// LP tokens have been staked in a farm.
+		Staked { farm_id: FarmId, who: T::AccountId, amount: AssetBalanceOf<T> },
+		/// LP tokens have been unstaked from a farm.
+		Unstaked { farm_id: FarmId, who: T::AccountId, amount: AssetBalanceOf<T> },
+		/// The rewards owed to an account have been paid out.
+		RewardsClaimed { farm_id: FarmId, who: T::AccountId, amount: AssetBalanceOf<T> },
+		/// A farm has been ended and the rewards it doesn't owe have been transferred to `dest`.
+		FarmClosed { farm_id: FarmId, dest: T::AccountId, amount: AssetBalanceOf<T> },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The pool doesn't exist
+		PoolDoesNotExist,
+		/// The farm doesn't exist
+		FarmDoesNotExist,
+		/// A farm has to start at the current block or later, end after it starts and pay some rewards per block
+		InvalidSchedule,
+		/// The LP token of the pool can't be the reward asset of its farm
+		InvalidRewardAsset,
+		/// The amount has to be more than zero
+		AmountZero,
+		/// The account doesn't have that much staked in the farm
+		NotEnoughStaked,
+		/// The account isn't owed any rewards by the farm
+		NothingToClaim,
+		/// The account of the farm doesn't hold enough of the reward asset to pay the rewards
+		RewardsNotFunded,
+		/// There are too many farms
+		StorageOverflow,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// CreateFarm: Creates a farm that pays `reward_per_block` of `reward_asset` to the stakers of the LP
+		/// tokens of a pool, in every block from `start` up to `end`. The rewards are paid out of the account
+		/// of the farm, which anyone can fund with `fund_farm`.
+		///
+		/// # Arguments
+		///
+		/// * `origin` - Has to be the governance origin of the dex.
+		/// * `asset_a` - One of the assets in the liquidity pool.
+		/// * `asset_b` - The other asset in the liquidity pool.
+		/// * `fee` - The fee tier of the liquidity pool.
+		/// * `reward_asset` - The asset the rewards are paid in.
+		/// * `reward_per_block` - The rewards shared by the stakers in every block.
+		/// * `start` - The first block that pays rewards.
+		/// * `end` - The block the rewards stop at.
+		#[pallet::call_index(0)]
+		#[pallet::weight(<T as Config>::WeightInfo::create_farm())]
+		pub fn create_farm(
+			origin: OriginFor<T>,
+			asset_a: BalanceType<T>,
+			asset_b: BalanceType<T>,
+			fee: Permill,
+			reward_asset: BalanceType<T>,
+			reward_per_block: AssetBalanceOf<T>,
+			start: BlockNumberFor<T>,
+			end: BlockNumberFor<T>,
+		) -> DispatchResult {
+			<T as pallet_dex::Config>::GovernanceOrigin::ensure_origin(origin)?;
+
+			Self::do_create_farm(asset_a, asset_b, fee, reward_asset, reward_per_block, start, end)?;
+			Ok(())
+		}
+
+		/// FundFarm: Transfers `amount` of the reward asset of a farm to the account of the farm, e.g. by the
+		/// team of a token that wants to bootstrap the liquidity of its pool.
+		#[pallet::call_index(1)]
+		#[pallet::weight(<T as Config>::WeightInfo::fund_farm())]
+		pub fn fund_farm(origin: OriginFor<T>, farm_id: FarmId, amount: AssetBalanceOf<T>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			let farm = Farms::<T>::get(farm_id).ok_or(Error::<T>::FarmDoesNotExist)?;
+			ensure!(!amount.is_zero(), Error::<T>::AmountZero);
+			pallet_dex::Pallet::<T>::transfer_asset(&farm.reward_asset, &who, &Self::farm_account(farm_id), amount)?;
+
+			Self::deposit_event(Event::<T>::FarmFunded { farm_id, who, amount });
+			Ok(())
+		}
+
+		/// Stake: Stakes `amount` of the LP tokens of the pool of a farm. The LP tokens are held in the
+		/// account of the farm and earn a share of its rewards until they are unstaked.
+		#[pallet::call_index(2)]
+		#[pallet::weight(<T as Config>::WeightInfo::stake())]
+		pub fn stake(origin: OriginFor<T>, farm_id: FarmId, amount: AssetBalanceOf<T>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			Self::do_stake(&who, farm_id, amount)?;
+			Ok(())
+		}
+
+		/// Unstake: Pays `amount` of the staked LP tokens back. The rewards earned so far stay owed and can
+		/// be claimed at any time.
+		#[pallet::call_index(3)]
+		#[pallet::weight(<T as Config>::WeightInfo::unstake())]
+		pub fn unstake(origin: OriginFor<T>, farm_id: FarmId, amount: AssetBalanceOf<T>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			Self::do_unstake(&who, farm_id, amount)?;
+			Ok(())
+		}
+
+		/// Claim: Pays out the rewards a farm owes to the caller.
+		#[pallet::call_index(4)]
+		#[pallet::weight(<T as Config>::WeightInfo::claim())]
+		pub fn claim(origin: OriginFor<T>, farm_id: FarmId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			Self::do_claim(&who, farm_id)?;
+			Ok(())
+		}
+
+		/// CloseFarm: Ends a farm at the current block and transfers the rewards it doesn't owe to `dest`,
+		/// e.g. the rest of the rewards of a farm that governance ends early. The stakers keep what they have
+		/// earned up to the current block and can still unstake and claim it. A farm without any stakes is
+		/// removed, so a farm is closed again to remove it once its stakers have left.
+		///
+		/// # Arguments
+		///
+		/// * `origin` - Has to be the governance origin of the dex.
+		/// * `farm_id` - The farm to close.
+		/// * `dest` - The account the rewards the farm doesn't owe are transferred to.
+		#[pallet::call_index(5)]
+		#[pallet::weight(<T as Config>::WeightInfo::close_farm())]
+		pub fn close_farm(origin: OriginFor<T>, farm_id: FarmId, dest: T::AccountId) -> DispatchResult {
+			<T as pallet_dex::Config>::GovernanceOrigin::ensure_origin(origin)?;
+
+			Self::do_close_farm(farm_id, &dest)?;
+			Ok(())
+		}
+	}
+}
+
+use frame_system::pallet_prelude::BlockNumberFor;
+use pallet_dex::BalanceType;
+use sp_runtime::{
+	traits::UniqueSaturatedInto,
+	ArithmeticError, FixedPointNumber, FixedU128, Permill,
+};
+
+impl<T: Config> Pallet<T> {
+	/// Creates a farm for the LP tokens of the pool of the pair with the fee tier. Returns the id of the farm.
+	pub fn do_create_farm(
+		asset_a: BalanceType<T>,
+		asset_b: BalanceType<T>,
+		fee: Permill,
+		reward_asset: BalanceType<T>,
+		reward_per_block: AssetBalanceOf<T>,
+		start: BlockNumberFor<T>,
+		end: BlockNumberFor<T>,
+	) -> Result<FarmId, DispatchError> {
+		// Order the asset ids, the pool has to exist
+		let (asset_a, asset_b, _, _) =
+			pallet_dex::Pallet::<T>::order_asset_ids(asset_a, asset_b, Zero::zero(), Zero::zero())?;
+		let pool = pallet_dex::Pallet::<T>::liquidity_pool((asset_a.clone(), asset_b.clone(), fee))
+			.ok_or(Error::<T>::PoolDoesNotExist)?;
+
+		ensure!(
+			start >= frame_system::Pallet::<T>::block_number() && start < end && !reward_per_block.is_zero(),
+			Error::<T>::InvalidSchedule
+		);
+		ensure!(reward_asset != BalanceType::AssetBalance(pool.liquidity_id.clone()), Error::<T>::InvalidRewardAsset);
+
+		let farm_id = NextFarmId::<T>::get();
+		NextFarmId::<T>::put(farm_id.checked_add(1).ok_or(Error::<T>::StorageOverflow)?);
+
+		// the account of the farm has to exist to hold the LP tokens and any reward asset
+		frame_system::Pallet::<T>::inc_providers(&Self::farm_account(farm_id));
+
+		Farms::<T>::insert(farm_id, Farm {
+			pool: (asset_a.clone(), asset_b.clone(), fee),
+			lp_token: pool.liquidity_id,
+			reward_asset: reward_asset.clone(),
+			reward_per_block,
+			start,
+			end,
+			total_staked: Zero::zero(),
+			reward_per_share: FixedU128::zero(),
+			last_reward_block: start,
+			undistributed: Zero::zero(),
+			rewards_owed: Zero::zero(),
+		});
+
+		Self::deposit_event(Event::<T>::FarmCreated {
+			farm_id,
+			asset_a,
+			asset_b,
+			fee,
+			reward_asset,
+			reward_per_block,
+			start,
+			end,
+		});
+
+		Ok(farm_id)
+	}
+
+	/// Stakes `amount` of the LP tokens of a farm of `who`.
+	pub fn do_stake(who: &T::AccountId, farm_id: FarmId, amount: AssetBalanceOf<T>) -> DispatchResult {
+		ensure!(!amount.is_zero(), Error::<T>::AmountZero);
+		let mut farm = Self::update_farm(farm_id)?;
+		let mut stake = Self::update_stake(&farm, farm_id, who);
+
+		// the LP tokens are held in the account of the farm
+		let lp_token = BalanceType::AssetBalance(farm.lp_token.clone());
+		pallet_dex::Pallet::<T>::transfer_asset(&lp_token, who, &Self::farm_account(farm_id), amount)?;
+
+		stake.amount = stake.amount.checked_add(&amount).ok_or(ArithmeticError::Overflow)?;
+		farm.total_staked = farm.total_staked.checked_add(&amount).ok_or(ArithmeticError::Overflow)?;
+		Stakes::<T>::insert(farm_id, who, stake);
+		Farms::<T>::insert(farm_id, farm);
+
+		Self::deposit_event(Event::<T>::Staked { farm_id, who: who.clone(), amount });
+		Ok(())
+	}
+
+	/// Pays `amount` of the staked LP tokens of a farm back to `who`.
+	pub fn do_unstake(who: &T::AccountId, farm_id: FarmId, amount: AssetBalanceOf<T>) -> DispatchResult {
+		ensure!(!amount.is_zero(), Error::<T>::AmountZero);
+		let mut farm = Self::update_farm(farm_id)?;
+		let mut stake = Self::update_stake(&farm, farm_id, who);
+		stake.amount = stake.amount.checked_sub(&amount).ok_or(Error::<T>::NotEnoughStaked)?;
+		farm.total_staked = farm.total_staked.checked_sub(&amount).ok_or(ArithmeticError::Underflow)?;
+
+		let lp_token = BalanceType::AssetBalance(farm.lp_token.clone());
+		pallet_dex::Pallet::<T>::transfer_asset(&lp_token, &Self::farm_account(farm_id), who, amount)?;
+
+		Self::put_stake(farm_id, who, stake);
+		Farms::<T>::insert(farm_id, farm);
+
+		Self::deposit_event(Event::<T>::Unstaked { farm_id, who: who.clone(), amount });
+		Ok(())
+	}
+
+	/// Pays out the rewards a farm owes to `who`. Returns the amount paid out.
+	pub fn do_claim(who: &T::AccountId, farm_id: FarmId) -> Result<AssetBalanceOf<T>, DispatchError> {
+		let mut farm = Self::update_farm(farm_id)?;
+		let mut stake = Self::update_stake(&farm, farm_id, who);
+		let amount = stake.rewards;
+		ensure!(!amount.is_zero(), Error::<T>::NothingToClaim);
+
+		// the rewards stay owed if the farm hasn't been funded with enough of them
+		let farm_account = Self::farm_account(farm_id);
+		ensure!(
+			pallet_dex::Pallet::<T>::asset_balance(&farm.reward_asset, &farm_account) >= amount,
+			Error::<T>::RewardsNotFunded
+		);
+		pallet_dex::Pallet::<T>::transfer_asset(&farm.reward_asset, &farm_account, who, amount)?;
+
+		stake.rewards = Zero::zero();
+		farm.rewards_owed = farm.rewards_owed.saturating_sub(amount);
+		Self::put_stake(farm_id, who, stake);
+		Farms::<T>::insert(farm_id, farm);
+
+		Self::deposit_event(Event::<T>::RewardsClaimed { farm_id, who: who.clone(), amount });
+		Ok(amount)
+	}
+
+	/// Ends a farm at the current block and transfers the rewards it doesn't owe to `dest`, removing the farm
+	/// if nothing is staked in it anymore. Returns the amount transferred.
+	pub fn do_close_farm(farm_id: FarmId, dest: &T::AccountId) -> Result<AssetBalanceOf<T>, DispatchError> {
+		let mut farm = Self::update_farm(farm_id)?;
+
+		// no rewards are paid after the current block, and none at all by a farm that hasn't started yet
+		let now = frame_system::Pallet::<T>::block_number();
+		farm.end = farm.end.min(now.max(farm.start));
+
+		// the rewards owed to the stakers stay in the account of the farm, a farm without stakes owes nothing
+		let farm_account = Self::farm_account(farm_id);
+		let balance = pallet_dex::Pallet::<T>::asset_balance(&farm.reward_asset, &farm_account);
+		let has_stakes = Stakes::<T>::iter_prefix(farm_id).next().is_some();
+		let amount = if has_stakes { balance.saturating_sub(farm.rewards_owed) } else { balance };
+		pallet_dex::Pallet::<T>::transfer_asset(&farm.reward_asset, &farm_account, dest, amount)?;
+
+		if has_stakes {
+			Farms::<T>::insert(farm_id, farm);
+		} else {
+			Farms::<T>::remove(farm_id);
+			// the provider added when the farm was created, the account is kept if it still holds anything
+			let _ = frame_system::Pallet::<T>::dec_providers(&farm_account);
+		}
+
+		Self::deposit_event(Event::<T>::FarmClosed { farm_id, dest: dest.clone(), amount });
+		Ok(amount)
+	}
+
+	/// Returns the rewards a farm owes to `who` at the current block, including the ones not added to its
+	/// stake yet.
+	pub fn pending_rewards(farm_id: FarmId, who: &T::AccountId) -> AssetBalanceOf<T> {
+		let Some(mut farm) = Farms::<T>::get(farm_id) else {
+			return Zero::zero();
+		};
+		Self::accrue_rewards(&mut farm, frame_system::Pallet::<T>::block_number());
+		Self::update_stake(&farm, farm_id, who).rewards
+	}
+
+	/// The account of a farm, it holds the staked LP tokens and the rewards.
+	pub fn farm_account(farm_id: FarmId) -> T::AccountId {
+		<T as Config>::PalletId::get().into_sub_account_truncating(farm_id)
+	}
+
+	/// Returns the farm with the rewards up to the current block added to its `reward_per_share`.
+	fn update_farm(farm_id: FarmId) -> Result<Farm<T>, DispatchError> {
+		let mut farm = Farms::<T>::get(farm_id).ok_or(Error::<T>::FarmDoesNotExist)?;
+		Self::accrue_rewards(&mut farm, frame_system::Pallet::<T>::block_number());
+		Ok(farm)
+	}
+
+	/// Adds the rewards of the blocks since `last_reward_block`, up to `now` but not after the end of the
+	/// farm, to the rewards per staked LP token. Blocks without any stake don't pay rewards. The reward per
+	/// share is rounded down, what that leaves of the rewards is carried over to the next blocks, so small
+	/// rewards on a large stake add up instead of being lost.
+	fn accrue_rewards(farm: &mut Farm<T>, now: BlockNumberFor<T>) {
+		let until = now.min(farm.end);
+		if until <= farm.last_reward_block {
+			return;
+		}
+		if !farm.total_staked.is_zero() {
+			let blocks: u128 = (until - farm.last_reward_block).unique_saturated_into();
+			let rewards = blocks
+				.saturating_mul(farm.reward_per_block.unique_saturated_into())
+				.saturating_add(farm.undistributed.unique_saturated_into());
+			let total_staked: u128 = farm.total_staked.unique_saturated_into();
+			let reward_per_share = FixedU128::checked_from_rational(rewards, total_staked).unwrap_or_default();
+			let distributed = reward_per_share.saturating_mul_int(total_staked).min(rewards);
+			farm.reward_per_share = farm.reward_per_share.saturating_add(reward_per_share);
+			farm.undistributed = (rewards - distributed).unique_saturated_into();
+			farm.rewards_owed = farm.rewards_owed.saturating_add(distributed.unique_saturated_into());
+		}
+		farm.last_reward_block = until;
+	}
+
+	/// Returns the stake of `who` with the rewards it earned since its last update added to what it is owed.
+	fn update_stake(farm: &Farm<T>, farm_id: FarmId, who: &T::AccountId) -> Stake<T> {
+		let mut stake = Stakes::<T>::get(farm_id, who).unwrap_or(Stake {
+			amount: Zero::zero(),
+			reward_per_share_paid: farm.reward_per_share,
+			rewards: Zero::zero(),
+		});
+		let earned = farm
+			.reward_per_share
+			.saturating_sub(stake.reward_per_share_paid)
+			.saturating_mul_int(stake.amount.unique_saturated_into());
+		stake.rewards = stake.rewards.saturating_add(earned.unique_saturated_into());
+		stake.reward_per_share_paid = farm.reward_per_share;
+		stake
+	}
+
+	/// Stores the stake of `who`, or removes it once nothing is staked or owed anymore.
+	fn put_stake(farm_id: FarmId, who: &T::AccountId, stake: Stake<T>) {
+		if stake.amount.is_zero() && stake.rewards.is_zero() {
+			Stakes::<T>::remove(farm_id, who);
+		} else {
+			Stakes::<T>::insert(farm_id, who, stake);
+		}
+	}
+}
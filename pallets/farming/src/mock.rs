@@ -0,0 +1,138 @@
+use crate as pallet_farming;
+use frame_support::{
+	parameter_types,
+	traits::{AsEnsureOriginWithArg, ConstU128, ConstU16, ConstU32, ConstU64},
+};
+use frame_system::{EnsureRoot, EnsureSigned};
+use sp_core::H256;
+use sp_runtime::{
+	traits::{BlakeTwo256, IdentityLookup},
+	BuildStorage, Permill,
+};
+
+type Block = frame_system::mocking::MockBlock<Test>;
+type Balance = u128;
+type AccountId = u128;
+
+use frame_support::PalletId;
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+	pub enum Test
+	{
+		System: frame_system,
+		Balances: pallet_balances,
+		Assets: pallet_assets,
+		Dex: pallet_dex,
+		Farming: pallet_farming,
+	}
+);
+
+impl frame_system::Config for Test {
+	type BaseCallFilter = frame_support::traits::Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type RuntimeOrigin = RuntimeOrigin;
+	type RuntimeCall = RuntimeCall;
+	type Nonce = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = AccountId;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Block = Block;
+	type RuntimeEvent = RuntimeEvent;
+	type BlockHashCount = ConstU64<250>;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = pallet_balances::AccountData<Balance>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ConstU16<42>;
+	type OnSetCode = ();
+	type MaxConsumers = frame_support::traits::ConstU32<16>;
+}
+
+impl pallet_balances::Config for Test {
+	type Balance = Balance;
+	type DustRemoval = ();
+	type RuntimeEvent = RuntimeEvent;
+	type ExistentialDeposit = ConstU128<1>;
+	type AccountStore = System;
+	type WeightInfo = ();
+	type MaxLocks = ConstU32<10>;
+	type MaxReserves = ();
+	type ReserveIdentifier = [u8; 8];
+	type RuntimeHoldReason = ();
+	type FreezeIdentifier = ();
+	type MaxHolds = ConstU32<10>;
+	type MaxFreezes = ConstU32<10>;
+}
+
+impl pallet_assets::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type Balance = Balance;
+	type AssetId = u32;
+	type AssetIdParameter = codec::Compact<u32>;
+	type Currency = Balances;
+	type CreateOrigin = AsEnsureOriginWithArg<EnsureSigned<Self::AccountId>>;
+	//type CreateOrigin = AsEnsureOriginWithArg<EnsureSignedBy<AssetConversionOrigin, AccountId>>;
+
+	type ForceOrigin = EnsureRoot<Self::AccountId>;
+	type AssetDeposit = ConstU128<100>;
+	type AssetAccountDeposit = ConstU128<0>;
+	type MetadataDepositBase = ConstU128<10>;
+	type MetadataDepositPerByte = ConstU128<1>;
+	type ApprovalDeposit = ConstU128<1>;
+	type StringLimit = ConstU32<50>;
+	type Freezer = ();
+	type Extra = ();
+	type CallbackHandle = ();
+	type WeightInfo = ();
+	type RemoveItemsLimit = ConstU32<1000>;
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = ();
+}
+
+parameter_types! {
+	pub const DexPalletId: PalletId = PalletId(*b"pba/dex0");
+	pub const DexDefaultFeeTier: Permill = Permill::from_percent(5);
+	pub const FarmingPalletId: PalletId = PalletId(*b"pba/farm");
+}
+
+impl pallet_dex::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type NativeBalance = Balances;
+	type Fungibles = Assets;
+	type MinPoolDeposit = ConstU32<100>;
+	type MinimumLiquidity = ConstU128<10>;
+	type LpAssetIdStart = ConstU32<1_000>;
+	type LpMetadataDeposit = ConstU128<100>;
+	type DefaultFeeTier = DexDefaultFeeTier;
+	type MaxFeeTiers = ConstU32<8>;
+	type MaxAmplification = ConstU32<10_000>;
+	type MaxWeightedPoolAssets = ConstU32<8>;
+	type MaxConcentratedTicks = ConstU32<100>;
+	type MaxSwapPathLength = ConstU32<4>;
+	type MaxPriceObservations = ConstU32<100>;
+	type MaxLimitOrders = ConstU32<16>;
+	type MaxSwapIntents = ConstU32<16>;
+	type CreatePoolOrigin = frame_system::EnsureSigned<AccountId>;
+	type GovernanceOrigin = EnsureRoot<AccountId>;
+	type PauseOrigin = EnsureRoot<AccountId>;
+	type FlashSwapHandler = ();
+	type PalletId = DexPalletId;
+	type WeightInfo = ();
+}
+
+impl pallet_farming::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type PalletId = FarmingPalletId;
+	type WeightInfo = ();
+}
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	frame_system::GenesisConfig::<Test>::default().build_storage().unwrap().into()
+}
\ No newline at end of file
@@ -0,0 +1,175 @@
+use crate::{mock::*, Error, Event};
+use frame_support::{assert_noop, assert_ok};
+use pallet_dex::BalanceType;
+use sp_runtime::Permill;
+
+use frame_support::traits::fungibles::{Inspect, Mutate};
+
+type Assets = <Test as pallet_dex::Config>::Fungibles;
+
+const FEE: Permill = Permill::from_percent(5);
+
+fn asset(id: u32) -> BalanceType<Test> {
+	BalanceType::AssetBalance(id)
+}
+
+// Creates the pool of the assets 1 and 2, account 1 gets 99_990 LP tokens and account 2 100_000.
+// Returns the id of the LP token.
+fn create_pool_with_two_lps() -> u32 {
+	for who in 1..=3 {
+		assert_ok!(Dex::setup_account(who, 1_000_000, vec![(1, 1_000_000), (2, 1_000_000), (3, 1_000_000)]));
+	}
+	assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, 100_000, 100_000));
+	assert_ok!(Dex::add_liquidity(RuntimeOrigin::signed(2), asset(1), asset(2), FEE, 100_000, 100_000, 0, 0, None));
+	Dex::liquidity_pool((asset(2), asset(1), FEE)).unwrap().liquidity_id
+}
+
+#[test]
+fn farms_can_only_be_created_by_governance_for_existing_pools() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let lp_token = create_pool_with_two_lps();
+
+		assert_noop!(
+			Farming::create_farm(RuntimeOrigin::signed(1), asset(1), asset(2), FEE, asset(3), 100, 10, 20),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		assert_noop!(
+			Farming::create_farm(RuntimeOrigin::root(), asset(1), asset(3), FEE, asset(3), 100, 10, 20),
+			Error::<Test>::PoolDoesNotExist
+		);
+		// The schedule can't start in the past, has to end after it starts and has to pay some rewards
+		assert_noop!(
+			Farming::create_farm(RuntimeOrigin::root(), asset(1), asset(2), FEE, asset(3), 100, 0, 20),
+			Error::<Test>::InvalidSchedule
+		);
+		assert_noop!(
+			Farming::create_farm(RuntimeOrigin::root(), asset(1), asset(2), FEE, asset(3), 100, 20, 20),
+			Error::<Test>::InvalidSchedule
+		);
+		assert_noop!(
+			Farming::create_farm(RuntimeOrigin::root(), asset(1), asset(2), FEE, asset(3), 0, 10, 20),
+			Error::<Test>::InvalidSchedule
+		);
+		assert_noop!(
+			Farming::create_farm(RuntimeOrigin::root(), asset(1), asset(2), FEE, asset(lp_token), 100, 10, 20),
+			Error::<Test>::InvalidRewardAsset
+		);
+
+		assert_ok!(Farming::create_farm(RuntimeOrigin::root(), asset(1), asset(2), FEE, asset(3), 100, 10, 20));
+		System::assert_last_event(
+			Event::FarmCreated {
+				farm_id: 0,
+				asset_a: asset(2),
+				asset_b: asset(1),
+				fee: FEE,
+				reward_asset: asset(3),
+				reward_per_block: 100,
+				start: 10,
+				end: 20,
+			}
+			.into(),
+		);
+		assert_eq!(Farming::farm(0).unwrap().lp_token, lp_token);
+	});
+}
+
+#[test]
+fn stakers_share_the_rewards_of_every_block_by_their_stake() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let lp_token = create_pool_with_two_lps();
+		assert_ok!(Farming::create_farm(RuntimeOrigin::root(), asset(1), asset(2), FEE, asset(3), 100, 10, 20));
+		assert_ok!(Farming::fund_farm(RuntimeOrigin::signed(3), 0, 600));
+
+		// Account 1 is alone in the farm for 5 blocks and shares the next 5 with account 2
+		System::set_block_number(10);
+		assert_ok!(Farming::stake(RuntimeOrigin::signed(1), 0, 25_000));
+		assert_eq!(Assets::balance(lp_token, &1), 99_990 - 25_000);
+		System::set_block_number(15);
+		assert_eq!(Farming::pending_rewards(0, &1), 500);
+		assert_ok!(Farming::stake(RuntimeOrigin::signed(2), 0, 25_000));
+
+		// No more rewards are paid after the end of the farm
+		System::set_block_number(30);
+		assert_eq!(Farming::pending_rewards(0, &1), 750);
+		assert_eq!(Farming::pending_rewards(0, &2), 250);
+
+		// The rewards stay owed while the farm isn't funded with enough of them
+		assert_noop!(Farming::claim(RuntimeOrigin::signed(1), 0), Error::<Test>::RewardsNotFunded);
+		assert_ok!(Farming::claim(RuntimeOrigin::signed(2), 0));
+		assert_eq!(Assets::balance(3, &2), 1_000_000 + 250);
+		assert_ok!(Farming::fund_farm(RuntimeOrigin::signed(3), 0, 400));
+		assert_ok!(Farming::claim(RuntimeOrigin::signed(1), 0));
+		System::assert_last_event(Event::RewardsClaimed { farm_id: 0, who: 1, amount: 750 }.into());
+		assert_noop!(Farming::claim(RuntimeOrigin::signed(1), 0), Error::<Test>::NothingToClaim);
+
+		// Unstaking pays the LP tokens back and removes the stake once nothing is owed anymore
+		assert_noop!(Farming::unstake(RuntimeOrigin::signed(1), 0, 25_001), Error::<Test>::NotEnoughStaked);
+		assert_ok!(Farming::unstake(RuntimeOrigin::signed(1), 0, 25_000));
+		assert_eq!(Assets::balance(lp_token, &1), 99_990);
+		assert!(Farming::stake_of(0, 1).is_none());
+		assert_eq!(Farming::farm(0).unwrap().total_staked, 25_000);
+	});
+}
+
+#[test]
+fn small_rewards_on_a_large_stake_are_carried_over() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let lp_token = create_pool_with_two_lps();
+		assert_ok!(Farming::create_farm(RuntimeOrigin::root(), asset(1), asset(2), FEE, asset(3), 500_000, 10, 20));
+		assert_ok!(Farming::fund_farm(RuntimeOrigin::signed(3), 0, 1_000_000));
+
+		// 1_000_000 LP tokens of 18 decimals earn 0.5 of a reward asset of 6 decimals per block, 5 * 10^-19 per
+		// LP token, below the precision of the reward per share
+		assert_ok!(Dex::setup_account(4, 1_000_000, vec![]));
+		assert_ok!(Assets::mint_into(lp_token, &4, 10u128.pow(24)));
+		System::set_block_number(10);
+		assert_ok!(Farming::stake(RuntimeOrigin::signed(4), 0, 10u128.pow(24)));
+
+		// Account 1 stakes in every block, so the rewards are added block by block. The rewards of one block
+		// round down to nothing per share, they are carried over until they add up to 10^-18 per share.
+		for block in 11..20 {
+			System::set_block_number(block);
+			assert_ok!(Farming::stake(RuntimeOrigin::signed(1), 0, 1));
+		}
+		System::set_block_number(20);
+		assert_eq!(Farming::pending_rewards(0, &4), 4_000_000);
+		let farm = Farming::farm(0).unwrap();
+		assert_eq!((farm.rewards_owed, farm.undistributed), (4_000_000, 500_000));
+	});
+}
+
+#[test]
+fn governance_closes_a_farm_and_takes_back_the_rewards_it_does_not_owe() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let lp_token = create_pool_with_two_lps();
+		assert_ok!(Farming::create_farm(RuntimeOrigin::root(), asset(1), asset(2), FEE, asset(3), 100, 10, 20));
+		assert_ok!(Farming::fund_farm(RuntimeOrigin::signed(3), 0, 1_000));
+		System::set_block_number(10);
+		assert_ok!(Farming::stake(RuntimeOrigin::signed(1), 0, 25_000));
+
+		// The farm is ended after 5 blocks, the 500 it owes to account 1 stay in the account of the farm
+		System::set_block_number(15);
+		assert_noop!(Farming::close_farm(RuntimeOrigin::signed(1), 0, 3), sp_runtime::DispatchError::BadOrigin);
+		assert_ok!(Farming::close_farm(RuntimeOrigin::root(), 0, 3));
+		System::assert_last_event(Event::FarmClosed { farm_id: 0, dest: 3, amount: 500 }.into());
+		assert_eq!(Assets::balance(3, &3), 1_000_000 - 500);
+		assert_eq!(Farming::farm(0).unwrap().end, 15);
+
+		// The staker keeps what it earned and leaves the farm
+		System::set_block_number(20);
+		assert_eq!(Farming::pending_rewards(0, &1), 500);
+		assert_ok!(Farming::claim(RuntimeOrigin::signed(1), 0));
+		assert_ok!(Farming::unstake(RuntimeOrigin::signed(1), 0, 25_000));
+		assert_eq!(Assets::balance(lp_token, &1), 99_990);
+
+		// Closed again without stakes, the farm and its account are removed
+		assert_ok!(Farming::close_farm(RuntimeOrigin::root(), 0, 3));
+		System::assert_last_event(Event::FarmClosed { farm_id: 0, dest: 3, amount: 0 }.into());
+		assert!(Farming::farm(0).is_none());
+		assert!(!System::account_exists(&Farming::farm_account(0)));
+	});
+}
@@ -0,0 +1,116 @@
+//! Weights for pallet_farming
+//!
+//! These weights are estimates written by hand, they have not been generated by the benchmark
+//! CLI. The storage accesses follow the benchmarks in `benchmarking.rs`, the execution times and
+//! proof sizes are rough figures. Replace the file with the output of `benchmark pallet` on
+//! reference hardware before relying on it in production.
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use core::marker::PhantomData;
+
+/// Weight functions needed for pallet_farming.
+pub trait WeightInfo {
+	fn create_farm() -> Weight;
+	fn fund_farm() -> Weight;
+	fn stake() -> Weight;
+	fn unstake() -> Weight;
+	fn claim() -> Weight;
+	fn close_farm() -> Weight;
+}
+
+/// Weights for pallet_farming estimated by hand, see the module docs.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	/// Storage: Dex LiquidityPoolMap (r:1 w:0)
+	/// Storage: Farming NextFarmId (r:1 w:1)
+	/// Storage: System Account (r:1 w:1)
+	/// Storage: Farming Farms (r:0 w:1)
+	fn create_farm() -> Weight {
+		Weight::from_parts(31_000_000, 5_000)
+			.saturating_add(T::DbWeight::get().reads(3_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+	}
+	/// Storage: Farming Farms (r:1 w:0)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Assets Account (r:2 w:2)
+	fn fund_farm() -> Weight {
+		Weight::from_parts(42_000_000, 6_000)
+			.saturating_add(T::DbWeight::get().reads(4_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+	}
+	/// Storage: Farming Farms (r:1 w:1)
+	/// Storage: Farming Stakes (r:1 w:1)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Assets Account (r:2 w:2)
+	fn stake() -> Weight {
+		Weight::from_parts(48_000_000, 7_000)
+			.saturating_add(T::DbWeight::get().reads(5_u64))
+			.saturating_add(T::DbWeight::get().writes(5_u64))
+	}
+	/// Storage: Farming Farms (r:1 w:1)
+	/// Storage: Farming Stakes (r:1 w:1)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Assets Account (r:2 w:2)
+	fn unstake() -> Weight {
+		Weight::from_parts(47_000_000, 7_000)
+			.saturating_add(T::DbWeight::get().reads(5_u64))
+			.saturating_add(T::DbWeight::get().writes(5_u64))
+	}
+	/// Storage: Farming Farms (r:1 w:1)
+	/// Storage: Farming Stakes (r:1 w:1)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Assets Account (r:2 w:2)
+	fn claim() -> Weight {
+		Weight::from_parts(46_000_000, 7_000)
+			.saturating_add(T::DbWeight::get().reads(5_u64))
+			.saturating_add(T::DbWeight::get().writes(5_u64))
+	}
+	/// Storage: Farming Farms (r:1 w:1)
+	/// Storage: Farming Stakes (r:1 w:0)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Assets Account (r:2 w:2)
+	/// Storage: System Account (r:1 w:1)
+	fn close_farm() -> Weight {
+		Weight::from_parts(52_000_000, 8_000)
+			.saturating_add(T::DbWeight::get().reads(6_u64))
+			.saturating_add(T::DbWeight::get().writes(5_u64))
+	}
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+	fn create_farm() -> Weight {
+		Weight::from_parts(31_000_000, 5_000)
+			.saturating_add(RocksDbWeight::get().reads(3_u64))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+	}
+	fn fund_farm() -> Weight {
+		Weight::from_parts(42_000_000, 6_000)
+			.saturating_add(RocksDbWeight::get().reads(4_u64))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+	}
+	fn stake() -> Weight {
+		Weight::from_parts(48_000_000, 7_000)
+			.saturating_add(RocksDbWeight::get().reads(5_u64))
+			.saturating_add(RocksDbWeight::get().writes(5_u64))
+	}
+	fn unstake() -> Weight {
+		Weight::from_parts(47_000_000, 7_000)
+			.saturating_add(RocksDbWeight::get().reads(5_u64))
+			.saturating_add(RocksDbWeight::get().writes(5_u64))
+	}
+	fn claim() -> Weight {
+		Weight::from_parts(46_000_000, 7_000)
+			.saturating_add(RocksDbWeight::get().reads(5_u64))
+			.saturating_add(RocksDbWeight::get().writes(5_u64))
+	}
+	fn close_farm() -> Weight {
+		Weight::from_parts(52_000_000, 8_000)
+			.saturating_add(RocksDbWeight::get().reads(6_u64))
+			.saturating_add(RocksDbWeight::get().writes(5_u64))
+	}
+}
@@ -34,20 +34,20 @@ pub trait DexInterface {
 	/// Return the number of basis points (1/100) used for swap fees.
 	fn swap_fee() -> u16;
 
-	// /// Get the LP Token ID that will be generated by creating a pool of `asset_a` and `asset_b`.
-	// fn lp_id(asset_a: Self::AssetId, asset_b: Self::AssetId) -> Self::AssetId;
+	/// Get the LP Token ID of the pool of `asset_a` and `asset_b`, or `None` if the pool doesn't exist.
+	fn lp_id(asset_a: Self::AssetId, asset_b: Self::AssetId) -> Option<Self::AssetId>;
 
-	// /// Add liquidity to a pool on behalf of the user. If needed this will create the pool.
-	// ///
-	// /// LP tokens are minted to the caller which are used to represent
-	// /// "ownership" of the pool.
-	// fn add_liquidity(
-	// 	who: Self::AccountId,
-	// 	asset_a: Self::AssetId,
-	// 	asset_b: Self::AssetId,
-	// 	amount_a: Self::AssetBalance,
-	// 	amount_b: Self::AssetBalance,
-	// ) -> DispatchResult;
+	/// Add liquidity to a pool on behalf of the user. If needed this will create the pool.
+	///
+	/// LP tokens are minted to the caller which are used to represent
+	/// "ownership" of the pool.
+	fn add_liquidity(
+		who: Self::AccountId,
+		asset_a: Self::AssetId,
+		asset_b: Self::AssetId,
+		amount_a: Self::AssetBalance,
+		amount_b: Self::AssetBalance,
+	) -> DispatchResult;
 
 	/// Removes liquidity from the pool on behalf of the user.
 	///
@@ -7,7 +7,6 @@
 include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
 
 use codec::{Decode, Encode};
-use frame_support::traits::AsEnsureOriginWithArg;
 use frame_system::{EnsureRoot, EnsureSigned};
 use pallet_grandpa::AuthorityId as GrandpaId;
 use sp_api::impl_runtime_apis;
@@ -67,6 +66,9 @@ pub type Balance = u128;
 /// Index of a transaction in the chain.
 pub type Nonce = u32;
 
+/// Identifier of an asset in the assets pallet.
+pub type AssetId = u32;
+
 /// A hash of some data used by the chain.
 pub type Hash = sp_core::H256;
 
@@ -105,11 +107,12 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	//   runtime in substitute for the on-chain Wasm runtime unless all of `spec_name`,
 	//   `spec_version`, and `authoring_version` are the same between Wasm and native.
 	// This value is set to 100 to notify Polkadot-JS App (https://polkadot.js.org/apps) to use
-	//   the compatible custom types.
-	spec_version: 100,
+	//   the compatible custom types. 101 runs the storage migrations of pallet-dex.
+	spec_version: 101,
 	impl_version: 1,
 	apis: RUNTIME_API_VERSIONS,
-	transaction_version: 1,
+	// The calls of pallet-dex take new arguments, e.g. the fee tier of the pool.
+	transaction_version: 2,
 	state_version: 1,
 };
 
@@ -281,10 +284,11 @@ parameter_types! {
 impl pallet_assets::Config for Runtime {
 	type RuntimeEvent = RuntimeEvent;
 	type Balance = u128;
-	type AssetId = u32;
+	type AssetId = AssetId;
 	type AssetIdParameter = codec::Compact<u32>;
 	type Currency = Balances;
-	type CreateOrigin = AsEnsureOriginWithArg<EnsureSigned<AccountId>>;
+	// Signed accounts create assets below the ids of the LP tokens
+	type CreateOrigin = pallet_dex::EnsureBelowLpAssetIds<Runtime, EnsureSigned<AccountId>>;
 	type ForceOrigin = EnsureRoot<AccountId>;
 	type AssetDeposit = AssetDeposit;
 	type AssetAccountDeposit = ConstU128<1>;
@@ -314,17 +318,49 @@ impl Convert<AuraId, AccountId> for AuthorityToAccount {
 
 parameter_types! {
 	pub const DexPalletId: PalletId = PalletId(*b"pba/dex0");
+	pub const DexDefaultFeeTier: Permill = Permill::from_percent(5);
 }
 
+/// An asset that can be traded on the dex, either the native currency or an asset id.
+pub type DexAssetKind = pallet_dex::BalanceType<Runtime>;
+
 /// Configure the pallet-dex in pallets/dex.
 impl pallet_dex::Config for Runtime {
 	type RuntimeEvent = RuntimeEvent;
 	type NativeBalance = Balances;
 	type Fungibles = Assets;
 	type MinPoolDeposit = ConstU32<1_000>;
-	type FeePercentage = ConstU32<5>;
+	type MinimumLiquidity = ConstU128<1_000>;
+	// LP tokens take the ids from 2^30 on, the ids below are left to the other assets
+	type LpAssetIdStart = ConstU32<{ 1 << 30 }>;
+	// Covers the metadata deposit of an LP token and keeps its free part above the existential deposit
+	type LpMetadataDeposit = ConstU128<{ 2 * EXISTENTIAL_DEPOSIT }>;
+	type DefaultFeeTier = DexDefaultFeeTier;
+	type MaxFeeTiers = ConstU32<8>;
+	type MaxAmplification = ConstU32<10_000>;
+	type MaxWeightedPoolAssets = ConstU32<8>;
+	type MaxConcentratedTicks = ConstU32<100>;
+	type MaxSwapPathLength = ConstU32<4>;
+	type MaxPriceObservations = ConstU32<100>;
+	type MaxLimitOrders = ConstU32<16>;
+	type MaxSwapIntents = ConstU32<64>;
 	type CreatePoolOrigin = frame_system::EnsureSigned<AccountId>;
+	type GovernanceOrigin = EnsureRoot<AccountId>;
+	type PauseOrigin = EnsureRoot<AccountId>;
+	type FlashSwapHandler = ();
 	type PalletId = DexPalletId;
+	type WeightInfo = pallet_dex::weights::SubstrateWeight<Runtime>;
+}
+
+parameter_types! {
+	pub const FarmingPalletId: PalletId = PalletId(*b"pba/farm");
+}
+
+/// Configure the pallet-farming in pallets/farming.
+impl pallet_farming::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type PalletId = FarmingPalletId;
+	type WeightInfo = pallet_farming::weights::SubstrateWeight<Runtime>;
 }
 
 // Create the runtime by composing the FRAME pallets that were previously configured.
@@ -339,6 +375,7 @@ construct_runtime!(
 		Sudo: pallet_sudo,
 		Assets: pallet_assets,
 		Dex: pallet_dex,
+		Farming: pallet_farming,
 	}
 );
 
@@ -365,6 +402,11 @@ pub type UncheckedExtrinsic =
 	generic::UncheckedExtrinsic<Address, RuntimeCall, Signature, SignedExtra>;
 /// The payload being signed in transactions.
 pub type SignedPayload = generic::SignedPayload<RuntimeCall, SignedExtra>;
+/// Migrations to apply on runtime upgrade.
+pub type Migrations = (
+	pallet_dex::migrations::v1::MigrateToPoolRecords<Runtime>,
+	pallet_dex::migrations::v2::MigrateToSubAccounts<Runtime>,
+);
 /// Executive: handles dispatch to the various modules.
 pub type Executive = frame_executive::Executive<
 	Runtime,
@@ -372,6 +414,7 @@ pub type Executive = frame_executive::Executive<
 	frame_system::ChainContext<Runtime>,
 	Runtime,
 	AllPalletsWithSystem,
+	Migrations,
 >;
 
 #[cfg(feature = "runtime-benchmarks")]
@@ -387,8 +430,7 @@ mod benches {
 		[pallet_timestamp, Timestamp]
 		[pallet_sudo, Sudo]
 		[pallet_dex, Dex]
-		[pallet_dpos, Dpos]
-		[pallet_voting, Voting]
+		[pallet_farming, Farming]
 	);
 }
 
@@ -537,6 +579,32 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl pallet_dex_runtime_api::DexApi<Block, DexAssetKind, Permill, AssetId, Balance> for Runtime {
+		fn quote_exact_in(asset_in: DexAssetKind, asset_out: DexAssetKind, fee: Permill, amount_in: Balance) -> Option<Balance> {
+			Dex::quote_exact_in(asset_in, asset_out, fee, amount_in)
+		}
+
+		fn quote_exact_out(asset_in: DexAssetKind, asset_out: DexAssetKind, fee: Permill, amount_out: Balance) -> Option<Balance> {
+			Dex::quote_exact_out(asset_in, asset_out, fee, amount_out)
+		}
+
+		fn get_price(asset_in: DexAssetKind, asset_out: DexAssetKind, fee: Permill, amount_in: Balance) -> Option<Balance> {
+			Dex::get_price(asset_in, asset_out, fee, amount_in).ok()
+		}
+
+		fn pool_reserves(asset_a: DexAssetKind, asset_b: DexAssetKind, fee: Permill) -> Option<(Balance, Balance)> {
+			Dex::pool_reserves(asset_a, asset_b, fee)
+		}
+
+		fn lp_token_id(asset_a: DexAssetKind, asset_b: DexAssetKind, fee: Permill) -> Option<AssetId> {
+			Dex::lp_token_id(asset_a, asset_b, fee)
+		}
+
+		fn list_pools() -> Vec<(DexAssetKind, DexAssetKind, Permill, AssetId)> {
+			Dex::list_pools()
+		}
+	}
+
 	impl pallet_transaction_payment_rpc_runtime_api::TransactionPaymentCallApi<Block, Balance, RuntimeCall>
 		for Runtime
 	{